@@ -0,0 +1,118 @@
+use std::borrow::Cow;
+
+use regex::{Regex, RegexBuilder};
+
+/// A test on the value of an attribute, for
+/// [`Parser::parse_tags_matching`](crate::Parser::parse_tags_matching) and friends
+///
+/// The string operators compare the attribute value after character references
+/// are decoded (unless decoding is turned off), and mirror the operators of CSS
+/// attribute selectors: `Prefix` is `^=`, `Suffix` is `$=`, `Contains` is `*=`,
+/// `Word` is `~=` and `DashPrefix` is `|=`.
+///
+/// # Examples
+///
+/// ```
+/// use tagparser::AttrMatch;
+///
+/// assert!(AttrMatch::Prefix("https://".to_string()).matches("https://example.com"));
+/// assert!(AttrMatch::Word("primary".to_string()).matches("btn primary"));
+/// assert!(AttrMatch::DashPrefix("en".to_string()).matches("en-US"));
+/// assert!(AttrMatch::regex(r"\.(png|jpe?g)$").unwrap().matches("/logo.png"));
+///
+/// let suffix = AttrMatch::Suffix(".PNG".to_string()).ignore_case();
+/// assert!(suffix.matches("/logo.png"));
+/// ```
+#[derive(Debug, Clone)]
+pub enum AttrMatch {
+    /// The attribute is present, with any value
+    Exists,
+    /// The value is exactly this string
+    Equals(String),
+    /// The value starts with this string
+    Prefix(String),
+    /// The value ends with this string
+    Suffix(String),
+    /// The value contains this string
+    Contains(String),
+    /// One of the whitespace-separated words of the value is this string, like
+    /// a class in `class="btn primary"`
+    Word(String),
+    /// The value is this string or starts with it followed by `-`, like `en`
+    /// for `lang="en-GB"`
+    DashPrefix(String),
+    /// The regular expression matches somewhere in the value
+    Regex(Regex),
+    /// The inner test, with the value and the string it's compared with both
+    /// lowercased
+    ///
+    /// A regular expression is matched against the value as it is; use
+    /// [`AttrMatch::ignore_case`], which makes the expression itself ignore
+    /// case, or put `(?i)` in the pattern.
+    IgnoreCase(Box<AttrMatch>),
+}
+
+impl AttrMatch {
+    /// A [`Regex`](AttrMatch::Regex) test from a pattern in the syntax of the
+    /// [`regex`] crate
+    ///
+    /// # Errors
+    ///
+    /// Returns the error from the `regex` crate if the pattern is invalid.
+    pub fn regex(pattern: &str) -> Result<AttrMatch, regex::Error> {
+        Regex::new(pattern).map(AttrMatch::Regex)
+    }
+
+    /// The same test, ignoring case
+    pub fn ignore_case(self) -> AttrMatch {
+        match self {
+            AttrMatch::Regex(regex) => {
+                let regex = RegexBuilder::new(regex.as_str())
+                    .case_insensitive(true)
+                    .build()
+                    .expect("pattern compiled before");
+                AttrMatch::Regex(regex)
+            }
+            AttrMatch::Exists | AttrMatch::IgnoreCase(_) => self,
+            _ => AttrMatch::IgnoreCase(Box::new(self)),
+        }
+    }
+
+    /// Checks an attribute value against the test
+    pub fn matches(&self, value: &str) -> bool {
+        self.test(value, None)
+    }
+
+    /// Checks a value, comparing its lowercased form when `folded` is given
+    /// and lowercasing the strings it's compared with in the same way;
+    /// regular expressions always see the value as it is
+    fn test(&self, value: &str, folded: Option<&str>) -> bool {
+        let fold = |expected| lowercase_if(expected, folded.is_some());
+        let compared = folded.unwrap_or(value);
+        match self {
+            AttrMatch::Exists => true,
+            AttrMatch::Equals(expected) => compared == fold(expected),
+            AttrMatch::Prefix(expected) => compared.starts_with(&*fold(expected)),
+            AttrMatch::Suffix(expected) => compared.ends_with(&*fold(expected)),
+            AttrMatch::Contains(expected) => compared.contains(&*fold(expected)),
+            AttrMatch::Word(expected) => {
+                let expected = fold(expected);
+                compared.split_ascii_whitespace().any(|word| word == expected)
+            }
+            AttrMatch::DashPrefix(expected) => compared
+                .strip_prefix(&*fold(expected))
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('-')),
+            AttrMatch::Regex(regex) => regex.is_match(value),
+            AttrMatch::IgnoreCase(inner) if folded.is_some() => inner.test(value, folded),
+            AttrMatch::IgnoreCase(inner) => inner.test(value, Some(&value.to_lowercase())),
+        }
+    }
+}
+
+fn lowercase_if(text: &str, lowercase: bool) -> Cow<'_, str> {
+    if lowercase {
+        Cow::Owned(text.to_lowercase())
+    } else {
+        Cow::Borrowed(text)
+    }
+}
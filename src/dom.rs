@@ -0,0 +1,671 @@
+use std::borrow::Cow;
+use std::fmt;
+
+use crate::entities::{decode_attribute, decode_text};
+use crate::parser::diagnostics::{ParseDiagnostic, RawDiagnostic};
+use crate::parser::tokenizer::Span;
+use crate::parser::tree_builder::{self, has_optional_end_tag, is_void_element};
+use crate::selector::{Selector, SelectorError};
+use crate::text;
+use crate::xpath::{Value, XPath, XPathError};
+
+/// Identifies a node inside a [`Document`]
+///
+/// Ids are only meaningful for the document that handed them out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NodeId(pub(crate) usize);
+
+impl NodeId {
+    /// Position of the node in the document's node list
+    pub fn index(&self) -> usize {
+        self.0
+    }
+}
+
+/// The kind of a [`Node`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeKind {
+    /// The root of the tree; it has no source of its own
+    Document,
+    /// An element such as `<p>...</p>`
+    Element,
+    /// Character data between tags
+    Text,
+    /// A `<!-- ... -->` comment
+    Comment,
+    /// A `<!DOCTYPE ...>` declaration
+    Doctype,
+}
+
+/// A point in the source, as a byte offset and a 1-based line and column
+///
+/// Columns count characters, not bytes, from the start of the line. Lines are
+/// separated by `\n`, so a `\r\n` line ending counts as one break.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Position {
+    pub offset: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+/// A range of the source with the line and column of both ends
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Location {
+    /// Where the range starts
+    pub start: Position,
+    /// Just past the end of the range
+    pub end: Position,
+}
+
+impl Location {
+    /// The byte range covered
+    pub fn span(&self) -> Span {
+        Span::new(self.start.offset, self.end.offset)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct ElementData {
+    pub(crate) name: Span,
+    pub(crate) attributes: Vec<(Span, Span)>,
+    pub(crate) start_tag: Span,
+    pub(crate) end_tag: Option<Span>,
+    pub(crate) self_closing: bool,
+    /// Everything between the start tag and the point where the element ends
+    pub(crate) content: Span,
+    /// A copy of a formatting element reopened after misnested tags closed it,
+    /// with an empty start tag where it was reopened
+    pub(crate) reopened: bool,
+    /// Closed before its end tag by misnested tags, with the end tag found
+    /// later, after the element was reopened
+    pub(crate) misnested_end_tag: bool,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum NodeValue {
+    Document,
+    Element(ElementData),
+    /// Character data; `raw` text, from inside `<script>`, `<style>` and the
+    /// like, is never decoded
+    Text { raw: bool },
+    /// The comment text without its delimiters
+    Comment(Span),
+    /// The doctype text after the `DOCTYPE` keyword
+    Doctype(Span),
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct NodeData {
+    pub(crate) value: NodeValue,
+    /// Source range covered by the node, including its tags
+    pub(crate) span: Span,
+    pub(crate) parent: Option<NodeId>,
+    pub(crate) first_child: Option<NodeId>,
+    pub(crate) last_child: Option<NodeId>,
+    pub(crate) prev_sibling: Option<NodeId>,
+    pub(crate) next_sibling: Option<NodeId>,
+}
+
+/// A parsed HTML document
+///
+/// The document stores the tree as a flat list of nodes that refer to each
+/// other by [`NodeId`]. Element names, attributes and text are kept as ranges
+/// of the source, so walking the tree never copies them. The source itself is
+/// borrowed when the document is parsed from a `&'a str` and owned when it is
+/// parsed from a `String`, so parsing never copies the HTML either.
+///
+/// # Examples
+///
+/// ```
+/// use tagparser::Document;
+///
+/// let document = Document::parse("<ul><li>One</li><li>Two</li></ul>");
+/// let list = document.root().first_child().unwrap();
+///
+/// assert_eq!(list.name(), Some("ul"));
+/// let items: Vec<String> = list.children().map(|item| item.text()).collect();
+/// assert_eq!(items, vec!["One", "Two"]);
+/// ```
+pub struct Document<'a> {
+    source: Cow<'a, str>,
+    nodes: Vec<NodeData>,
+    /// Byte offset at which each line starts
+    line_starts: Vec<usize>,
+    diagnostics: Vec<ParseDiagnostic>,
+}
+
+impl<'a> Document<'a> {
+    /// Parses `html` into a tree that borrows it
+    pub fn parse(html: &'a str) -> Document<'a> {
+        tree_builder::build(Cow::Borrowed(html))
+    }
+
+    /// Parses `html` into a tree, borrowing or taking ownership of it
+    pub(crate) fn from_source(html: Cow<'a, str>) -> Document<'a> {
+        tree_builder::build(html)
+    }
+
+    pub(crate) fn from_nodes(source: Cow<'a, str>, nodes: Vec<NodeData>, diagnostics: Vec<RawDiagnostic>) -> Document<'a> {
+        let line_starts = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(index, _)| index + 1))
+            .collect();
+        let mut document = Document { source, nodes, line_starts, diagnostics: Vec::new() };
+        document.diagnostics = diagnostics
+            .into_iter()
+            .map(|diagnostic| ParseDiagnostic {
+                kind: diagnostic.kind,
+                message: diagnostic.message,
+                span: diagnostic.span,
+                location: document.location(diagnostic.span),
+            })
+            .collect();
+        document
+    }
+
+    pub(crate) fn data(&self, id: NodeId) -> &NodeData {
+        &self.nodes[id.0]
+    }
+
+    /// The HTML the document was parsed from
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// The problems the parser recovered from, in source order
+    ///
+    /// An empty list means the markup parsed cleanly. See [`ParseDiagnostic`]
+    /// for an example.
+    pub fn diagnostics(&self) -> &[ParseDiagnostic] {
+        &self.diagnostics
+    }
+
+    pub(crate) fn slice(&self, span: Span) -> &str {
+        &self.source[span.start..span.end]
+    }
+
+    /// Returns the line and column of a byte offset in the source
+    ///
+    /// # Panics
+    ///
+    /// Panics if `offset` is past the end of the source or not on a character boundary.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tagparser::Document;
+    ///
+    /// let document = Document::parse("<ul>\n  <li>One</li>\n</ul>");
+    /// let position = document.position(7);
+    /// assert_eq!((position.line, position.column), (2, 3));
+    /// ```
+    pub fn position(&self, offset: usize) -> Position {
+        let line = self.line_starts.partition_point(|start| *start <= offset) - 1;
+        let line_start = self.line_starts[line];
+        let column = self.source[line_start..offset].chars().count() + 1;
+        Position { offset, line: line + 1, column }
+    }
+
+    /// Returns the line and column of both ends of a span
+    pub fn location(&self, span: Span) -> Location {
+        Location { start: self.position(span.start), end: self.position(span.end) }
+    }
+
+    /// The document node at the top of the tree
+    pub fn root(&self) -> Node<'_> {
+        self.node(NodeId(0))
+    }
+
+    /// Returns the node with the given id
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` does not belong to this document.
+    pub fn node(&self, id: NodeId) -> Node<'_> {
+        assert!(id.0 < self.nodes.len(), "node id out of range");
+        Node { document: self, id }
+    }
+
+    /// Number of nodes in the document, including the root
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Returns `true` if the document has no nodes besides the root
+    pub fn is_empty(&self) -> bool {
+        self.nodes.len() == 1
+    }
+
+    /// All element nodes in document order
+    pub fn elements(&self) -> impl Iterator<Item = Node<'_>> {
+        self.root().descendants().filter(|node| node.is_element())
+    }
+
+    /// Returns every element matching the CSS `selector`, in document order
+    ///
+    /// See [`Selector`] for the supported syntax.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tagparser::Document;
+    ///
+    /// let document = Document::parse("<div class='article'><h2><a href='https://a'>A</a></h2></div>");
+    /// let links = document.select("div.article > h2 a[href^='https']").unwrap();
+    /// assert_eq!(links[0].text(), "A");
+    /// ```
+    pub fn select(&self, selector: &str) -> Result<Vec<Node<'_>>, SelectorError> {
+        self.root().select(selector)
+    }
+
+    /// Evaluates an XPath 1.0 expression with the document root as the context node
+    ///
+    /// See [`XPath`] for the supported syntax.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tagparser::Document;
+    ///
+    /// let document = Document::parse("<ul><li>One</li><li>Two</li></ul>");
+    /// let count = document.xpath("count(//li)").unwrap();
+    /// assert_eq!(count.to_number(), 2.0);
+    /// ```
+    pub fn xpath(&self, expression: &str) -> Result<Value<'_>, XPathError> {
+        self.root().xpath(expression)
+    }
+}
+
+impl fmt::Debug for Document<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Document").field("nodes", &self.nodes.len()).finish()
+    }
+}
+
+/// A reference to one node of a [`Document`]
+///
+/// `Node` is a cheap copyable handle; all navigation methods return new
+/// handles into the same document.
+#[derive(Clone, Copy)]
+pub struct Node<'a> {
+    document: &'a Document<'a>,
+    id: NodeId,
+}
+
+impl<'a> Node<'a> {
+    fn data(&self) -> &'a NodeData {
+        self.document.data(self.id)
+    }
+
+    fn element(&self) -> Option<&'a ElementData> {
+        match &self.data().value {
+            NodeValue::Element(element) => Some(element),
+            _ => None,
+        }
+    }
+
+    fn wrap(&self, id: Option<NodeId>) -> Option<Node<'a>> {
+        id.map(|id| Node { document: self.document, id })
+    }
+
+    pub fn id(&self) -> NodeId {
+        self.id
+    }
+
+    /// The document this node belongs to
+    pub fn document(&self) -> &'a Document<'a> {
+        self.document
+    }
+
+    pub fn kind(&self) -> NodeKind {
+        match self.data().value {
+            NodeValue::Document => NodeKind::Document,
+            NodeValue::Element(_) => NodeKind::Element,
+            NodeValue::Text { .. } => NodeKind::Text,
+            NodeValue::Comment(_) => NodeKind::Comment,
+            NodeValue::Doctype(_) => NodeKind::Doctype,
+        }
+    }
+
+    pub fn is_element(&self) -> bool {
+        self.kind() == NodeKind::Element
+    }
+
+    pub fn is_text(&self) -> bool {
+        self.kind() == NodeKind::Text
+    }
+
+    pub fn is_comment(&self) -> bool {
+        self.kind() == NodeKind::Comment
+    }
+
+    /// Tag name of an element, exactly as written in the source
+    pub fn name(&self) -> Option<&'a str> {
+        self.element().map(|element| self.document.slice(element.name))
+    }
+
+    /// Value of the attribute called `name`, if this is an element that has one
+    ///
+    /// Attribute names are compared ASCII case-insensitively, as in HTML.
+    /// Character references in the value are decoded; see [`Node::raw_attribute`]
+    /// for the value as written in the source.
+    pub fn attribute(&self, name: &str) -> Option<Cow<'a, str>> {
+        self.raw_attribute(name).map(decode_attribute)
+    }
+
+    /// Value of the attribute called `name` exactly as written, without decoding
+    pub fn raw_attribute(&self, name: &str) -> Option<&'a str> {
+        self.raw_attributes()
+            .find(|(attr_name, _)| attr_name.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value)
+    }
+
+    /// All attributes of an element as `(name, value)` pairs in source order,
+    /// with character references in the values decoded
+    pub fn attributes(&self) -> impl Iterator<Item = (&'a str, Cow<'a, str>)> {
+        self.raw_attributes().map(|(name, value)| (name, decode_attribute(value)))
+    }
+
+    /// All attributes of an element as `(name, value)` pairs exactly as written
+    pub fn raw_attributes(&self) -> impl Iterator<Item = (&'a str, &'a str)> {
+        let document = self.document;
+        self.element()
+            .into_iter()
+            .flat_map(|element| element.attributes.iter())
+            .map(move |(name, value)| (document.slice(*name), document.slice(*value)))
+    }
+
+    /// The source range covered by this node, including its start and end tags
+    pub fn span(&self) -> Span {
+        self.data().span
+    }
+
+    /// The source range of an element's start tag
+    pub fn start_tag_span(&self) -> Option<Span> {
+        self.element().map(|element| element.start_tag)
+    }
+
+    /// The source range of an element's end tag, if it has one
+    pub fn end_tag_span(&self) -> Option<Span> {
+        self.element().and_then(|element| element.end_tag)
+    }
+
+    /// Line and column of the whole node in the source
+    pub fn location(&self) -> Location {
+        self.document.location(self.span())
+    }
+
+    /// Line and column of an element's start tag
+    pub fn start_tag_location(&self) -> Option<Location> {
+        self.start_tag_span().map(|span| self.document.location(span))
+    }
+
+    /// Line and column of an element's end tag, if it has one
+    pub fn end_tag_location(&self) -> Option<Location> {
+        self.end_tag_span().map(|span| self.document.location(span))
+    }
+
+    /// Returns `true` for elements written as `<tag/>`
+    pub fn is_self_closing(&self) -> bool {
+        self.element().is_some_and(|element| element.self_closing)
+    }
+
+    /// Returns `true` for void elements such as `<br>` and `<img>`, which never
+    /// have content or an end tag
+    pub fn is_void(&self) -> bool {
+        self.name().is_some_and(is_void_element)
+    }
+
+    /// Returns `true` if the element ended the way the HTML standard allows:
+    /// with its own end tag, as a void element or `<tag/>`, or, for elements
+    /// such as `<li>` and `<p>` whose end tag is optional, wherever it was implied
+    ///
+    /// A formatting element closed early by misnested tags, such as the `<i>`
+    /// in `<b><i>x</b>y</i>`, counts as complete when its end tag comes later.
+    /// The copies of it reopened around the following content never do, since
+    /// they have no start tag in the source.
+    pub(crate) fn is_complete(&self) -> bool {
+        self.element().is_some_and(|element| {
+            let name = self.document.slice(element.name);
+            !element.reopened
+                && (element.self_closing
+                    || element.end_tag.is_some()
+                    || element.misnested_end_tag
+                    || is_void_element(name)
+                    || has_optional_end_tag(name))
+        })
+    }
+
+    /// The source of this node: the whole element including its tags, or the
+    /// raw text of a text or comment node
+    pub fn outer_html(&self) -> &'a str {
+        self.document.slice(self.span())
+    }
+
+    /// The source between an element's start and end tags
+    ///
+    /// For anything that isn't an element this is the same as [`Node::outer_html`].
+    pub fn inner_html(&self) -> &'a str {
+        match self.element() {
+            Some(element) => self.document.slice(element.content),
+            None => self.outer_html(),
+        }
+    }
+
+    /// Text of a text node, with character references decoded
+    ///
+    /// The content of raw-text elements such as `<script>` and `<style>` is
+    /// returned as written, since references mean nothing there.
+    pub fn as_text(&self) -> Option<Cow<'a, str>> {
+        match self.data().value {
+            NodeValue::Text { raw: true } => self.as_raw_text().map(Cow::Borrowed),
+            _ => self.as_raw_text().map(decode_text),
+        }
+    }
+
+    /// Text of a text node exactly as written in the source
+    pub fn as_raw_text(&self) -> Option<&'a str> {
+        match self.data().value {
+            NodeValue::Text { .. } => Some(self.outer_html()),
+            _ => None,
+        }
+    }
+
+    /// Text of a comment node, without the `<!--` and `-->` delimiters
+    pub fn as_comment(&self) -> Option<&'a str> {
+        match self.data().value {
+            NodeValue::Comment(text) => Some(self.document.slice(text)),
+            _ => None,
+        }
+    }
+
+    /// Text of a doctype node after the `DOCTYPE` keyword, such as `html`
+    pub fn as_doctype(&self) -> Option<&'a str> {
+        match self.data().value {
+            NodeValue::Doctype(text) => Some(self.document.slice(text)),
+            _ => None,
+        }
+    }
+
+    /// The concatenated text of this node and all of its descendants, with
+    /// character references decoded
+    pub fn text(&self) -> String {
+        if let Some(text) = self.as_text() {
+            return text.into_owned();
+        }
+        self.descendants().filter_map(|node| node.as_text()).collect()
+    }
+
+    /// The text of this node as a browser would render it
+    ///
+    /// Nested markup is stripped, `<script>` and `<style>` are skipped, character
+    /// references are decoded and whitespace is collapsed, with block elements
+    /// and `<br>` on lines of their own. Use [`Node::text`] for the plain
+    /// concatenation of all descendant text.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tagparser::Document;
+    ///
+    /// let document = Document::parse("<div><h1>Title</h1>  Some <b>bold</b>\n  text<style>b{}</style></div>");
+    /// let div = document.root().first_child().unwrap();
+    /// assert_eq!(div.text_content(), "Title\nSome bold text");
+    /// ```
+    pub fn text_content(&self) -> String {
+        text::text_content(self, true)
+    }
+
+    /// The concatenated text of this node and all of its descendants, exactly as written
+    pub fn raw_text(&self) -> String {
+        if let Some(text) = self.as_raw_text() {
+            return text.to_string();
+        }
+        self.descendants().filter_map(|node| node.as_raw_text()).collect()
+    }
+
+    pub fn parent(&self) -> Option<Node<'a>> {
+        self.wrap(self.data().parent)
+    }
+
+    pub fn first_child(&self) -> Option<Node<'a>> {
+        self.wrap(self.data().first_child)
+    }
+
+    pub fn last_child(&self) -> Option<Node<'a>> {
+        self.wrap(self.data().last_child)
+    }
+
+    pub fn next_sibling(&self) -> Option<Node<'a>> {
+        self.wrap(self.data().next_sibling)
+    }
+
+    pub fn prev_sibling(&self) -> Option<Node<'a>> {
+        self.wrap(self.data().prev_sibling)
+    }
+
+    /// The direct children of this node, in document order
+    pub fn children(&self) -> Siblings<'a> {
+        Siblings { next: self.first_child(), forward: true }
+    }
+
+    /// The siblings that come after this node, nearest first
+    pub fn next_siblings(&self) -> Siblings<'a> {
+        Siblings { next: self.next_sibling(), forward: true }
+    }
+
+    /// The siblings that come before this node, nearest first
+    pub fn prev_siblings(&self) -> Siblings<'a> {
+        Siblings { next: self.prev_sibling(), forward: false }
+    }
+
+    /// The parent, grandparent and so on up to the document node
+    pub fn ancestors(&self) -> Ancestors<'a> {
+        Ancestors { next: self.parent() }
+    }
+
+    /// Every node below this one, in document order
+    pub fn descendants(&self) -> Descendants<'a> {
+        Descendants { root: *self, next: self.first_child() }
+    }
+
+    /// Returns every element below this node that matches the CSS `selector`
+    ///
+    /// The selector is matched against the whole document, so `div p` also finds
+    /// `<p>` elements below this node whose `<div>` ancestor is outside of it.
+    pub fn select(&self, selector: &str) -> Result<Vec<Node<'a>>, SelectorError> {
+        let selector = Selector::parse(selector)?;
+        Ok(self.descendants().filter(|node| selector.matches(node)).collect())
+    }
+
+    /// Evaluates an XPath 1.0 expression with this node as the context node
+    pub fn xpath(&self, expression: &str) -> Result<Value<'a>, XPathError> {
+        XPath::parse(expression)?.evaluate(*self)
+    }
+}
+
+impl PartialEq for Node<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self.document, other.document) && self.id == other.id
+    }
+}
+
+impl Eq for Node<'_> {}
+
+impl fmt::Debug for Node<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind() {
+            NodeKind::Element => write!(f, "Element({:?}, {:?})", self.name().unwrap_or_default(), self.id),
+            kind => write!(f, "{:?}({:?})", kind, self.id),
+        }
+    }
+}
+
+/// Iterator over a run of sibling nodes
+#[derive(Debug, Clone)]
+pub struct Siblings<'a> {
+    next: Option<Node<'a>>,
+    forward: bool,
+}
+
+impl<'a> Iterator for Siblings<'a> {
+    type Item = Node<'a>;
+
+    fn next(&mut self) -> Option<Node<'a>> {
+        let node = self.next?;
+        self.next = if self.forward { node.next_sibling() } else { node.prev_sibling() };
+        Some(node)
+    }
+}
+
+/// Iterator over the ancestors of a node, nearest first
+#[derive(Debug, Clone)]
+pub struct Ancestors<'a> {
+    next: Option<Node<'a>>,
+}
+
+impl<'a> Iterator for Ancestors<'a> {
+    type Item = Node<'a>;
+
+    fn next(&mut self) -> Option<Node<'a>> {
+        let node = self.next?;
+        self.next = node.parent();
+        Some(node)
+    }
+}
+
+/// Pre-order iterator over the descendants of a node
+#[derive(Debug, Clone)]
+pub struct Descendants<'a> {
+    root: Node<'a>,
+    next: Option<Node<'a>>,
+}
+
+impl<'a> Iterator for Descendants<'a> {
+    type Item = Node<'a>;
+
+    fn next(&mut self) -> Option<Node<'a>> {
+        let node = self.next?;
+
+        self.next = node.first_child().or_else(|| {
+            // Climb until an ancestor below the root has a following sibling
+            let mut current = node;
+            loop {
+                if current == self.root {
+                    return None;
+                }
+                if let Some(sibling) = current.next_sibling() {
+                    return Some(sibling);
+                }
+                current = current.parent()?;
+            }
+        });
+
+        Some(node)
+    }
+}
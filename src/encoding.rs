@@ -0,0 +1,351 @@
+//! Detection of the character encoding of HTML bytes, and transcoding to UTF-8
+//!
+//! Detection follows the encoding sniffing algorithm of the HTML standard: a
+//! byte order mark wins, then an encoding given by the caller (typically the
+//! `charset` of an HTTP `Content-Type` header), then a `<meta charset>` or
+//! `<meta http-equiv="Content-Type">` declaration in the first 1024 bytes.
+//! Pages that declare nothing are read as UTF-8 if they are valid UTF-8, and as
+//! windows-1252 otherwise. Encoding labels are the ones browsers accept, so
+//! `latin1`, `cp1251` and `sjis` all work.
+
+use std::borrow::Cow;
+
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8, WINDOWS_1252, X_USER_DEFINED};
+
+/// How many bytes are searched for a `<meta>` declaration
+pub(crate) const PRESCAN_LENGTH: usize = 1024;
+
+/// The encoding that HTML bytes were read in, and what it was picked by
+///
+/// # Examples
+///
+/// ```
+/// use tagparser::encoding::{sniff, EncodingSource};
+///
+/// let html = b"<meta charset='windows-1251'><p>\xcf\xf0\xe8\xe2\xe5\xf2</p>";
+/// let detected = sniff(html, None);
+///
+/// assert_eq!(detected.name, "windows-1251");
+/// assert_eq!(detected.source, EncodingSource::Meta);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DetectedEncoding {
+    /// The name of the encoding, such as `"UTF-8"`, `"windows-1251"` or `"Shift_JIS"`
+    pub name: &'static str,
+    /// What the encoding was picked by
+    pub source: EncodingSource,
+    encoding: &'static Encoding,
+}
+
+/// What picked the encoding of a document
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EncodingSource {
+    /// A UTF-8 or UTF-16 byte order mark at the start of the input
+    ByteOrderMark,
+    /// The encoding given by the caller
+    Hint,
+    /// A `<meta charset>` or `<meta http-equiv="Content-Type">` element
+    Meta,
+    /// Nothing was declared; the input is UTF-8 if it's valid UTF-8, and
+    /// windows-1252 otherwise
+    Default,
+}
+
+impl DetectedEncoding {
+    fn new(encoding: &'static Encoding, source: EncodingSource) -> Self {
+        DetectedEncoding { name: encoding.name(), source, encoding }
+    }
+
+    /// Transcodes `bytes` to UTF-8
+    ///
+    /// A byte order mark is left out, and malformed byte sequences are
+    /// replaced with U+FFFD. The text is only copied when it isn't already
+    /// valid UTF-8.
+    pub fn decode<'a>(&self, bytes: &'a [u8]) -> Cow<'a, str> {
+        let (text, _) = match self.source {
+            EncodingSource::ByteOrderMark => self.encoding.decode_with_bom_removal(bytes),
+            _ => self.encoding.decode_without_bom_handling(bytes),
+        };
+        text
+    }
+
+    /// A decoder for input that arrives in pieces
+    pub(crate) fn new_decoder(&self) -> encoding_rs::Decoder {
+        match self.source {
+            EncodingSource::ByteOrderMark => self.encoding.new_decoder_with_bom_removal(),
+            _ => self.encoding.new_decoder_without_bom_handling(),
+        }
+    }
+}
+
+/// Works out the encoding of HTML bytes
+///
+/// `bytes` is the document, or at least its start; only the first 1024 bytes
+/// are searched for a declaration. `hint` is an encoding label the caller
+/// knows from elsewhere, such as an HTTP header. It overrides `<meta>`
+/// declarations but not a byte order mark, and is ignored if it isn't a known
+/// label.
+///
+/// # Arguments
+///
+/// * `bytes` - The start of the HTML document
+/// * `hint` - An optional encoding label, like `"latin1"` or `"Shift_JIS"`
+///
+/// # Returns
+///
+/// The encoding to read the document in and what picked it
+///
+/// # Examples
+///
+/// ```
+/// use tagparser::encoding::{sniff, EncodingSource};
+///
+/// // A byte order mark beats everything else
+/// let detected = sniff(b"\xef\xbb\xbf<meta charset='latin1'>", Some("Shift_JIS"));
+/// assert_eq!((detected.name, detected.source), ("UTF-8", EncodingSource::ByteOrderMark));
+///
+/// let detected = sniff(b"<meta http-equiv='Content-Type' content='text/html; charset=sjis'>", None);
+/// assert_eq!((detected.name, detected.source), ("Shift_JIS", EncodingSource::Meta));
+///
+/// // Without a declaration, bytes that aren't UTF-8 are read as windows-1252
+/// let detected = sniff(b"<p>Caf\xe9</p>", None);
+/// assert_eq!((detected.name, detected.source), ("windows-1252", EncodingSource::Default));
+/// ```
+pub fn sniff(bytes: &[u8], hint: Option<&str>) -> DetectedEncoding {
+    if let Some((encoding, _)) = Encoding::for_bom(bytes) {
+        return DetectedEncoding::new(encoding, EncodingSource::ByteOrderMark);
+    }
+    if let Some(encoding) = hint.and_then(|label| Encoding::for_label(label.as_bytes())) {
+        return DetectedEncoding::new(encoding, EncodingSource::Hint);
+    }
+    let start = &bytes[..bytes.len().min(PRESCAN_LENGTH)];
+    if let Some(encoding) = prescan(start) {
+        return DetectedEncoding::new(encoding, EncodingSource::Meta);
+    }
+    let encoding = if is_utf8_prefix(start, start.len() < bytes.len()) { UTF_8 } else { WINDOWS_1252 };
+    DetectedEncoding::new(encoding, EncodingSource::Default)
+}
+
+/// Detects the encoding of HTML bytes and transcodes them to UTF-8
+///
+/// See [`sniff`] for how the encoding is picked. The text borrows `bytes` when
+/// they are already valid UTF-8.
+///
+/// # Examples
+///
+/// ```
+/// use tagparser::encoding::decode;
+///
+/// let (text, detected) = decode(b"<p>Caf\xe9</p>", Some("latin1"));
+/// assert_eq!(text, "<p>Café</p>");
+/// assert_eq!(detected.name, "windows-1252");
+/// ```
+pub fn decode<'a>(bytes: &'a [u8], hint: Option<&str>) -> (Cow<'a, str>, DetectedEncoding) {
+    let detected = sniff(bytes, hint);
+    (detected.decode(bytes), detected)
+}
+
+/// Checks that `bytes` are valid UTF-8, except that the last character may be
+/// cut off when the input was `truncated`
+fn is_utf8_prefix(bytes: &[u8], truncated: bool) -> bool {
+    match std::str::from_utf8(bytes) {
+        Ok(_) => true,
+        Err(error) => truncated && error.error_len().is_none(),
+    }
+}
+
+/// Searches the start of a document for a `<meta>` encoding declaration, as the
+/// HTML standard's prescan does
+fn prescan(bytes: &[u8]) -> Option<&'static Encoding> {
+    let mut pos = 0;
+    while pos < bytes.len() {
+        let rest = &bytes[pos..];
+        if rest.starts_with(b"<!--") {
+            // The dashes of `<!--` may also end the comment, as in `<!-->`
+            pos += 2 + find(&rest[2..], b"-->")? + 3;
+            continue;
+        }
+        if starts_with_ignore_case(rest, b"<meta") && rest.get(5).is_some_and(|&b| is_space(b) || b == b'/') {
+            pos += 5;
+            if let Some(encoding) = meta_encoding(bytes, &mut pos)? {
+                return Some(encoding);
+            }
+            continue;
+        }
+        let tag_name = match rest {
+            [b'<', b'/', c, ..] | [b'<', c, ..] if c.is_ascii_alphabetic() => true,
+            [b'<', b'!' | b'/' | b'?', ..] => false,
+            _ => {
+                pos += 1;
+                continue;
+            }
+        };
+        if tag_name {
+            // Skip the name and the attributes of any other tag
+            pos += rest.iter().position(|&b| is_space(b) || b == b'>')?;
+            while get_attribute(bytes, &mut pos)?.is_some() {}
+        } else {
+            pos += rest.iter().position(|&b| b == b'>')?;
+        }
+        pos += 1;
+    }
+    None
+}
+
+/// Reads the attributes of a `<meta>` tag, returning the encoding it declares
+///
+/// Returns `None` when the end of the input cuts the tag off, which ends the
+/// prescan.
+fn meta_encoding(bytes: &[u8], pos: &mut usize) -> Option<Option<&'static Encoding>> {
+    let mut seen = Vec::new();
+    let mut got_pragma = false;
+    let mut need_pragma = None;
+    let mut charset = None;
+
+    while let Some((name, value)) = get_attribute(bytes, pos)? {
+        if seen.contains(&name) {
+            continue;
+        }
+        match name.as_slice() {
+            b"http-equiv" => got_pragma |= value == b"content-type",
+            b"content" if charset.is_none() => {
+                if let Some(encoding) = charset_from_content(&value) {
+                    charset = Some(encoding);
+                    need_pragma = Some(true);
+                }
+            }
+            b"charset" => {
+                charset = Encoding::for_label(&value);
+                need_pragma = Some(false);
+            }
+            _ => {}
+        }
+        seen.push(name);
+    }
+
+    let declared = match need_pragma {
+        Some(true) if !got_pragma => None,
+        Some(_) => charset,
+        None => None,
+    };
+    // A document that could be read as ASCII can't really be UTF-16
+    Some(declared.map(|encoding| match encoding {
+        e if e == UTF_16BE || e == UTF_16LE => UTF_8,
+        e if e == X_USER_DEFINED => WINDOWS_1252,
+        e => e,
+    }))
+}
+
+/// Reads the next attribute of a tag, lowercased, as the prescan does
+///
+/// Returns `Some(None)` at the end of the tag, and `None` when the end of the
+/// input comes first.
+fn get_attribute(bytes: &[u8], pos: &mut usize) -> Option<Option<(Vec<u8>, Vec<u8>)>> {
+    while is_space(*bytes.get(*pos)?) || bytes[*pos] == b'/' {
+        *pos += 1;
+    }
+    if bytes[*pos] == b'>' {
+        return Some(None);
+    }
+
+    let mut name = Vec::new();
+    loop {
+        let byte = *bytes.get(*pos)?;
+        match byte {
+            b'=' if !name.is_empty() => break,
+            _ if is_space(byte) => {
+                while is_space(*bytes.get(*pos)?) {
+                    *pos += 1;
+                }
+                if bytes[*pos] != b'=' {
+                    return Some(Some((name, Vec::new())));
+                }
+                break;
+            }
+            b'/' | b'>' => return Some(Some((name, Vec::new()))),
+            _ => name.push(byte.to_ascii_lowercase()),
+        }
+        *pos += 1;
+    }
+    // Step over the `=`
+    *pos += 1;
+
+    while is_space(*bytes.get(*pos)?) {
+        *pos += 1;
+    }
+    let mut value = Vec::new();
+    match bytes[*pos] {
+        quote @ (b'"' | b'\'') => {
+            *pos += 1;
+            loop {
+                let byte = *bytes.get(*pos)?;
+                *pos += 1;
+                if byte == quote {
+                    return Some(Some((name, value)));
+                }
+                value.push(byte.to_ascii_lowercase());
+            }
+        }
+        b'>' => return Some(Some((name, value))),
+        _ => {}
+    }
+    loop {
+        let byte = *bytes.get(*pos)?;
+        if is_space(byte) || byte == b'>' {
+            return Some(Some((name, value)));
+        }
+        value.push(byte.to_ascii_lowercase());
+        *pos += 1;
+    }
+}
+
+/// Finds the encoding in a `content` attribute like `text/html; charset=utf-8`
+fn charset_from_content(content: &[u8]) -> Option<&'static Encoding> {
+    charset_label(content).and_then(Encoding::for_label)
+}
+
+/// Finds the label after `charset=` in a `content` attribute, without quotes
+pub(crate) fn charset_label(content: &[u8]) -> Option<&[u8]> {
+    let mut pos = 0;
+    loop {
+        pos += find_ignore_case(&content[pos..], b"charset")? + b"charset".len();
+        while content.get(pos).is_some_and(|&b| is_space(b)) {
+            pos += 1;
+        }
+        if content.get(pos) != Some(&b'=') {
+            continue;
+        }
+        pos += 1;
+        while content.get(pos).is_some_and(|&b| is_space(b)) {
+            pos += 1;
+        }
+        let rest = &content[pos..];
+        let label = match rest.first()? {
+            quote @ (b'"' | b'\'') => {
+                let end = rest[1..].iter().position(|b| b == quote)?;
+                &rest[1..1 + end]
+            }
+            _ => {
+                let end = rest.iter().position(|&b| is_space(b) || b == b';').unwrap_or(rest.len());
+                &rest[..end]
+            }
+        };
+        return Some(label);
+    }
+}
+
+fn is_space(byte: u8) -> bool {
+    matches!(byte, b'\t' | b'\n' | b'\x0C' | b'\r' | b' ')
+}
+
+fn starts_with_ignore_case(bytes: &[u8], prefix: &[u8]) -> bool {
+    bytes.len() >= prefix.len() && bytes[..prefix.len()].eq_ignore_ascii_case(prefix)
+}
+
+fn find(bytes: &[u8], needle: &[u8]) -> Option<usize> {
+    bytes.windows(needle.len()).position(|window| window == needle)
+}
+
+fn find_ignore_case(bytes: &[u8], needle: &[u8]) -> Option<usize> {
+    bytes.windows(needle.len()).position(|window| window.eq_ignore_ascii_case(needle))
+}
@@ -0,0 +1,180 @@
+//! Decoding of HTML character references such as `&amp;`, `&#39;` and `&#xE9;`
+//!
+//! Decoding follows the HTML standard: every named reference in the HTML5
+//! table is recognised, the legacy names such as `&amp` and `&copy` are also
+//! decoded without their trailing semicolon, and numeric references that don't
+//! name a usable character are replaced the way browsers replace them.
+
+use std::borrow::Cow;
+
+mod table;
+
+use table::ENTITIES;
+
+/// Length of the longest name in the entity table, including its `;`
+const LONGEST_NAME: usize = 32;
+
+/// Decodes the character references in text content
+///
+/// Returns the input unchanged (and unallocated) if it holds no references.
+///
+/// # Examples
+///
+/// ```
+/// use tagparser::entities::decode_text;
+///
+/// assert_eq!(decode_text("Caf&eacute; &amp; bar &#8212; &#x1F980;"), "Café & bar — 🦀");
+/// // Legacy names work without a semicolon, unknown names are left alone
+/// assert_eq!(decode_text("&copy 2024 &bogus;"), "© 2024 &bogus;");
+/// ```
+pub fn decode_text(text: &str) -> Cow<'_, str> {
+    decode(text, false)
+}
+
+/// Decodes the character references in an attribute value
+///
+/// This differs from [`decode_text`] in one way that matters for URLs: a
+/// legacy reference without its semicolon is left alone when it is followed by
+/// a letter, digit or `=`, so `?a=1&copy=2` keeps its query parameter.
+///
+/// # Examples
+///
+/// ```
+/// use tagparser::entities::decode_attribute;
+///
+/// assert_eq!(decode_attribute("/search?q=rust&amp;page=2"), "/search?q=rust&page=2");
+/// assert_eq!(decode_attribute("/search?q=rust&copy=2"), "/search?q=rust&copy=2");
+/// ```
+pub fn decode_attribute(value: &str) -> Cow<'_, str> {
+    decode(value, true)
+}
+
+fn decode(input: &str, in_attribute: bool) -> Cow<'_, str> {
+    let Some(first) = input.find('&') else {
+        return Cow::Borrowed(input);
+    };
+
+    let mut output = String::with_capacity(input.len());
+    output.push_str(&input[..first]);
+    let mut pos = first;
+
+    while pos < input.len() {
+        let rest = &input[pos..];
+        if !rest.starts_with('&') {
+            let next = rest.find('&').unwrap_or(rest.len());
+            output.push_str(&rest[..next]);
+            pos += next;
+            continue;
+        }
+
+        match reference(&rest[1..], in_attribute) {
+            Some((replacement, len)) => {
+                match replacement {
+                    Replacement::Named(text) => output.push_str(text),
+                    Replacement::Numeric(c) => output.push(c),
+                }
+                pos += 1 + len;
+            }
+            None => {
+                output.push('&');
+                pos += 1;
+            }
+        }
+    }
+
+    if output == input {
+        Cow::Borrowed(input)
+    } else {
+        Cow::Owned(output)
+    }
+}
+
+/// What a reference decodes to
+enum Replacement {
+    /// A named reference, which may stand for one or two characters
+    Named(&'static str),
+    Numeric(char),
+}
+
+/// Reads the reference after a `&`, returning its replacement and how many
+/// bytes of `rest` it used, or `None` if `rest` doesn't start a reference
+fn reference(rest: &str, in_attribute: bool) -> Option<(Replacement, usize)> {
+    if let Some(numeric) = rest.strip_prefix('#') {
+        let (c, len) = numeric_reference(numeric)?;
+        return Some((Replacement::Numeric(c), 1 + len));
+    }
+
+    let alphanumeric = rest
+        .bytes()
+        .take(LONGEST_NAME)
+        .take_while(u8::is_ascii_alphanumeric)
+        .count();
+    if alphanumeric == 0 {
+        return None;
+    }
+    let candidate_len = if rest[alphanumeric..].starts_with(';') { alphanumeric + 1 } else { alphanumeric };
+
+    // The longest name in the table that the input starts with wins
+    let (len, decoded) = (1..=candidate_len.min(LONGEST_NAME))
+        .rev()
+        .find_map(|len| lookup(&rest[..len]).map(|decoded| (len, decoded)))?;
+
+    if in_attribute && !rest[..len].ends_with(';') {
+        let next = rest.as_bytes().get(len);
+        if next.is_some_and(|b| b.is_ascii_alphanumeric() || *b == b'=') {
+            return None;
+        }
+    }
+    Some((Replacement::Named(decoded), len))
+}
+
+fn lookup(name: &str) -> Option<&'static str> {
+    ENTITIES
+        .binary_search_by(|(entry, _)| (*entry).cmp(name))
+        .ok()
+        .map(|index| ENTITIES[index].1)
+}
+
+/// Reads the digits of `&#...;` (after the `#`), returning the character and
+/// the number of bytes used, including the optional `x` and `;`
+fn numeric_reference(rest: &str) -> Option<(char, usize)> {
+    let (radix, prefix) = match rest.as_bytes().first() {
+        Some(b'x' | b'X') => (16, 1),
+        _ => (10, 0),
+    };
+    let digits = rest[prefix..].bytes().take_while(|b| (*b as char).is_digit(radix)).count();
+    if digits == 0 {
+        return None;
+    }
+
+    // Saturate instead of overflowing; anything past U+10FFFF is replaced anyway
+    let value = rest[prefix..prefix + digits].chars().fold(0u32, |value, digit| {
+        value.saturating_mul(radix).saturating_add(digit.to_digit(radix).unwrap()).min(0x110000)
+    });
+
+    let mut len = prefix + digits;
+    if rest[len..].starts_with(';') {
+        len += 1;
+    }
+    Some((numeric_char(value), len))
+}
+
+/// Maps the value of a numeric reference to the character browsers produce
+fn numeric_char(value: u32) -> char {
+    // C1 control codes are read as their Windows-1252 characters
+    const WINDOWS_1252: [u32; 32] = [
+        0x20AC, 0x81, 0x201A, 0x0192, 0x201E, 0x2026, 0x2020, 0x2021, 0x02C6, 0x2030, 0x0160, 0x2039, 0x0152, 0x8D,
+        0x017D, 0x8F, 0x90, 0x2018, 0x2019, 0x201C, 0x201D, 0x2022, 0x2013, 0x2014, 0x02DC, 0x2122, 0x0161, 0x203A,
+        0x0153, 0x9D, 0x017E, 0x0178,
+    ];
+
+    let value = match value {
+        0x80..=0x9F => WINDOWS_1252[(value - 0x80) as usize],
+        _ => value,
+    };
+    // NUL, surrogates and values past U+10FFFF become U+FFFD
+    match value {
+        0 => char::REPLACEMENT_CHARACTER,
+        value => char::from_u32(value).unwrap_or(char::REPLACEMENT_CHARACTER),
+    }
+}
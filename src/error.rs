@@ -0,0 +1,103 @@
+use std::fmt;
+
+use crate::filter::FilterError;
+use crate::selector::SelectorError;
+use crate::xpath::XPathError;
+
+/// The error type of the fallible `try_*` functions and methods
+///
+/// Tag and attribute names are always matched literally, so a name is only
+/// rejected if it could never appear in HTML, such as an empty name or one
+/// with whitespace in it. Any attribute value is accepted.
+///
+/// # Examples
+///
+/// ```
+/// use tagparser::{try_parse_tags, Error};
+///
+/// let html = "<a href='/'>Home</a>".to_string();
+///
+/// assert_eq!(try_parse_tags(html.clone(), "a".to_string()).unwrap(), vec!["<a href='/'>Home</a>"]);
+/// assert_eq!(
+///     try_parse_tags(html, "a b".to_string()),
+///     Err(Error::InvalidTagName("a b".to_string()))
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// The tag name is empty, doesn't start with an ASCII letter, or contains
+    /// whitespace, `/` or `>`
+    InvalidTagName(String),
+    /// The attribute name is empty or contains whitespace, `/`, `>` or `=`
+    InvalidAttributeName(String),
+    /// A CSS selector couldn't be parsed
+    Selector(SelectorError),
+    /// An XPath expression couldn't be parsed or evaluated
+    XPath(XPathError),
+    /// A filter expression couldn't be parsed
+    Filter(FilterError),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidTagName(name) => write!(f, "invalid tag name {:?}", name),
+            Error::InvalidAttributeName(name) => write!(f, "invalid attribute name {:?}", name),
+            Error::Selector(error) => error.fmt(f),
+            Error::XPath(error) => error.fmt(f),
+            Error::Filter(error) => error.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Selector(error) => Some(error),
+            Error::XPath(error) => Some(error),
+            Error::Filter(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<SelectorError> for Error {
+    fn from(error: SelectorError) -> Self {
+        Error::Selector(error)
+    }
+}
+
+impl From<XPathError> for Error {
+    fn from(error: XPathError) -> Self {
+        Error::XPath(error)
+    }
+}
+
+impl From<FilterError> for Error {
+    fn from(error: FilterError) -> Self {
+        Error::Filter(error)
+    }
+}
+
+fn is_name_terminator(c: char) -> bool {
+    c.is_ascii_whitespace() || c == '/' || c == '>'
+}
+
+/// Checks that `name` could be the name of a tag, as the tokenizer reads names
+pub(crate) fn check_tag_name(name: &str) -> Result<(), Error> {
+    let starts_with_letter = name.starts_with(|c: char| c.is_ascii_alphabetic());
+    if starts_with_letter && !name.contains(is_name_terminator) {
+        Ok(())
+    } else {
+        Err(Error::InvalidTagName(name.to_string()))
+    }
+}
+
+/// Checks that `name` could be the name of an attribute, as the tokenizer reads names
+pub(crate) fn check_attribute_name(name: &str) -> Result<(), Error> {
+    if !name.is_empty() && !name.contains(|c: char| is_name_terminator(c) || c == '=') {
+        Ok(())
+    } else {
+        Err(Error::InvalidAttributeName(name.to_string()))
+    }
+}
@@ -0,0 +1,447 @@
+use std::fmt;
+use std::ops::{Bound, Not, RangeBounds};
+use std::str::FromStr;
+
+use crate::attr_match::AttrMatch;
+use crate::dom::Node;
+use crate::parser::attribute_value;
+
+/// An error found while parsing a filter expression
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilterError {
+    /// What went wrong
+    pub message: String,
+    /// Byte offset in the expression where the problem was found
+    pub position: usize,
+}
+
+impl fmt::Display for FilterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid filter at position {}: {}", self.position, self.message)
+    }
+}
+
+impl std::error::Error for FilterError {}
+
+/// A test on elements built from tag names, attribute values, text and depth,
+/// for [`Parser::filter`](crate::Parser::filter)
+///
+/// Filters combine with [`and`](Filter::and), [`or`](Filter::or) and `!`, so a
+/// single query can ask for "links with `class=nav` and `rel=external` but not
+/// `target=_blank`". They can also be parsed from a small expression language
+/// with [`Filter::parse`].
+///
+/// # Examples
+///
+/// ```
+/// use tagparser::{AttrMatch, Filter, Parser};
+///
+/// let html = r#"
+///     <a class="nav" rel="external" href="/a">A</a>
+///     <a class="nav" rel="external" target="_blank" href="/b">B</a>
+///     <a class="nav" href="/c">C</a>
+/// "#;
+///
+/// let filter = Filter::tag("a")
+///     .and(Filter::attr("class", AttrMatch::Word("nav".to_string())))
+///     .and(Filter::attr("rel", AttrMatch::Equals("external".to_string())))
+///     .and(!Filter::attr("target", AttrMatch::Equals("_blank".to_string())));
+///
+/// let parser = Parser::new(html);
+/// assert_eq!(parser.filter(&filter), vec![r#"<a class="nav" rel="external" href="/a">A</a>"#]);
+///
+/// // The same filter as an expression
+/// let parsed = Filter::parse("tag=a and @class~=nav and @rel=external and not @target=_blank").unwrap();
+/// assert_eq!(parser.filter(&parsed), parser.filter(&filter));
+/// ```
+#[derive(Debug, Clone)]
+pub enum Filter {
+    /// The element has this tag name
+    Tag(String),
+    /// The element has this attribute, and its value passes the test
+    Attr(String, AttrMatch),
+    /// The text of the element and its descendants passes the test
+    Text(AttrMatch),
+    /// The element has at least `min` and at most `max` element ancestors;
+    /// the outermost elements have depth 0
+    Depth { min: usize, max: Option<usize> },
+    /// Every filter matches; an empty list matches every element
+    And(Vec<Filter>),
+    /// At least one filter matches; an empty list matches nothing
+    Or(Vec<Filter>),
+    /// The filter doesn't match
+    Not(Box<Filter>),
+}
+
+impl Filter {
+    /// Elements with this tag name
+    pub fn tag(name: &str) -> Filter {
+        Filter::Tag(name.to_string())
+    }
+
+    /// Elements whose attribute called `name` passes `matcher`
+    pub fn attr(name: &str, matcher: AttrMatch) -> Filter {
+        Filter::Attr(name.to_string(), matcher)
+    }
+
+    /// Elements that have an attribute called `name`, with any value
+    pub fn has_attr(name: &str) -> Filter {
+        Filter::attr(name, AttrMatch::Exists)
+    }
+
+    /// Elements whose text passes `matcher`
+    ///
+    /// The text is that of the element and all of its descendants, as in
+    /// [`Tag::text`](crate::Tag::text).
+    pub fn text(matcher: AttrMatch) -> Filter {
+        Filter::Text(matcher)
+    }
+
+    /// Elements whose number of element ancestors is in `range`, such as `..3`
+    /// or `2..=2`
+    pub fn depth(range: impl RangeBounds<usize>) -> Filter {
+        let min = match range.start_bound() {
+            Bound::Included(&min) => min,
+            Bound::Excluded(&min) => min.saturating_add(1),
+            Bound::Unbounded => 0,
+        };
+        let max = match range.end_bound() {
+            Bound::Included(&max) => Some(max),
+            // No depth is below 0
+            Bound::Excluded(&0) => return Filter::Or(Vec::new()),
+            Bound::Excluded(&max) => Some(max - 1),
+            Bound::Unbounded => None,
+        };
+        Filter::Depth { min, max }
+    }
+
+    /// Elements matched by both filters
+    pub fn and(self, other: Filter) -> Filter {
+        match self {
+            Filter::And(mut filters) => {
+                filters.push(other);
+                Filter::And(filters)
+            }
+            _ => Filter::And(vec![self, other]),
+        }
+    }
+
+    /// Elements matched by either filter
+    pub fn or(self, other: Filter) -> Filter {
+        match self {
+            Filter::Or(mut filters) => {
+                filters.push(other);
+                Filter::Or(filters)
+            }
+            _ => Filter::Or(vec![self, other]),
+        }
+    }
+
+    /// Parses a filter expression
+    ///
+    /// An expression is made of these predicates:
+    ///
+    /// * `tag=a` - the tag name is `a`
+    /// * `@href` - the element has an `href` attribute
+    /// * `@href^=https://` - the attribute value passes a test: `=` (equals),
+    ///   `^=` (prefix), `$=` (suffix), `*=` (contains), `~=` (word), `|=` (dash
+    ///   prefix) or `=~` (regular expression)
+    /// * `text*=Buy` - the element's text passes a test, with the same operators
+    /// * `depth<3` - the element's depth compares to a number with `=`, `!=`,
+    ///   `<`, `<=`, `>` or `>=`
+    ///
+    /// combined with `and`, `or`, `not` and parentheses; `and` binds tighter
+    /// than `or`. Values may be quoted with `'` or `"`, and are otherwise read
+    /// up to the next whitespace or parenthesis. An `i` after a value makes the
+    /// test ignore case, as in `@src$=.png i`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tagparser::Filter;
+    ///
+    /// assert!(Filter::parse("tag=a and (@rel=external or @href^='https://') and not text*=ad i").is_ok());
+    ///
+    /// let error = Filter::parse("tag=a and").unwrap_err();
+    /// assert_eq!(error.to_string(), "invalid filter at position 9: expected a predicate");
+    /// ```
+    pub fn parse(expression: &str) -> Result<Filter, FilterError> {
+        let mut parser = FilterParser { input: expression, pos: 0 };
+        let filter = parser.parse_or()?;
+        parser.skip_whitespace();
+        if let Some(c) = parser.peek() {
+            return Err(parser.error(format!("unexpected character '{}'", c)));
+        }
+        Ok(filter)
+    }
+
+    /// Returns `true` if the node is an element the filter matches
+    ///
+    /// Names are compared ASCII case-insensitively, and attribute values and
+    /// text with character references decoded, as [`Parser`](crate::Parser)
+    /// does by default.
+    pub fn matches(&self, node: &Node) -> bool {
+        node.is_element() && self.test(node, true, false)
+    }
+
+    /// Tests an element, comparing names and values as a parser with these
+    /// settings does
+    pub(crate) fn test(&self, node: &Node, decode: bool, case_sensitive: bool) -> bool {
+        match self {
+            Filter::Tag(name) => node.name().is_some_and(|node_name| {
+                if case_sensitive {
+                    node_name == name
+                } else {
+                    node_name.eq_ignore_ascii_case(name)
+                }
+            }),
+            Filter::Attr(name, matcher) => {
+                attribute_value(node, name, decode, case_sensitive).is_some_and(|value| matcher.matches(&value))
+            }
+            Filter::Text(matcher) => matcher.matches(&if decode { node.text() } else { node.raw_text() }),
+            Filter::Depth { min, max } => {
+                let depth = node.ancestors().filter(|ancestor| ancestor.is_element()).count();
+                depth >= *min && max.is_none_or(|max| depth <= max)
+            }
+            Filter::And(filters) => filters.iter().all(|filter| filter.test(node, decode, case_sensitive)),
+            Filter::Or(filters) => filters.iter().any(|filter| filter.test(node, decode, case_sensitive)),
+            Filter::Not(filter) => !filter.test(node, decode, case_sensitive),
+        }
+    }
+
+    /// A tag name every matching element must have, so that only the elements
+    /// with that name need to be tested
+    pub(crate) fn required_tag(&self) -> Option<&str> {
+        match self {
+            Filter::Tag(name) => Some(name),
+            Filter::And(filters) => filters.iter().find_map(Filter::required_tag),
+            _ => None,
+        }
+    }
+}
+
+impl Not for Filter {
+    type Output = Filter;
+
+    /// Elements the filter doesn't match
+    fn not(self) -> Filter {
+        match self {
+            Filter::Not(filter) => *filter,
+            _ => Filter::Not(Box::new(self)),
+        }
+    }
+}
+
+impl FromStr for Filter {
+    type Err = FilterError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Filter::parse(s)
+    }
+}
+
+/// Recursive descent parser for filter expressions
+struct FilterParser<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl FilterParser<'_> {
+    fn parse_or(&mut self) -> Result<Filter, FilterError> {
+        let mut filters = vec![self.parse_and()?];
+        while self.keyword("or") {
+            filters.push(self.parse_and()?);
+        }
+        Ok(if filters.len() == 1 { filters.remove(0) } else { Filter::Or(filters) })
+    }
+
+    fn parse_and(&mut self) -> Result<Filter, FilterError> {
+        let mut filters = vec![self.parse_not()?];
+        while self.keyword("and") {
+            filters.push(self.parse_not()?);
+        }
+        Ok(if filters.len() == 1 { filters.remove(0) } else { Filter::And(filters) })
+    }
+
+    fn parse_not(&mut self) -> Result<Filter, FilterError> {
+        if self.keyword("not") {
+            return Ok(!self.parse_not()?);
+        }
+        self.skip_whitespace();
+        if self.eat("(") {
+            let filter = self.parse_or()?;
+            self.skip_whitespace();
+            if !self.eat(")") {
+                return Err(self.error("expected ')'"));
+            }
+            return Ok(filter);
+        }
+        self.parse_predicate()
+    }
+
+    fn parse_predicate(&mut self) -> Result<Filter, FilterError> {
+        if self.eat("@") {
+            let name = self.parse_name("an attribute name")?;
+            self.skip_whitespace();
+            if !self.peek().is_some_and(is_operator_char) {
+                return Ok(Filter::has_attr(&name));
+            }
+            let matcher = self.parse_match()?;
+            return Ok(Filter::Attr(name, matcher));
+        }
+
+        let start = self.pos;
+        let word = self.parse_name("a predicate")?;
+        match word.to_ascii_lowercase().as_str() {
+            "tag" => {
+                self.skip_whitespace();
+                if !self.eat("=") {
+                    return Err(self.error("expected '=' after tag"));
+                }
+                self.skip_whitespace();
+                Ok(Filter::Tag(self.parse_name("a tag name")?))
+            }
+            "text" => Ok(Filter::Text(self.parse_match()?)),
+            "depth" => self.parse_depth(),
+            _ => Err(self.error_at(start, format!("unknown predicate '{}'", word))),
+        }
+    }
+
+    /// Parses an operator and a value into a test
+    fn parse_match(&mut self) -> Result<AttrMatch, FilterError> {
+        self.skip_whitespace();
+        let start = self.pos;
+        let operator = ["=~", "^=", "$=", "*=", "~=", "|=", "="]
+            .into_iter()
+            .find(|operator| self.eat(operator))
+            .ok_or_else(|| self.error("expected an operator"))?;
+        self.skip_whitespace();
+        let value = self.parse_value()?;
+        let matcher = match operator {
+            "=" => AttrMatch::Equals(value),
+            "^=" => AttrMatch::Prefix(value),
+            "$=" => AttrMatch::Suffix(value),
+            "*=" => AttrMatch::Contains(value),
+            "~=" => AttrMatch::Word(value),
+            "|=" => AttrMatch::DashPrefix(value),
+            _ => AttrMatch::regex(&value).map_err(|error| self.error_at(start, error.to_string()))?,
+        };
+
+        // A lone `i` after the value makes the test ignore case
+        let before = self.pos;
+        self.skip_whitespace();
+        if self.eat("i") && self.at_word_end() {
+            return Ok(matcher.ignore_case());
+        }
+        self.pos = before;
+        Ok(matcher)
+    }
+
+    fn parse_depth(&mut self) -> Result<Filter, FilterError> {
+        self.skip_whitespace();
+        let operator = ["!=", "<=", ">=", "=", "<", ">"]
+            .into_iter()
+            .find(|operator| self.eat(operator))
+            .ok_or_else(|| self.error("expected a comparison after depth"))?;
+        self.skip_whitespace();
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        let depth: usize = self.input[start..self.pos]
+            .parse()
+            .map_err(|_| self.error_at(start, "expected a number"))?;
+        Ok(match operator {
+            "=" => Filter::depth(depth..=depth),
+            "!=" => !Filter::depth(depth..=depth),
+            "<" => Filter::depth(..depth),
+            "<=" => Filter::depth(..=depth),
+            ">" => Filter::depth(depth.checked_add(1).ok_or_else(|| self.error_at(start, "depth is too large"))?..),
+            _ => Filter::depth(depth..),
+        })
+    }
+
+    /// Reads a tag name, attribute name or keyword
+    fn parse_name(&mut self, what: &str) -> Result<String, FilterError> {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| !c.is_whitespace() && !is_operator_char(c) && c != '(' && c != ')') {
+            self.pos += self.peek().map_or(0, char::len_utf8);
+        }
+        if start == self.pos {
+            return Err(self.error(format!("expected {}", what)));
+        }
+        Ok(self.input[start..self.pos].to_string())
+    }
+
+    /// Reads a quoted value, or a bare one up to whitespace or a parenthesis
+    fn parse_value(&mut self) -> Result<String, FilterError> {
+        if let Some(quote @ ('"' | '\'')) = self.peek() {
+            let start = self.pos;
+            self.pos += 1;
+            let Some(len) = self.input[self.pos..].find(quote) else {
+                return Err(self.error_at(start, "unterminated string"));
+            };
+            let value = self.input[self.pos..self.pos + len].to_string();
+            self.pos += len + 1;
+            return Ok(value);
+        }
+        let start = self.pos;
+        while self.peek().is_some_and(|c| !c.is_whitespace() && c != '(' && c != ')') {
+            self.pos += self.peek().map_or(0, char::len_utf8);
+        }
+        if start == self.pos {
+            return Err(self.error("expected a value"));
+        }
+        Ok(self.input[start..self.pos].to_string())
+    }
+
+    /// Consumes `word` if it comes next as a whole word, ignoring ASCII case
+    fn keyword(&mut self, word: &str) -> bool {
+        let before = self.pos;
+        self.skip_whitespace();
+        let rest = &self.input[self.pos..];
+        if rest.len() >= word.len() && rest.as_bytes()[..word.len()].eq_ignore_ascii_case(word.as_bytes()) {
+            self.pos += word.len();
+            if self.at_word_end() {
+                return true;
+            }
+        }
+        self.pos = before;
+        false
+    }
+
+    fn at_word_end(&self) -> bool {
+        self.peek().is_none_or(|c| c.is_whitespace() || c == '(' || c == ')')
+    }
+
+    fn eat(&mut self, text: &str) -> bool {
+        if self.input[self.pos..].starts_with(text) {
+            self.pos += text.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.input[self.pos..].chars().next()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += self.peek().map_or(0, char::len_utf8);
+        }
+    }
+
+    fn error(&self, message: impl Into<String>) -> FilterError {
+        self.error_at(self.pos, message)
+    }
+
+    fn error_at(&self, position: usize, message: impl Into<String>) -> FilterError {
+        FilterError { message: message.into(), position }
+    }
+}
+
+fn is_operator_char(c: char) -> bool {
+    matches!(c, '=' | '^' | '$' | '*' | '~' | '|' | '!' | '<' | '>')
+}
@@ -0,0 +1,404 @@
+//! Extraction of `<form>` elements and their controls
+//!
+//! Controls are found the way a browser finds a form's elements: every
+//! `<input>`, `<select>`, `<textarea>` and `<button>` inside the form, unless a
+//! `form` attribute hands it to another form, plus those elsewhere in the page
+//! whose `form` attribute names this form's `id`.
+
+use std::borrow::Cow;
+use std::fmt::Write;
+
+use crate::dom::Node;
+use crate::parser::attribute_value;
+
+/// Input types a browser knows; any other `type` behaves as `text`
+const INPUT_TYPES: &[&str] = &[
+    "button", "checkbox", "color", "date", "datetime-local", "email", "file", "hidden", "image", "month", "number",
+    "password", "radio", "range", "reset", "search", "submit", "tel", "text", "time", "url", "week",
+];
+
+/// Input types that only take part in a submission when they submit the form
+const BUTTON_TYPES: &[&str] = &["button", "image", "reset", "submit"];
+
+/// A form found in a page, with its controls
+///
+/// # Examples
+///
+/// ```
+/// use tagparser::{extract_forms, ControlKind};
+///
+/// let html = r#"
+///     <form action="/search" method="POST">
+///         <label for="q">Search for</label> <input id="q" name="q" value="rust html">
+///         <label><input type="checkbox" name="exact" checked> Exact match</label>
+///         <select name="sort"><option value="new">Newest<option value="top" selected>Top</select>
+///         <button>Go</button>
+///     </form>
+/// "#.to_string();
+/// let form = &extract_forms(html)[0];
+///
+/// assert_eq!(form.action.as_deref(), Some("/search"));
+/// assert_eq!(form.method, "post");
+/// assert_eq!(form.controls[0].label.as_deref(), Some("Search for"));
+/// assert_eq!(form.controls[1].label.as_deref(), Some("Exact match"));
+/// assert_eq!(form.controls[2].kind, ControlKind::Select);
+/// assert_eq!(form.controls[2].value, "top");
+/// assert_eq!(form.controls[3].control_type, "submit");
+/// assert_eq!(form.to_urlencoded(), "q=rust+html&exact=on&sort=top");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Form {
+    /// The `id` attribute, if any
+    pub id: Option<String>,
+    /// The `name` attribute, if any
+    pub name: Option<String>,
+    /// The `action` attribute as written; `None` if the form submits to the page's own URL
+    pub action: Option<String>,
+    /// The submission method, lowercased: `get` (the default), `post` or `dialog`
+    pub method: String,
+    /// The encoding of the submission, lowercased: `application/x-www-form-urlencoded`
+    /// (the default), `multipart/form-data` or `text/plain`
+    pub enctype: String,
+    /// The controls of the form in document order
+    pub controls: Vec<Control>,
+}
+
+/// The element a [`Control`] is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ControlKind {
+    /// An `<input>`
+    Input,
+    /// A `<select>`, with its options in [`Control::options`]
+    Select,
+    /// A `<textarea>`
+    Textarea,
+    /// A `<button>`
+    Button,
+}
+
+/// A control of a [`Form`], in the state it's in when the page loads
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Control {
+    /// Which element the control is
+    pub kind: ControlKind,
+    /// The `name` attribute; empty if the control has none, in which case it's never submitted
+    pub name: String,
+    /// The type as a browser reports it: the lowercased `type` of an input, with
+    /// unknown types as `text`; `submit`, `reset` or `button` for a button;
+    /// `select-one` or `select-multiple` for a select; `textarea` for a textarea
+    pub control_type: String,
+    /// The value: the `value` attribute of an input or button (`on` for a
+    /// checkbox or radio button without one), the text of a textarea, or the
+    /// value of the first selected option of a select
+    pub value: String,
+    /// Whether a checkbox or radio button is checked
+    pub checked: bool,
+    /// Whether the control is disabled, by its own `disabled` attribute or a disabled `<fieldset>`
+    pub disabled: bool,
+    /// The text of the control's label: a `<label for>` naming its `id`, or else a
+    /// `<label>` wrapped around it
+    pub label: Option<String>,
+    /// The options of a select, including those in `<optgroup>`s
+    pub options: Vec<SelectOption>,
+}
+
+/// An option of a `<select>`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SelectOption {
+    /// The `value` attribute, or the text of the option without one
+    pub value: String,
+    /// The text of the option, with whitespace collapsed
+    pub text: String,
+    /// Whether the option is selected when the page loads
+    ///
+    /// When no option of a single-choice select has a `selected` attribute,
+    /// the first enabled option is selected, as in browsers.
+    pub selected: bool,
+    /// Whether the option, or the `<optgroup>` it's in, is disabled
+    pub disabled: bool,
+}
+
+impl Form {
+    /// The name-value pairs a browser sends when the form is submitted without
+    /// changing anything and without pressing a button
+    ///
+    /// Disabled controls, controls without a name, unchecked checkboxes and
+    /// radio buttons, and buttons are left out. A select contributes one pair
+    /// for each of its selected, enabled options, and a file input an empty value.
+    pub fn submission(&self) -> Vec<(String, String)> {
+        let mut entries = Vec::new();
+        for control in &self.controls {
+            if control.disabled || control.name.is_empty() {
+                continue;
+            }
+            let name = control.name.clone();
+            match control.kind {
+                ControlKind::Button => {}
+                ControlKind::Select => entries.extend(
+                    control
+                        .options
+                        .iter()
+                        .filter(|option| option.selected && !option.disabled)
+                        .map(|option| (name.clone(), option.value.clone())),
+                ),
+                ControlKind::Textarea => entries.push((name, control.value.clone())),
+                ControlKind::Input => match control.control_type.as_str() {
+                    "checkbox" | "radio" if !control.checked => {}
+                    "file" => entries.push((name, String::new())),
+                    input_type if BUTTON_TYPES.contains(&input_type) => {}
+                    _ => entries.push((name, control.value.clone())),
+                },
+            }
+        }
+        entries
+    }
+
+    /// Encodes the [`submission`](Form::submission) as
+    /// `application/x-www-form-urlencoded`, as sent in a GET query string or a POST body
+    ///
+    /// Line breaks become `%0D%0A`, spaces `+`, and every byte other than ASCII
+    /// letters, digits and `*-._` is percent-encoded as UTF-8.
+    pub fn to_urlencoded(&self) -> String {
+        let pairs: Vec<String> = self
+            .submission()
+            .iter()
+            .map(|(name, value)| format!("{}={}", urlencode(name), urlencode(value)))
+            .collect();
+        pairs.join("&")
+    }
+}
+
+impl Form {
+    /// Copies a `<form>` element and its controls out of its document, decoding
+    /// character references in values and text if `decode` is set
+    pub(crate) fn new(form: Node<'_>, decode: bool) -> Form {
+        let attribute = |name: &str| attribute_value(&form, name, decode, false).map(Cow::into_owned);
+        let method = keyword(attribute("method"), &["get", "post", "dialog"]);
+        let enctype = keyword(
+            attribute("enctype"),
+            &["application/x-www-form-urlencoded", "multipart/form-data", "text/plain"],
+        );
+
+        let id = attribute("id");
+        let mut controls: Vec<Control> = form
+            .document()
+            .elements()
+            .filter(|node| control_kind(node).is_some() && owns(&form, id.as_deref(), node))
+            .map(|node| Control::new(node, decode))
+            .collect();
+        uncheck_radio_groups(&mut controls);
+
+        Form { id, name: attribute("name"), action: attribute("action"), method, enctype, controls }
+    }
+}
+
+impl From<Node<'_>> for Form {
+    /// Copies a `<form>` element and its controls out of its document
+    fn from(node: Node<'_>) -> Self {
+        Form::new(node, true)
+    }
+}
+
+impl Control {
+    fn new(node: Node<'_>, decode: bool) -> Control {
+        let attribute = |name: &str| attribute_value(&node, name, decode, false);
+        let has_attribute = |name: &str| node.raw_attribute(name).is_some();
+        let kind = control_kind(&node).expect("only controls are passed in");
+        let lowercase_type = attribute("type").map(|value| value.trim().to_ascii_lowercase());
+        let value = attribute("value").map(Cow::into_owned);
+
+        let mut options = Vec::new();
+        let (control_type, value) = match kind {
+            ControlKind::Input => {
+                let input_type = lowercase_type
+                    .filter(|input_type| INPUT_TYPES.contains(&input_type.as_str()))
+                    .unwrap_or_else(|| "text".to_string());
+                let default = if input_type == "checkbox" || input_type == "radio" { "on" } else { "" };
+                (input_type, value.unwrap_or_else(|| default.to_string()))
+            }
+            ControlKind::Button => {
+                let button_type = match lowercase_type.as_deref() {
+                    Some(button_type @ ("reset" | "button")) => button_type.to_string(),
+                    _ => "submit".to_string(),
+                };
+                (button_type, value.unwrap_or_default())
+            }
+            ControlKind::Textarea => {
+                let text = if decode { node.text() } else { node.raw_text() };
+                // A line break straight after the start tag isn't part of the value
+                let text = text.strip_prefix("\r\n").or_else(|| text.strip_prefix('\n')).unwrap_or(&text);
+                ("textarea".to_string(), text.to_string())
+            }
+            ControlKind::Select => {
+                let multiple = has_attribute("multiple");
+                let size = attribute("size").and_then(|size| size.trim().parse::<usize>().ok());
+                options = select_options(node, decode, !multiple && size.unwrap_or(1) <= 1, multiple);
+                let value = options.iter().find(|option| option.selected).map(|option| option.value.clone());
+                let select_type = if multiple { "select-multiple" } else { "select-one" };
+                (select_type.to_string(), value.unwrap_or_default())
+            }
+        };
+
+        let name = attribute("name").map(Cow::into_owned).unwrap_or_default();
+        let checked = (control_type == "checkbox" || control_type == "radio") && has_attribute("checked");
+        let label = if control_type == "hidden" { None } else { label(node, decode) };
+        Control {
+            kind,
+            name,
+            checked,
+            disabled: has_attribute("disabled") || in_disabled_fieldset(node),
+            control_type,
+            value,
+            label,
+            options,
+        }
+    }
+}
+
+fn is_named(node: &Node, name: &str) -> bool {
+    node.name().is_some_and(|node_name| node_name.eq_ignore_ascii_case(name))
+}
+
+fn control_kind(node: &Node) -> Option<ControlKind> {
+    match node.name()?.to_ascii_lowercase().as_str() {
+        "input" => Some(ControlKind::Input),
+        "select" => Some(ControlKind::Select),
+        "textarea" => Some(ControlKind::Textarea),
+        "button" => Some(ControlKind::Button),
+        _ => None,
+    }
+}
+
+/// Returns the value if it's one of `keywords` ignoring case, or else the first keyword
+fn keyword(value: Option<String>, keywords: &[&str]) -> String {
+    let value = value.map(|value| value.trim().to_ascii_lowercase());
+    value.filter(|value| keywords.contains(&value.as_str())).unwrap_or_else(|| keywords[0].to_string())
+}
+
+/// Returns `true` if `control` belongs to `form`, whose `id` is `form_id`
+fn owns(form: &Node, form_id: Option<&str>, control: &Node) -> bool {
+    match control.attribute("form") {
+        Some(owner) => form_id == Some(&*owner),
+        None => control.ancestors().find(|node| is_named(node, "form")).is_some_and(|owner| owner.id() == form.id()),
+    }
+}
+
+/// Leaves only the last checked radio button of each group checked, as
+/// checking a radio button unchecks the others with its name
+fn uncheck_radio_groups(controls: &mut [Control]) {
+    for index in (0..controls.len()).rev() {
+        let control = &controls[index];
+        if control.control_type != "radio" || !control.checked || control.name.is_empty() {
+            continue;
+        }
+        let name = control.name.clone();
+        for earlier in &mut controls[..index] {
+            if earlier.control_type == "radio" && earlier.name == name {
+                earlier.checked = false;
+            }
+        }
+    }
+}
+
+/// Returns `true` if the control is inside a disabled `<fieldset>`, other than
+/// in its first `<legend>`, which stays enabled
+fn in_disabled_fieldset(control: Node) -> bool {
+    let mut child = control;
+    for ancestor in control.ancestors() {
+        if is_named(&ancestor, "fieldset") && ancestor.raw_attribute("disabled").is_some() {
+            let first_legend = ancestor.children().find(|node| is_named(node, "legend"));
+            if first_legend.is_none_or(|legend| legend.id() != child.id()) {
+                return true;
+            }
+        }
+        child = ancestor;
+    }
+    false
+}
+
+/// Collects the options of a select, settling which are selected
+///
+/// A single-choice select has at most one selected option, the last one marked
+/// `selected`; `pick_first` selects its first enabled option when none is.
+fn select_options(select: Node, decode: bool, pick_first: bool, multiple: bool) -> Vec<SelectOption> {
+    let mut options: Vec<SelectOption> = select
+        .descendants()
+        .filter(|node| is_named(node, "option"))
+        .map(|option| {
+            let text = collapse_whitespace(&if decode { option.text() } else { option.raw_text() });
+            let group_disabled = option
+                .parent()
+                .is_some_and(|parent| is_named(&parent, "optgroup") && parent.raw_attribute("disabled").is_some());
+            SelectOption {
+                value: attribute_value(&option, "value", decode, false).map_or_else(|| text.clone(), Cow::into_owned),
+                text,
+                selected: option.raw_attribute("selected").is_some(),
+                disabled: group_disabled || option.raw_attribute("disabled").is_some(),
+            }
+        })
+        .collect();
+
+    if !multiple {
+        if let Some(last) = options.iter().rposition(|option| option.selected) {
+            for option in &mut options[..last] {
+                option.selected = false;
+            }
+        } else if pick_first {
+            if let Some(first) = options.iter_mut().find(|option| !option.disabled) {
+                first.selected = true;
+            }
+        }
+    }
+    options
+}
+
+/// The text of the label associated with a control
+fn label(control: Node, decode: bool) -> Option<String> {
+    let mut labels = control.document().elements().filter(|node| is_named(node, "label"));
+    let explicit = match control.attribute("id") {
+        Some(id) => labels.find(|label| label.attribute("for").as_deref() == Some(&*id)),
+        None => None,
+    };
+    // A label with `for` set only labels the control it names
+    let label = explicit.or_else(|| {
+        control
+            .ancestors()
+            .find(|node| is_named(node, "label"))
+            .filter(|label| label.raw_attribute("for").is_none())
+    })?;
+    Some(label_text(label, decode))
+}
+
+/// The text of a label, leaving out the text of controls inside it, such as
+/// the options of a select
+fn label_text(label: Node, decode: bool) -> String {
+    let text: String = label
+        .descendants()
+        .filter(|node| {
+            let mut inside = node.ancestors().take_while(|ancestor| ancestor.id() != label.id());
+            inside.all(|ancestor| control_kind(&ancestor).is_none())
+        })
+        .filter_map(|node| if decode { node.as_text() } else { node.as_raw_text().map(Cow::Borrowed) })
+        .collect();
+    collapse_whitespace(&text)
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_ascii_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Percent-encodes a name or value for `application/x-www-form-urlencoded`
+fn urlencode(text: &str) -> String {
+    let text = text.replace("\r\n", "\n").replace('\r', "\n").replace('\n', "\r\n");
+    let mut encoded = String::with_capacity(text.len());
+    for byte in text.bytes() {
+        match byte {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'*' | b'-' | b'.' | b'_' => encoded.push(byte as char),
+            b' ' => encoded.push('+'),
+            _ => {
+                let _ = write!(encoded, "%{:02X}", byte);
+            }
+        }
+    }
+    encoded
+}
@@ -1,5 +1,32 @@
+pub mod attr_match;
+pub mod dom;
+pub mod encoding;
+pub mod entities;
+pub mod error;
+pub mod filter;
+pub mod form;
+pub mod metadata;
 pub mod parser;
+pub mod selector;
+pub mod stream;
+pub mod table;
+pub mod tag;
+mod text;
+pub mod xpath;
+pub use crate::attr_match::AttrMatch;
+pub use crate::dom::{Document, Location, Node, NodeId, NodeKind, Position};
+pub use crate::encoding::{DetectedEncoding, EncodingSource};
+pub use crate::error::Error;
+pub use crate::filter::{Filter, FilterError};
+pub use crate::form::{Control, ControlKind, Form, SelectOption};
+pub use crate::metadata::{Icon, Metadata, Property};
+pub use crate::parser::diagnostics::{DiagnosticKind, ParseDiagnostic};
 pub use crate::parser::Parser;
+pub use crate::selector::{Selector, SelectorError};
+pub use crate::stream::StreamParser;
+pub use crate::table::Table;
+pub use crate::tag::Tag;
+pub use crate::xpath::{Value, XPath, XPathError, XPathNode};
 
 /// Extract html tag from page
 /// # Examples
@@ -31,8 +58,63 @@ pub use crate::parser::Parser;
 ///
 /// ```
 pub fn parse_tags(html: String, tag: String) -> Vec<String> {
-    let mut parser = Parser::new(html);
-    parser.parse_tags(tag)
+    let parser = Parser::new(html);
+    owned(parser.parse_tags(&tag))
+}
+
+/// Extract html tag from page, rejecting invalid tag names
+/// 
+/// This works like [`parse_tags`], but a tag name that could never appear in HTML
+/// (empty, not starting with a letter, or containing whitespace, `/` or `>`) gives
+/// an [`Error`] instead of an empty result. Names are always matched literally.
+/// 
+/// # Examples
+/// 
+/// ```
+///     use tagparser::{try_parse_tags, Error};
+///
+///     let html = "<p>Test</p><a href='/'>Home</a>".to_string();
+///     
+///     assert_eq!(Ok(vec!["<p>Test</p>".to_string()]), try_parse_tags(html.clone(), "p".to_string()));
+///     assert_eq!(Ok(Vec::new()), try_parse_tags(html.clone(), "a(".to_string()));
+///     assert_eq!(Err(Error::InvalidTagName("<p>".to_string())), try_parse_tags(html, "<p>".to_string()));
+/// ```
+pub fn try_parse_tags(html: String, tag: String) -> Result<Vec<String>, Error> {
+    let parser = Parser::new(html);
+    parser.try_parse_tags(&tag).map(owned)
+}
+
+/// Find HTML tags in a page as structured [`Tag`]s
+/// 
+/// This works like [`parse_tags`], but instead of the raw HTML of each element it
+/// returns a [`Tag`] holding the name, attributes, inner and outer HTML, text and
+/// source position, so the results don't have to be parsed again.
+/// 
+/// # Arguments
+/// 
+/// * `html` - HTML content to parse
+/// * `tag` - The HTML tag name to search for (e.g., "a", "p", "div")
+/// 
+/// # Returns
+/// 
+/// A vector of [`Tag`]s in document order
+/// 
+/// # Examples
+/// 
+/// ```
+///     use tagparser::find_tags;
+///
+///     let html = r#"<a href="https://github.com" title="GitHub">Git<i>Hub</i></a>"#.to_string();
+///     
+///     let links = find_tags(html, "a".to_string());
+///     assert_eq!("a", links[0].name);
+///     assert_eq!(Some("https://github.com"), links[0].attribute("href"));
+///     assert_eq!("Git<i>Hub</i>", links[0].inner_html);
+///     assert_eq!("GitHub", links[0].text);
+/// ```
+pub fn find_tags(html: String, tag: String) -> Vec<Tag> {
+    let parser = Parser::new(html);
+    parser.find_tags(&tag)
 }
 
 /// Extract HTML tags with specific attribute from page
@@ -103,8 +185,112 @@ pub fn parse_tags(html: String, tag: String) -> Vec<String> {
 ///    let form_inputs = parse_tags_with_attr(html, "input".to_string(), "name", None);
 ///    ```
 pub fn parse_tags_with_attr(html: String, tag: String, attr_name: &str, attr_value: Option<&str>) -> Vec<String> {
-    let mut parser = Parser::new(html);
-    parser.parse_tags_with_attr(tag, attr_name, attr_value)
+    let parser = Parser::new(html);
+    owned(parser.parse_tags_with_attr(&tag, attr_name, attr_value))
+}
+
+/// Extract HTML tags with a specific attribute, rejecting invalid names
+/// 
+/// This works like [`parse_tags_with_attr`], but a tag or attribute name that could
+/// never appear in HTML gives an [`Error`]. The attribute value is compared
+/// literally, so any value is accepted.
+/// 
+/// # Examples
+/// 
+/// ```
+///     use tagparser::{try_parse_tags_with_attr, Error};
+///
+///     let html = "<a class='a.b'>Dot</a><a class='axb'>X</a>".to_string();
+///     
+///     let dotted = try_parse_tags_with_attr(html.clone(), "a".to_string(), "class", Some("a.b"));
+///     assert_eq!(Ok(vec!["<a class='a.b'>Dot</a>".to_string()]), dotted);
+///     
+///     let invalid = try_parse_tags_with_attr(html, "a".to_string(), "data value", None);
+///     assert_eq!(Err(Error::InvalidAttributeName("data value".to_string())), invalid);
+/// ```
+pub fn try_parse_tags_with_attr(
+    html: String,
+    tag: String,
+    attr_name: &str,
+    attr_value: Option<&str>,
+) -> Result<Vec<String>, Error> {
+    let parser = Parser::new(html);
+    parser.try_parse_tags_with_attr(&tag, attr_name, attr_value).map(owned)
+}
+
+/// Extract HTML tags whose attribute passes an [`AttrMatch`] test
+/// 
+/// This works like [`parse_tags_with_attr`], but the attribute value can be
+/// matched by prefix, suffix, substring, whitespace-separated word, dash prefix
+/// (`en` for `en-GB`) or regular expression, and any of these can ignore case.
+/// 
+/// # Arguments
+/// 
+/// * `html` - HTML content to parse
+/// * `tag` - The HTML tag name to search for (e.g., "a", "img")
+/// * `attr_name` - The attribute to test (e.g., "href", "src", "class")
+/// * `matcher` - The test the attribute's value must pass
+/// 
+/// # Examples
+/// 
+/// ```
+///     use tagparser::{parse_tags_matching, AttrMatch};
+///
+///     let html = "<img src='/logo.png'><img src='/photo.JPG'><img src='/icon.svg'>".to_string();
+///     
+///     let png = parse_tags_matching(html.clone(), "img".to_string(), "src", &AttrMatch::Suffix(".png".to_string()));
+///     assert_eq!(vec!["<img src='/logo.png'>".to_string()], png);
+///     
+///     let raster = AttrMatch::regex(r"\.(png|jpe?g)$").unwrap().ignore_case();
+///     let images = parse_tags_matching(html, "img".to_string(), "src", &raster);
+///     assert_eq!(vec!["<img src='/logo.png'>".to_string(), "<img src='/photo.JPG'>".to_string()], images);
+/// ```
+pub fn parse_tags_matching(html: String, tag: String, attr_name: &str, matcher: &AttrMatch) -> Vec<String> {
+    let parser = Parser::new(html);
+    owned(parser.parse_tags_matching(&tag, attr_name, matcher))
+}
+
+/// Extract HTML tags whose attribute passes an [`AttrMatch`] test, rejecting
+/// invalid names
+/// 
+/// This works like [`parse_tags_matching`], but a tag or attribute name that
+/// could never appear in HTML gives an [`Error`].
+pub fn try_parse_tags_matching(
+    html: String,
+    tag: String,
+    attr_name: &str,
+    matcher: &AttrMatch,
+) -> Result<Vec<String>, Error> {
+    let parser = Parser::new(html);
+    parser.try_parse_tags_matching(&tag, attr_name, matcher).map(owned)
+}
+
+/// Find HTML tags with a specific attribute as structured [`Tag`]s
+/// 
+/// This works like [`parse_tags_with_attr`] but returns [`Tag`]s instead of raw HTML.
+/// 
+/// # Arguments
+/// 
+/// * `html` - HTML content to parse
+/// * `tag` - The HTML tag name to search for (e.g., "a", "div", "img")
+/// * `attr_name` - The attribute name to filter by (e.g., "href", "class", "id")
+/// * `attr_value` - Optional attribute value to filter by
+/// 
+/// # Examples
+/// 
+/// ```
+///     use tagparser::find_tags_with_attr;
+///
+///     let html = "<img src='a.png' alt='First'/><img class='icon' src='b.png' alt='Second'/>".to_string();
+///     
+///     let icons = find_tags_with_attr(html, "img".to_string(), "class", Some("icon"));
+///     assert_eq!(1, icons.len());
+///     assert_eq!(Some("b.png"), icons[0].attribute("src"));
+///     assert_eq!(Some("Second"), icons[0].attribute("alt"));
+/// ```
+pub fn find_tags_with_attr(html: String, tag: String, attr_name: &str, attr_value: Option<&str>) -> Vec<Tag> {
+    let parser = Parser::new(html);
+    parser.find_tags_with_attr(&tag, attr_name, attr_value)
 }
 
 /// Extract the text content from inside HTML tags
@@ -175,8 +361,76 @@ pub fn parse_tags_with_attr(html: String, tag: String, attr_name: &str, attr_val
 ///    // Returns: ["Item 1", "Item 2"]
 ///    ```
 pub fn extract_tag_content(html: String, tag: String) -> Vec<String> {
-    let mut parser = Parser::new(html);
-    parser.extract_tag_content(tag)
+    let parser = Parser::new(html);
+    owned(parser.extract_tag_content(&tag))
+}
+
+/// Extract the text content from inside HTML tags, rejecting invalid tag names
+/// 
+/// This works like [`extract_tag_content`], but a tag name that could never appear
+/// in HTML gives an [`Error`] instead of an empty result.
+/// 
+/// # Examples
+/// 
+/// ```
+///     use tagparser::{try_extract_tag_content, Error};
+///
+///     let html = "<a href='/'>Home</a>".to_string();
+///     
+///     assert_eq!(Ok(vec!["Home".to_string()]), try_extract_tag_content(html.clone(), "a".to_string()));
+///     assert!(matches!(try_extract_tag_content(html, " a".to_string()), Err(Error::InvalidTagName(_))));
+/// ```
+pub fn try_extract_tag_content(html: String, tag: String) -> Result<Vec<String>, Error> {
+    let parser = Parser::new(html);
+    parser.try_extract_tag_content(&tag).map(owned)
+}
+
+/// Extract the plain text of HTML tags
+/// 
+/// Unlike [`extract_tag_content`], this strips all nested markup. The text is
+/// rendered the way a browser would show it: `<script>` and `<style>` bodies are
+/// dropped, character references are decoded, runs of whitespace collapse to a
+/// single space, and block elements (`<p>`, `<div>`, `<li>`, ...) and `<br>` start
+/// new lines. Table cells in a row are separated by tabs, and whitespace inside
+/// `<pre>` is kept.
+/// 
+/// # Arguments
+/// 
+/// * `html` - HTML content to parse
+/// * `tag` - The HTML tag name to search for (e.g., "p", "article", "body")
+/// 
+/// # Returns
+/// 
+/// A vector of strings containing the plain text of all matching tags
+/// 
+/// # Examples
+/// 
+/// ```
+///     use tagparser::text_content;
+///
+///     let html = r#"
+///         <p>This is a <strong>paragraph</strong>
+///            with   some text.</p>
+///         <div class="article">
+///             <h2>Fish &amp; Chips</h2>
+///             <script>track("view");</script>
+///             <p>First paragraph.</p>
+///             <p>Second<br/>paragraph.</p>
+///         </div>
+///     "#.to_string();
+///     
+///     let paragraphs = text_content(html.clone(), "p".to_string());
+///     assert_eq!(
+///         vec!["This is a paragraph with some text.", "First paragraph.", "Second\nparagraph."],
+///         paragraphs
+///     );
+///     
+///     let article = text_content(html, "div".to_string());
+///     assert_eq!(vec!["Fish & Chips\n\nFirst paragraph.\n\nSecond\nparagraph."], article);
+/// ```
+pub fn text_content(html: String, tag: String) -> Vec<String> {
+    let parser = Parser::new(html);
+    parser.text_content(&tag)
 }
 
 /// Extract attribute values from HTML tags
@@ -247,6 +501,397 @@ pub fn extract_tag_content(html: String, tag: String) -> Vec<String> {
 ///    // Returns: ["username", "password"]
 ///    ```
 pub fn extract_attribute_values(html: String, tag: String, attr_name: &str) -> Vec<String> {
-    let mut parser = Parser::new(html);
-    parser.extract_attribute_values(tag, attr_name)
+    let parser = Parser::new(html);
+    owned(parser.extract_attribute_values(&tag, attr_name))
+}
+
+/// Extract attribute values from HTML tags, rejecting invalid names
+/// 
+/// This works like [`extract_attribute_values`], but a tag or attribute name that
+/// could never appear in HTML gives an [`Error`] instead of an empty result.
+/// 
+/// # Examples
+/// 
+/// ```
+///     use tagparser::{try_extract_attribute_values, Error};
+///
+///     let html = "<a href='/one'>One</a><a href='/two'>Two</a>".to_string();
+///     
+///     let hrefs = try_extract_attribute_values(html.clone(), "a".to_string(), "href");
+///     assert_eq!(Ok(vec!["/one".to_string(), "/two".to_string()]), hrefs);
+///     
+///     let invalid = try_extract_attribute_values(html, "a".to_string(), "");
+///     assert_eq!(Err(Error::InvalidAttributeName("".to_string())), invalid);
+/// ```
+pub fn try_extract_attribute_values(html: String, tag: String, attr_name: &str) -> Result<Vec<String>, Error> {
+    let parser = Parser::new(html);
+    parser.try_extract_attribute_values(&tag, attr_name).map(owned)
+}
+
+/// Find HTML elements matching a CSS selector
+/// 
+/// This function supports type, class, id and attribute selectors, the descendant,
+/// child and sibling combinators, selector lists and the common structural
+/// pseudo-classes. See [`Selector`] for the full list.
+/// 
+/// # Arguments
+/// 
+/// * `html` - HTML content to parse
+/// * `selector` - A CSS selector (e.g., "div.article > h2 a", "ul li:nth-child(2)")
+/// 
+/// # Returns
+/// 
+/// A vector of strings containing the matching HTML elements in document order,
+/// or a [`SelectorError`] if the selector is invalid
+/// 
+/// # Examples
+/// 
+/// ```
+///     use tagparser::select;
+///
+///     let html = r#"
+///         <div class="article">
+///             <h2><a href="https://example.com">Secure</a> <a href="http://example.com">Plain</a></h2>
+///         </div>
+///     "#.to_string();
+///     
+///     let links = select(html.clone(), r#"div.article > h2 a[href^="https"]"#).unwrap();
+///     assert_eq!(
+///         vec![r#"<a href="https://example.com">Secure</a>"#],
+///         links
+///     );
+///     
+///     // Invalid selectors are reported instead of matching nothing
+///     assert!(select(html, "div >").is_err());
+/// ```
+/// 
+/// # Common Use Cases
+/// 
+/// 1. Find the second item of every list:
+///    ```
+///    # use tagparser::select;
+///    # let html = "<ul><li>Item 1</li><li>Item 2</li></ul>".to_string();
+///    let items = select(html, "ul li:nth-child(2)").unwrap();
+///    // Returns: ["<li>Item 2</li>"]
+///    ```
+/// 
+/// 2. Find all visible form inputs:
+///    ```
+///    # use tagparser::select;
+///    # let html = "<input type='hidden' name='token'/><input type='text' name='q'/>".to_string();
+///    let inputs = select(html, "input:not([type=hidden])").unwrap();
+///    // Returns: ["<input type='text' name='q'/>"]
+///    ```
+pub fn select(html: String, selector: &str) -> Result<Vec<String>, SelectorError> {
+    let parser = Parser::new(html);
+    parser.select(selector).map(owned)
+}
+
+/// Extract the HTML elements matched by a filter expression
+/// 
+/// Filter expressions test tag names (`tag=a`), attributes (`@rel=external`,
+/// `@href^=https://`), text (`text*=Buy`) and depth (`depth<3`), combined with
+/// `and`, `or`, `not` and parentheses. See [`Filter::parse`] for the full syntax,
+/// and [`Filter`] for building filters in code.
+/// 
+/// # Arguments
+/// 
+/// * `html` - HTML content to parse
+/// * `expression` - A filter expression (e.g., "tag=a and @class~=nav and not @target=_blank")
+/// 
+/// # Returns
+/// 
+/// The HTML of every complete matching element in document order,
+/// or a [`FilterError`] if the expression is invalid
+/// 
+/// # Examples
+/// 
+/// ```
+///     use tagparser::filter;
+///
+///     let html = r#"
+///         <a class="nav" rel="external" href="/a">A</a>
+///         <a class="nav" rel="external" target="_blank" href="/b">B</a>
+///         <a class="nav" href="/c">C</a>
+///     "#.to_string();
+///     
+///     let links = filter(html.clone(), "tag=a and @class~=nav and @rel=external and not @target=_blank").unwrap();
+///     assert_eq!(vec![r#"<a class="nav" rel="external" href="/a">A</a>"#], links);
+///     
+///     // Invalid expressions are reported instead of matching nothing
+///     assert!(filter(html, "tag=a and").is_err());
+/// ```
+pub fn filter(html: String, expression: &str) -> Result<Vec<String>, FilterError> {
+    let filter = Filter::parse(expression)?;
+    let parser = Parser::new(html);
+    Ok(owned(parser.filter(&filter)))
+}
+
+/// Evaluate an XPath 1.0 expression against a page
+/// 
+/// This function supports location paths with every XPath axis, predicates,
+/// positional functions such as `position()` and `last()`, and the rest of the
+/// XPath 1.0 function library. See [`XPath`] for details.
+/// 
+/// # Arguments
+/// 
+/// * `html` - HTML content to parse
+/// * `expression` - An XPath expression (e.g., "//table[@id='prices']//tr/td[2]/text()")
+/// 
+/// # Returns
+/// 
+/// A vector of strings, or an [`XPathError`] if the expression is invalid:
+/// - for a node-set, one string per node: the HTML of each element, the text
+///   of each text node and the value of each attribute
+/// - for a string, number or boolean, a single string holding the value
+/// 
+/// # Examples
+/// 
+/// ```
+///     use tagparser::xpath;
+///
+///     let html = r#"
+///         <table id="prices">
+///             <tr><th>Item</th><th>Price</th></tr>
+///             <tr><td>Apple</td><td>1.20</td></tr>
+///             <tr><td>Pear</td><td>0.80</td></tr>
+///         </table>
+///     "#.to_string();
+///     
+///     let prices = xpath(html.clone(), "//table[@id='prices']//tr[position()>1]/td[2]/text()").unwrap();
+///     assert_eq!(vec!["1.20", "0.80"], prices);
+///     
+///     let rows = xpath(html, "count(//tr)").unwrap();
+///     assert_eq!(vec!["3"], rows);
+/// ```
+/// 
+/// # Common Use Cases
+/// 
+/// 1. Extract link targets by their text:
+///    ```
+///    # use tagparser::xpath;
+///    # let html = "<a href='/next'>Next page</a><a href='/prev'>Previous page</a>".to_string();
+///    let next = xpath(html, "//a[contains(., 'Next')]/@href").unwrap();
+///    // Returns: ["/next"]
+///    ```
+/// 
+/// 2. Find the last item of a list:
+///    ```
+///    # use tagparser::xpath;
+///    # let html = "<ul><li>Item 1</li><li>Item 2</li></ul>".to_string();
+///    let last = xpath(html, "//ul/li[last()]").unwrap();
+///    // Returns: ["<li>Item 2</li>"]
+///    ```
+pub fn xpath(html: String, expression: &str) -> Result<Vec<String>, XPathError> {
+    let parser = Parser::new(html);
+    parser.xpath(expression).map(owned)
+}
+
+/// Extract every table of a page as headers and rows
+///
+/// Cells spanning several columns or rows are repeated in each slot they
+/// cover, so every row has the same length, and tables nested in cells come
+/// back as tables of their own. See [`Table`] for how headers are found.
+///
+/// # Arguments
+///
+/// * `html` - HTML content to parse
+///
+/// # Returns
+///
+/// A vector of [`Table`]s in document order
+///
+/// # Examples
+///
+/// ```
+///     use tagparser::extract_tables;
+///
+///     let html = r#"
+///         <table id="prices">
+///             <thead><tr><th>Item</th><th>Price</th></tr></thead>
+///             <tr><td>Apple</td><td>1.20</td></tr>
+///             <tr><td>Pear, Conference</td><td>0.80</td></tr>
+///         </table>
+///     "#.to_string();
+///
+///     let tables = extract_tables(html);
+///     assert_eq!(vec!["Item", "Price"], tables[0].headers);
+///     assert_eq!(vec!["Apple", "1.20"], tables[0].rows[0]);
+///     assert_eq!("Item,Price\nApple,1.20\n\"Pear, Conference\",0.80\n", tables[0].to_csv());
+/// ```
+///
+/// # Common Use Cases
+///
+/// 1. Save a table as JSON:
+///    ```
+///    # use tagparser::extract_tables;
+///    # let html = "<table><tr><th>Name</th></tr><tr><td>Ann</td></tr></table>".to_string();
+///    let json = extract_tables(html)[0].to_json();
+///    // Returns: {"headers":["Name"],"rows":[["Ann"]]}
+///    ```
+pub fn extract_tables(html: String) -> Vec<Table> {
+    let parser = Parser::new(html);
+    parser.tables()
+}
+
+/// Extract every form of a page with its controls
+///
+/// Each [`Form`] holds the action, method and encoding type of a `<form>`, and
+/// every `<input>`, `<select>`, `<textarea>` and `<button>` that belongs to it,
+/// with its name, type, value, checked and disabled state and label.
+///
+/// # Arguments
+///
+/// * `html` - HTML content to parse
+///
+/// # Returns
+///
+/// A vector of [`Form`]s in document order
+///
+/// # Examples
+///
+/// ```
+///     use tagparser::extract_forms;
+///
+///     let html = r#"
+///         <form action="/subscribe" method="post">
+///             <label for="email">Email</label>
+///             <input type="email" id="email" name="email" required>
+///             <select name="plan"><option>Free</option><option>Pro</option></select>
+///             <input type="submit" value="Sign up">
+///         </form>
+///     "#.to_string();
+///
+///     let forms = extract_forms(html);
+///     assert_eq!("post", forms[0].method);
+///     assert_eq!("email", forms[0].controls[0].control_type);
+///     assert_eq!(Some("Email".to_string()), forms[0].controls[0].label);
+///     assert_eq!("email=&plan=Free", forms[0].to_urlencoded());
+/// ```
+///
+/// # Common Use Cases
+///
+/// 1. List the fields a form expects:
+///    ```
+///    # use tagparser::extract_forms;
+///    # let html = "<form><input name='user'><input type='password' name='pass'></form>".to_string();
+///    for control in &extract_forms(html)[0].controls {
+///        println!("{} ({})", control.name, control.control_type);
+///    }
+///    // Prints: user (text)
+///    //         pass (password)
+///    ```
+pub fn extract_forms(html: String) -> Vec<Form> {
+    let parser = Parser::new(html);
+    parser.forms()
+}
+
+/// Extract the metadata of a page for link previews and search listings
+///
+/// The title, description, keywords, canonical link, robots and viewport
+/// settings, declared charset and language, icons, and OpenGraph (`og:*`),
+/// Twitter card (`twitter:*`) and article (`article:*`) properties are
+/// collected in one pass over the page. See [`Metadata`] for details.
+///
+/// # Arguments
+///
+/// * `html` - HTML content to parse
+///
+/// # Returns
+///
+/// The [`Metadata`] of the page; fields the page doesn't set are `None` or empty
+///
+/// # Examples
+///
+/// ```
+///     use tagparser::extract_metadata;
+///
+///     let html = r#"
+///         <head>
+///             <meta charset="utf-8">
+///             <title>Release notes &middot; Example</title>
+///             <link rel="canonical" href="https://example.com/notes">
+///             <link rel="icon" href="/favicon.svg" type="image/svg+xml">
+///             <meta property="og:title" content="Release notes">
+///             <meta property="og:image" content="https://example.com/cover.png">
+///             <meta property="og:image:alt" content="Cover">
+///         </head>
+///     "#.to_string();
+///
+///     let metadata = extract_metadata(html);
+///     assert_eq!(Some("Release notes · Example".to_string()), metadata.title);
+///     assert_eq!(Some("utf-8".to_string()), metadata.charset);
+///     assert_eq!(Some("https://example.com/notes".to_string()), metadata.canonical);
+///     assert_eq!("/favicon.svg", metadata.icons[0].href);
+///     assert_eq!(Some("Release notes"), metadata.property("og:title"));
+///     assert_eq!(Some("Cover"), metadata.property("og:image:alt"));
+/// ```
+///
+/// # Common Use Cases
+///
+/// 1. Build a link preview, preferring OpenGraph over the plain tags:
+///    ```
+///    # use tagparser::extract_metadata;
+///    # let html = "<title>Page</title><meta name='description' content='About the page'>".to_string();
+///    let metadata = extract_metadata(html);
+///    let title = metadata.property("og:title").or(metadata.title.as_deref());
+///    let summary = metadata.property("og:description").or(metadata.description.as_deref());
+///    // Returns: Some("Page"), Some("About the page")
+///    ```
+pub fn extract_metadata(html: String) -> Metadata {
+    let parser = Parser::new(html);
+    parser.metadata()
+}
+
+/// Check HTML for markup the parser had to recover from
+/// 
+/// Parsing never fails: misnested tags such as `<b><i></b></i>`, stray end tags,
+/// unterminated attribute values and other mistakes are repaired the way browsers
+/// repair them. This function lists each repair with its kind, a message and its
+/// position in the source, so it can be used to validate generated HTML.
+/// 
+/// # Arguments
+/// 
+/// * `html` - HTML content to check
+/// 
+/// # Returns
+/// 
+/// A vector of [`ParseDiagnostic`]s in source order; empty if the HTML is well-formed
+/// 
+/// # Examples
+/// 
+/// ```
+///     use tagparser::{validate, DiagnosticKind};
+///
+///     let html = "<ul>\n<li><a href='/one'>One</a>\n<li><a href='/two\">Two</a>\n</ul>".to_string();
+///     
+///     // The quote left open swallows the rest of the page, so the list is never closed
+///     let diagnostics = validate(html);
+///     assert_eq!(2, diagnostics.len());
+///     assert_eq!("1:1: <ul> is never closed", diagnostics[0].to_string());
+///     assert_eq!(DiagnosticKind::UnterminatedTag, diagnostics[1].kind);
+///     assert_eq!(3, diagnostics[1].location.start.line);
+///     
+///     assert!(validate("<p>Fine</p>".to_string()).is_empty());
+/// ```
+/// 
+/// # Common Use Cases
+/// 
+/// 1. Print a report in `line:column: message` form:
+///    ```
+///    # use tagparser::validate;
+///    # let html = "<div><span>Text</div>".to_string();
+///    for diagnostic in validate(html) {
+///        println!("{}", diagnostic);
+///    }
+///    // Prints: 1:6: <span> is closed by </div> before its own end tag
+///    ```
+pub fn validate(html: String) -> Vec<ParseDiagnostic> {
+    let parser = Parser::new(html);
+    parser.diagnostics()
+}
+
+/// Copies the results of a parser that is about to be dropped out of it
+fn owned<S: Into<String>>(values: Vec<S>) -> Vec<String> {
+    values.into_iter().map(Into::into).collect()
 }
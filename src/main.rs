@@ -1,7 +1,9 @@
-pub mod parser;
-use crate::parser::Parser;
+use tagparser::{AttrMatch, Error, Filter, Parser, StreamParser, Table};
 use std::env;
-use std::fs;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::process;
 
 /// Tagparser CLI tool
 /// 
@@ -23,11 +25,42 @@ use std::fs;
 /// # Extract content - extract only the text content inside tags
 /// tagparser "<html>...</html>" "a" "--content"
 /// 
+/// # Extract plain text - strip nested tags, scripts and styles, decode entities
+/// tagparser "<html>...</html>" "p" "--text"
+/// 
 /// # Extract attribute values - extract values of a specific attribute
 /// tagparser "<html>...</html>" "a" "href" "--attr-values"
 /// 
 /// # Read HTML from file
 /// tagparser --file "path/to/file.html" "a"
+/// 
+/// # Read HTML from standard input
+/// cat "path/to/file.html" | tagparser --file - "a"
+/// 
+/// # Filter by an attribute test - equals, prefix, suffix, contains, word, dash or regex
+/// tagparser "<html>...</html>" "a" "href" --match prefix "https://"
+/// 
+/// # Make the attribute test ignore case
+/// tagparser "<html>...</html>" "img" "src" --match suffix ".png" --ignore-case
+/// 
+/// # Filter by an expression over tag names, attributes, text and depth
+/// tagparser "<html>...</html>" --filter "tag=a and @rel=external and not @target=_blank"
+/// 
+/// # Print the tables of a page as CSV or JSON, optionally only those matching a CSS selector
+/// tagparser --file "path/to/file.html" --table csv
+/// tagparser --file "path/to/file.html" "table#prices" --table json
+/// 
+/// # Match tag and attribute names case-sensitively, as in XML
+/// tagparser --file "path/to/file.xml" "Item" --case-sensitive
+/// 
+/// # Read a file in a given encoding, unless it declares its own
+/// tagparser --file "path/to/file.html" "a" --encoding windows-1251
+/// 
+/// # Show where each match starts, one `file:line:col: match` per line
+/// tagparser --file "path/to/file.html" "a" --positions
+/// 
+/// # Check the HTML for malformed markup, one `file:line:col: problem` per line
+/// tagparser --file "path/to/file.html" --validate
 /// ```
 /// 
 /// # Examples
@@ -56,83 +89,505 @@ use std::fs;
 ///    ```
 ///    Output: `["Example", "Home"]`
 /// 
-/// 5. Extract href values from links:
+/// 5. Extract the plain text of paragraphs:
+///    ```bash
+///    tagparser "<p>This is <b>bold</b> &amp; plain</p>" "p" "--text"
+///    ```
+///    Output: `["This is bold & plain"]`
+/// 
+/// 6. Extract href values from links:
 ///    ```bash
 ///    tagparser "<a href='https://example.com'>Example</a><a href='https://github.com'>GitHub</a>" "a" "href" "--attr-values"
 ///    ```
 ///    Output: `["https://example.com", "https://github.com"]`
 ///
-/// 6. Read HTML from file:
+/// 7. Read HTML from file:
 ///    ```bash
 ///    tagparser --file "index.html" "a" "href" "--attr-values"
 ///    ```
 ///    Output: `["https://example.com", "https://github.com"]`
+///
+///    Tag, attribute and attribute value queries on a file are streamed: the
+///    file is read a chunk at a time and never held in memory as a whole, so
+///    it can be larger than the available memory.
+///
+///    Files may be in any encoding. It is taken from a byte order mark or a
+///    `<meta charset>` declaration, or from `--encoding <label>` when the page
+///    doesn't start with a byte order mark; pages that declare nothing are read
+///    as UTF-8, or as windows-1252 if they aren't valid UTF-8.
+///
+/// 8. Extract links to secure sites only:
+///    ```bash
+///    tagparser "<a href='https://example.com'>A</a><a href='http://example.org'>B</a>" "a" "href" --match prefix "https://"
+///    ```
+///    Output: `["<a href='https://example.com'>A</a>"]`
+///
+///    With `--attr-values`, only the values that pass the test are printed.
+///
+/// 9. Show the line and column of each match:
+///    ```bash
+///    tagparser --file "index.html" "a" "href" "--attr-values" "--positions"
+///    ```
+///    Output:
+///    ```text
+///    index.html:12:5: "https://example.com"
+///    index.html:13:5: "https://github.com"
+///    ```
+///
+/// 10. Combine tests on several attributes in one filter:
+///    ```bash
+///    tagparser --file "index.html" --filter "tag=a and @class~=nav and (@rel=external or @href^=https://)"
+///    ```
+///    Output: `["<a class=\"nav\" rel=\"external\" href=\"/out\">Out</a>"]`
+///
+///    See `Filter::parse` for the full expression syntax.
+///
+/// 11. Save a table as CSV:
+///    ```bash
+///    tagparser --file "index.html" "table#prices" --table csv > prices.csv
+///    ```
+///    Output:
+///    ```text
+///    Item,Price
+///    Apple,1.20
+///    ```
+///
+/// 12. Check a page for malformed markup; the exit status is 1 if anything was found:
+///    ```bash
+///    tagparser --file "index.html" --validate
+///    ```
+///    Output:
+///    ```text
+///    index.html:14:9: <i> is closed by </b> before its own end tag
+///    index.html:20:1: </span> doesn't close any open element and is ignored
+///    ```
 pub fn main() {
-    let args: Vec<String> = env::args().collect();
+    let mut args: Vec<String> = env::args().collect();
+    
+    // `--positions` may appear anywhere after the program name
+    let positions = args.iter().skip(1).any(|arg| arg == "--positions");
+    args.retain(|arg| arg != "--positions");
     
-    if args.len() < 3 {
+    // Tag and attribute names ignore ASCII case unless `--case-sensitive` is given
+    let case_sensitive = args.iter().skip(1).any(|arg| arg == "--case-sensitive");
+    args.retain(|arg| arg != "--case-sensitive");
+    
+    // `--validate` takes no tag, only the HTML or file to check
+    let validate = args.iter().skip(1).any(|arg| arg == "--validate");
+    args.retain(|arg| arg != "--validate");
+    
+    // `--encoding <label>` may also appear anywhere, and only applies to files
+    let encoding = match args.iter().skip(1).position(|arg| arg == "--encoding") {
+        Some(index) if index + 2 < args.len() => {
+            args.remove(index + 1);
+            Some(args.remove(index + 1))
+        }
+        Some(_) => {
+            print_usage();
+            return;
+        }
+        None => None,
+    };
+    
+    // `--match <op> <value>` tests the attribute value, and `--ignore-case` relaxes it
+    let ignore_case = args.iter().skip(1).any(|arg| arg == "--ignore-case");
+    args.retain(|arg| arg != "--ignore-case");
+    let matcher = match args.iter().skip(1).position(|arg| arg == "--match") {
+        Some(index) if index + 3 < args.len() => {
+            let value = args.remove(index + 3);
+            let operator = args.remove(index + 2);
+            args.remove(index + 1);
+            match attr_match(&operator, value) {
+                Ok(matcher) if ignore_case => Some(matcher.ignore_case()),
+                Ok(matcher) => Some(matcher),
+                Err(e) => {
+                    println!("Error: {}", e);
+                    return;
+                }
+            }
+        }
+        Some(_) => {
+            print_usage();
+            return;
+        }
+        None => None,
+    };
+    
+    // `--filter <expression>` takes the place of the tag and attribute arguments
+    let filter = match args.iter().skip(1).position(|arg| arg == "--filter") {
+        Some(index) if index + 2 < args.len() => {
+            args.remove(index + 1);
+            match Filter::parse(&args.remove(index + 1)) {
+                Ok(filter) => Some(filter),
+                Err(e) => {
+                    println!("Error: {}", e);
+                    return;
+                }
+            }
+        }
+        Some(_) => {
+            print_usage();
+            return;
+        }
+        None => None,
+    };
+    
+    // `--table <format>` prints tables, optionally only those matching a CSS selector
+    let table_format = match args.iter().skip(1).position(|arg| arg == "--table") {
+        Some(index) if index + 2 < args.len() => {
+            args.remove(index + 1);
+            match args.remove(index + 1).as_str() {
+                "csv" => Some(TableFormat::Csv),
+                "json" => Some(TableFormat::Json),
+                format => {
+                    println!("Error: unknown table format {:?}; expected csv or json", format);
+                    return;
+                }
+            }
+        }
+        Some(_) => {
+            print_usage();
+            return;
+        }
+        None => None,
+    };
+    
+    // An attribute test needs the name of the attribute to test
+    let required_args = if validate || filter.is_some() || table_format.is_some() {
+        2
+    } else if matcher.is_some() {
+        4
+    } else {
+        3
+    };
+    if args.len() < required_args {
         print_usage();
         return;
     }
     
-    let html_content;
+    let mut file_content = None;
     let mut tag_index = 2;
+    let mut source_name = "<html>".to_string();
     
     // Check if we're reading from a file
     if args[1] == "--file" {
-        if args.len() < 4 {
+        if args.len() < required_args + 1 {
             print_usage();
             return;
         }
         
         let file_path = &args[2];
-        match fs::read_to_string(file_path) {
+        
+        // Queries that don't need the whole tree are answered while reading
+        if !validate && !positions && filter.is_none() && table_format.is_none() && is_streamable(&args[3..]) {
+            stream_file(file_path, &args[3..], encoding.as_deref(), case_sensitive, matcher.as_ref());
+            return;
+        }
+        
+        match read_input(file_path) {
             Ok(content) => {
-                html_content = content;
+                file_content = Some(content);
                 tag_index = 3;
+                source_name = file_path.clone();
             },
             Err(e) => {
                 println!("Error reading file: {}", e);
                 return;
             }
         }
-    } else {
-        html_content = args[1].clone();
+    }
+    
+    let parser = match &file_content {
+        Some(bytes) => Parser::from_bytes(bytes, encoding.as_deref()),
+        None => Parser::new(args[1].as_str()),
+    };
+    let parser = parser.case_sensitive(case_sensitive);
+    
+    if validate {
+        let diagnostics = parser.diagnostics();
+        for diagnostic in &diagnostics {
+            println!("{}:{}: {}", source_name, diagnostic.location.start, diagnostic.message);
+        }
+        if !diagnostics.is_empty() {
+            process::exit(1);
+        }
+        return;
+    }
+    
+    if let Some(filter) = &filter {
+        if positions {
+            for tag in parser.filter_tags(filter) {
+                println!("{}:{}: {:?}", source_name, tag.start_tag.start, tag.outer_html);
+            }
+        } else {
+            println!("{:?}", parser.filter(filter));
+        }
+        return;
+    }
+    
+    if let Some(format) = table_format {
+        let tables = match args.get(tag_index) {
+            Some(selector) => match parser.select_tables(selector) {
+                Ok(tables) => tables,
+                Err(e) => {
+                    println!("Error: {}", e);
+                    return;
+                }
+            },
+            None => parser.tables(),
+        };
+        print_tables(&tables, format);
+        return;
     }
     
     let tag = &args[tag_index];
     
-    let mut parser = Parser::new(html_content);
+    if positions {
+        print_positions(&source_name, &parser, &args[tag_index..], matcher.as_ref(), case_sensitive);
+        return;
+    }
     
     if args.len() > tag_index + 1 {
         if args[tag_index + 1] == "--content" {
             // Extract content from tags
-            println!("{:?}", parser.extract_tag_content(tag.to_string()));
+            print_result(parser.try_extract_tag_content(tag));
+        } else if args[tag_index + 1] == "--text" {
+            // Extract plain text from tags
+            println!("{:?}", parser.text_content(tag));
         } else if args.len() > tag_index + 2 && args[tag_index + 2] == "--attr-values" {
             // Extract attribute values
             let attr_name = &args[tag_index + 1];
-            println!("{:?}", parser.extract_attribute_values(tag.to_string(), attr_name));
+            let values = parser.try_extract_attribute_values(tag, attr_name);
+            print_result(values.map(|values| match &matcher {
+                Some(matcher) => values.into_iter().filter(|value| matcher.matches(value)).collect(),
+                None => values,
+            }));
         } else {
             // Filter by attribute
             let attr_name = &args[tag_index + 1];
+            if let Some(matcher) = &matcher {
+                print_result(parser.try_parse_tags_matching(tag, attr_name, matcher));
+                return;
+            }
             let attr_value = if args.len() > tag_index + 2 && args[tag_index + 2] != "--attr-values" { 
                 Some(args[tag_index + 2].as_str()) 
             } else { 
                 None 
             };
             
-            println!("{:?}", parser.parse_tags_with_attr(tag.to_string(), attr_name, attr_value));
+            print_result(parser.try_parse_tags_with_attr(tag, attr_name, attr_value));
+        }
+    } else {
+        print_result(parser.try_parse_tags(tag));
+    }
+}
+
+/// Reads a whole file, or standard input if `path` is `-`
+fn read_input(path: &str) -> io::Result<Vec<u8>> {
+    if path == "-" {
+        let mut content = Vec::new();
+        io::stdin().read_to_end(&mut content)?;
+        Ok(content)
+    } else {
+        fs::read(path)
+    }
+}
+
+/// Builds the attribute test named by `--match <operator> <value>`
+fn attr_match(operator: &str, value: String) -> Result<AttrMatch, String> {
+    match operator {
+        "equals" => Ok(AttrMatch::Equals(value)),
+        "prefix" => Ok(AttrMatch::Prefix(value)),
+        "suffix" => Ok(AttrMatch::Suffix(value)),
+        "contains" => Ok(AttrMatch::Contains(value)),
+        "word" => Ok(AttrMatch::Word(value)),
+        "dash" => Ok(AttrMatch::DashPrefix(value)),
+        "regex" => AttrMatch::regex(&value).map_err(|e| e.to_string()),
+        _ => Err(format!(
+            "unknown match operator {:?}; expected equals, prefix, suffix, contains, word, dash or regex",
+            operator
+        )),
+    }
+}
+
+/// Returns `true` for the queries a [`StreamParser`] can answer: tags by
+/// name or attribute, and attribute values
+fn is_streamable(args: &[String]) -> bool {
+    !args.iter().any(|arg| arg == "--content" || arg == "--text")
+}
+
+/// Answers a query while reading the file, printing each match as soon as it
+/// is found in the same format as [`print_result`]
+fn stream_file(
+    file_path: &str,
+    args: &[String],
+    encoding: Option<&str>,
+    case_sensitive: bool,
+    matcher: Option<&AttrMatch>,
+) {
+    let reader: Box<dyn Read> = if file_path == "-" {
+        Box::new(io::stdin().lock())
+    } else {
+        match File::open(file_path) {
+            Ok(file) => Box::new(file),
+            Err(e) => {
+                println!("Error reading file: {}", e);
+                return;
+            }
         }
+    };
+    
+    let mut parser = StreamParser::new(reader).case_sensitive(case_sensitive);
+    if let Some(label) = encoding {
+        parser = parser.encoding_hint(label);
+    }
+    let tag = &args[0];
+    match (args.get(1), matcher) {
+        (Some(attr_name), matcher) if args.get(2).is_some_and(|arg| arg == "--attr-values") => {
+            let values = parser.try_extract_attribute_values(tag, attr_name);
+            // Only the values that pass the test are printed
+            print_stream(values.map(|values| {
+                values.filter(move |value| match (value, matcher) {
+                    (Ok(value), Some(matcher)) => matcher.matches(value),
+                    _ => true,
+                })
+            }));
+        }
+        (Some(attr_name), Some(matcher)) => print_stream(parser.try_parse_tags_matching(tag, attr_name, matcher)),
+        (Some(attr_name), None) => {
+            print_stream(parser.try_parse_tags_with_attr(tag, attr_name, args.get(2).map(String::as_str)))
+        }
+        (None, _) => print_stream(parser.try_parse_tags(tag)),
+    }
+}
+
+/// Prints matches as they are read, or why the tag or attribute name was rejected
+fn print_stream(matches: Result<impl Iterator<Item = io::Result<String>>, Error>) {
+    let matches = match matches {
+        Ok(matches) => matches,
+        Err(e) => {
+            println!("Error: {}", e);
+            return;
+        }
+    };
+    
+    let mut stdout = io::stdout().lock();
+    let mut separator = "";
+    let _ = write!(stdout, "[");
+    for found in matches {
+        match found {
+            Ok(found) => {
+                let _ = write!(stdout, "{}{:?}", separator, found);
+                separator = ", ";
+            }
+            Err(e) => {
+                let _ = writeln!(stdout);
+                let _ = writeln!(stdout, "Error reading file: {}", e);
+                return;
+            }
+        }
+    }
+    let _ = writeln!(stdout, "]");
+}
+
+/// Prints the matches, or why the tag or attribute name was rejected
+fn print_result<T: fmt::Debug>(result: Result<Vec<T>, Error>) {
+    match result {
+        Ok(matches) => println!("{:?}", matches),
+        Err(e) => println!("Error: {}", e),
+    }
+}
+
+/// How `--table` writes tables out
+#[derive(Clone, Copy)]
+enum TableFormat {
+    Csv,
+    Json,
+}
+
+/// Prints the tables as CSV separated by blank lines, or as one JSON array
+fn print_tables(tables: &[Table], format: TableFormat) {
+    match format {
+        TableFormat::Csv => {
+            let csv: Vec<String> = tables.iter().map(Table::to_csv).collect();
+            print!("{}", csv.join("\n"));
+        }
+        TableFormat::Json => {
+            let json: Vec<String> = tables.iter().map(Table::to_json).collect();
+            println!("[{}]", json.join(","));
+        }
+    }
+}
+
+/// Prints each match as `source:line:col: match`, using the position of the
+/// element's start tag
+fn print_positions(source_name: &str, parser: &Parser, args: &[String], matcher: Option<&AttrMatch>, case_sensitive: bool) {
+    let tag = args[0].as_str();
+    let content = args.len() > 1 && args[1] == "--content";
+    let text = args.len() > 1 && args[1] == "--text";
+    let attr_values = args.len() > 2 && args[2] == "--attr-values";
+    
+    // Plain text comes from the same elements as `find_tags`, in the same order
+    let texts = if text { parser.text_content(tag) } else { Vec::new() };
+    
+    let tags = if content || text {
+        parser.find_tags(tag)
+    } else if let (Some(attr_name), Some(matcher)) = (args.get(1), matcher) {
+        parser.find_tags_matching(tag, attr_name, matcher)
+    } else if attr_values {
+        parser.find_tags_with_attr(tag, &args[1], None)
     } else {
-        println!("{:?}", parser.parse_tags(tag.to_string()));
+        match args.get(1) {
+            Some(attr_name) => parser.find_tags_with_attr(tag, attr_name, args.get(2).map(String::as_str)),
+            None => parser.find_tags(tag),
+        }
+    };
+    
+    for (index, tag) in tags.iter().enumerate() {
+        let value = if text {
+            texts[index].as_str()
+        } else if content {
+            // Void and self-closing tags, which are nothing but their start tag,
+            // have no content to extract
+            if tag.end_tag.is_none() && tag.span == tag.start_tag.span() {
+                continue;
+            }
+            tag.inner_html.as_str()
+        } else if attr_values {
+            // Look the attribute up the same way the tags were matched
+            let attribute = tag.attributes.iter().find(|(name, _)| {
+                if case_sensitive { *name == args[1] } else { name.eq_ignore_ascii_case(&args[1]) }
+            });
+            match attribute {
+                Some((_, value)) => value.as_str(),
+                None => continue,
+            }
+        } else {
+            tag.outer_html.as_str()
+        };
+        println!("{}:{}: {:?}", source_name, tag.start_tag.start, value);
     }
 }
 
 fn print_usage() {
     println!("Usage: tagparser <html> <tag> [attr_name] [attr_value]");
     println!("       tagparser <html> <tag> --content");
+    println!("       tagparser <html> <tag> --text");
     println!("       tagparser <html> <tag> <attr_name> --attr-values");
     println!("       tagparser --file <path> <tag> [attr_name] [attr_value]");
     println!("       tagparser --file <path> <tag> --content");
+    println!("       tagparser --file <path> <tag> --text");
     println!("       tagparser --file <path> <tag> <attr_name> --attr-values");
+    println!("       tagparser <html> --filter <expression>");
+    println!("       tagparser --file <path> --filter <expression>");
+    println!("       tagparser <html> [selector] --table <csv|json>");
+    println!("       tagparser --file <path> [selector] --table <csv|json>");
+    println!("       tagparser <html> --validate");
+    println!("       tagparser --file <path> --validate");
+    println!("Use --file - to read the HTML from standard input");
+    println!("Add --match <equals|prefix|suffix|contains|word|dash|regex> <value> after <attr_name> to test its value,");
+    println!("    and --ignore-case to make the test ignore case");
+    println!("Add --case-sensitive to match tag and attribute names exactly, as in XML");
+    println!("Add --encoding <label> to read a file that doesn't declare its encoding");
+    println!("Add --positions to print each match as <file>:<line>:<col>: <match>");
 }
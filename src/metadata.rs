@@ -0,0 +1,276 @@
+//! Extraction of the metadata of a page: its title, `<meta>` tags, canonical
+//! link, icons and OpenGraph, Twitter card and article properties
+//!
+//! URLs are returned as written in the page, without resolving them against
+//! the page's address or a `<base>` element.
+
+use std::borrow::Cow;
+use std::mem;
+
+use crate::dom::{Document, Node};
+use crate::encoding::charset_label;
+use crate::parser::attribute_value;
+
+/// Prefixes of the `<meta>` properties collected in [`Metadata::properties`]
+const PROPERTY_PREFIXES: &[&str] = &["og:", "twitter:", "article:"];
+
+/// The metadata of a page, as used for link previews and search listings
+///
+/// Each field holds the first matching tag found anywhere in the document;
+/// names of `<meta>` tags and `rel` values of `<link>` tags are compared
+/// ASCII case-insensitively.
+///
+/// # Examples
+///
+/// ```
+/// use tagparser::extract_metadata;
+///
+/// let html = r#"
+///     <html lang="en">
+///     <head>
+///         <title>Rust Programming Language</title>
+///         <meta name="description" content="A language empowering everyone.">
+///         <meta property="og:image" content="https://example.com/a.png">
+///         <meta property="og:image:width" content="1200">
+///         <meta property="og:image" content="https://example.com/b.png">
+///         <meta name="twitter:card" content="summary_large_image">
+///     </head>
+///     </html>
+/// "#.to_string();
+/// let metadata = extract_metadata(html);
+///
+/// assert_eq!(metadata.title.as_deref(), Some("Rust Programming Language"));
+/// assert_eq!(metadata.lang.as_deref(), Some("en"));
+/// assert_eq!(metadata.property("twitter:card"), Some("summary_large_image"));
+/// assert_eq!(metadata.property("og:image:width"), Some("1200"));
+///
+/// let images: Vec<_> = metadata.properties_named("og:image").collect();
+/// assert_eq!(images.len(), 2);
+/// assert_eq!(images[0].property("width"), Some("1200"));
+/// assert_eq!(images[1].content, "https://example.com/b.png");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Metadata {
+    /// The text of the `<title>`, with whitespace collapsed
+    pub title: Option<String>,
+    /// The `content` of `<meta name="description">`
+    pub description: Option<String>,
+    /// The comma-separated `content` of `<meta name="keywords">`, trimmed
+    pub keywords: Vec<String>,
+    /// The `href` of `<link rel="canonical">`
+    pub canonical: Option<String>,
+    /// The `content` of `<meta name="robots">`, such as `noindex, nofollow`
+    pub robots: Option<String>,
+    /// The `content` of `<meta name="viewport">`
+    pub viewport: Option<String>,
+    /// The encoding declared by `<meta charset>`, or by the `charset` in the
+    /// `content` of `<meta http-equiv="Content-Type">`, as written
+    pub charset: Option<String>,
+    /// The `lang` of the `<html>` element, or else the `content` of
+    /// `<meta http-equiv="Content-Language">`
+    pub lang: Option<String>,
+    /// The `<link>` tags whose `rel` includes an icon, in document order
+    pub icons: Vec<Icon>,
+    /// The `og:*`, `twitter:*` and `article:*` properties in document order,
+    /// with structured properties such as `og:image:width` attached to the
+    /// property they describe
+    pub properties: Vec<Property>,
+}
+
+/// An icon of a page, from a `<link rel="icon">`, `<link rel="apple-touch-icon">`
+/// or similar tag
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Icon {
+    /// The `href` of the link
+    pub href: String,
+    /// The `rel` of the link, lowercased with whitespace collapsed, such as `shortcut icon`
+    pub rel: String,
+    /// The `sizes` attribute, such as `32x32` or `any`
+    pub sizes: Option<String>,
+    /// The `type` attribute, such as `image/png`
+    pub mime_type: Option<String>,
+}
+
+/// A `<meta>` property such as `og:title` or `twitter:image`, from the
+/// `property` or `name` attribute and the `content` of the tag
+///
+/// A property repeated in a page, such as `og:image`, appears once for each
+/// tag. The structured properties that follow one, such as `og:image:width`
+/// and `og:image:alt`, are kept with it in [`Property::properties`], and so
+/// are those written before the first property they describe.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Property {
+    /// The name of the property, lowercased, such as `og:image`
+    pub name: String,
+    /// The `content` of the tag
+    pub content: String,
+    /// The structured properties describing this one, named without the
+    /// prefix, such as `("width", "1200")` for `og:image:width`
+    pub properties: Vec<(String, String)>,
+}
+
+impl Metadata {
+    /// The content of the first property called `name`
+    ///
+    /// A structured property such as `og:image:width` is looked up on the
+    /// first property it describes when it isn't found on its own.
+    pub fn property(&self, name: &str) -> Option<&str> {
+        if let Some(property) = self.properties_named(name).next() {
+            return Some(&property.content);
+        }
+        let (parent, key) = name.rsplit_once(':')?;
+        self.properties_named(parent).next()?.property(key)
+    }
+
+    /// Every property called `name`, in document order
+    pub fn properties_named<'s: 'q, 'q>(&'s self, name: &'q str) -> impl Iterator<Item = &'s Property> + 'q {
+        self.properties.iter().filter(move |property| property.name.eq_ignore_ascii_case(name))
+    }
+}
+
+impl Property {
+    /// The first structured property called `name`, without this property's
+    /// name in front, such as `width` for `og:image:width`
+    pub fn property(&self, name: &str) -> Option<&str> {
+        self.properties
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+impl From<&Document<'_>> for Metadata {
+    /// Collects the metadata of a document
+    fn from(document: &Document<'_>) -> Self {
+        Metadata::new(document, true)
+    }
+}
+
+impl Metadata {
+    /// Collects the metadata of a document, decoding character references in
+    /// values and text if `decode` is set
+    pub(crate) fn new(document: &Document<'_>, decode: bool) -> Metadata {
+        let mut metadata = Metadata::default();
+        let mut content_language = None;
+
+        for node in document.elements() {
+            let attribute = |name: &str| attribute_value(&node, name, decode, false).map(Cow::into_owned);
+            match node.name().unwrap_or_default().to_ascii_lowercase().as_str() {
+                "html" => {
+                    metadata.lang = metadata.lang.or_else(|| attribute("lang"));
+                }
+                "title" if metadata.title.is_none() => {
+                    let text = if decode { node.text() } else { node.raw_text() };
+                    metadata.title = Some(collapse_whitespace(&text));
+                }
+                "link" => {
+                    let rel = collapse_whitespace(&attribute("rel").unwrap_or_default().to_ascii_lowercase());
+                    let Some(href) = attribute("href") else {
+                        continue;
+                    };
+                    if rel.split(' ').any(|token| token == "canonical") {
+                        metadata.canonical = metadata.canonical.or(Some(href.clone()));
+                    }
+                    if rel.split(' ').any(|token| token.ends_with("icon") || token.starts_with("apple-touch-icon")) {
+                        let (sizes, mime_type) = (attribute("sizes"), attribute("type"));
+                        metadata.icons.push(Icon { href, rel, sizes, mime_type });
+                    }
+                }
+                "meta" => metadata.add_meta(node, decode, &mut content_language),
+                _ => {}
+            }
+        }
+        metadata.lang = metadata.lang.or(content_language);
+        metadata
+    }
+
+    /// Takes what a `<meta>` tag holds into the metadata
+    fn add_meta(&mut self, meta: Node<'_>, decode: bool, content_language: &mut Option<String>) {
+        let attribute = |name: &str| attribute_value(&meta, name, decode, false).map(Cow::into_owned);
+        if let Some(charset) = attribute("charset") {
+            self.charset = self.charset.take().or(Some(charset.trim().to_string()));
+        }
+        let Some(content) = attribute("content") else {
+            return;
+        };
+
+        if let Some(http_equiv) = attribute("http-equiv") {
+            if http_equiv.trim().eq_ignore_ascii_case("content-type") && self.charset.is_none() {
+                let label = charset_label(content.as_bytes());
+                self.charset = label.map(|label| String::from_utf8_lossy(label).into_owned());
+            } else if http_equiv.trim().eq_ignore_ascii_case("content-language") && content_language.is_none() {
+                *content_language = Some(content.trim().to_string());
+            }
+            return;
+        }
+
+        // OpenGraph uses `property`, Twitter cards mostly `name`, and pages mix them up
+        let name = attribute("property")
+            .filter(|property| is_property(property))
+            .or_else(|| attribute("name"))
+            .map(|name| name.trim().to_ascii_lowercase())
+            .unwrap_or_default();
+        let field = match name.as_str() {
+            "description" => &mut self.description,
+            "robots" => &mut self.robots,
+            "viewport" => &mut self.viewport,
+            "keywords" if self.keywords.is_empty() => {
+                let keywords = content.split(',').map(str::trim).filter(|keyword| !keyword.is_empty());
+                self.keywords = keywords.map(str::to_string).collect();
+                return;
+            }
+            name if is_property(name) => {
+                self.add_property(name.to_string(), content);
+                return;
+            }
+            _ => return,
+        };
+        if field.is_none() {
+            *field = Some(content);
+        }
+    }
+
+    /// Adds a property, or attaches it to the latest property it describes
+    fn add_property(&mut self, name: String, content: String) {
+        if let Some((parent, key)) = name.rsplit_once(':') {
+            if let Some(described) = self.properties.iter_mut().rev().find(|property| property.name == parent) {
+                described.properties.push((key.to_string(), content));
+                return;
+            }
+            // `og:image:url` means the same as `og:image`
+            if key == "url" && is_property(parent) {
+                self.push_property(parent.to_string(), content);
+                return;
+            }
+        }
+        self.push_property(name, content);
+    }
+
+    /// Adds a property, taking in the structured properties that describe it
+    /// but came before it, and so were added on their own
+    fn push_property(&mut self, name: String, content: String) {
+        let mut properties = Vec::new();
+        if !self.properties.iter().any(|property| property.name == name) {
+            let prefix = format!("{}:", name);
+            let (described, others): (Vec<Property>, Vec<Property>) = mem::take(&mut self.properties)
+                .into_iter()
+                .partition(|property| property.name.strip_prefix(&prefix).is_some_and(|key| !key.contains(':')));
+            self.properties = others;
+            properties = described
+                .into_iter()
+                .map(|property| (property.name[prefix.len()..].to_string(), property.content))
+                .collect();
+        }
+        self.properties.push(Property { name, content, properties });
+    }
+}
+
+fn is_property(name: &str) -> bool {
+    PROPERTY_PREFIXES
+        .iter()
+        .any(|prefix| name.len() > prefix.len() && name.as_bytes()[..prefix.len()].eq_ignore_ascii_case(prefix.as_bytes()))
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_ascii_whitespace().collect::<Vec<_>>().join(" ")
+}
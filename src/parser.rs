@@ -1,12 +1,217 @@
-use regex::Regex;
+use std::borrow::Cow;
 
-pub struct Parser {
-    html: String,
+use crate::attr_match::AttrMatch;
+use crate::dom::{Document, Node, NodeId};
+use crate::encoding::{self, DetectedEncoding};
+use crate::entities::decode_attribute;
+use crate::error::{check_attribute_name, check_tag_name, Error};
+use crate::filter::Filter;
+use crate::form::Form;
+use crate::metadata::Metadata;
+use crate::parser::diagnostics::ParseDiagnostic;
+use crate::selector::{Selector, SelectorError};
+use crate::table::Table;
+use crate::tag::Tag;
+use crate::text;
+use crate::xpath::{Value, XPathError, XPathNode};
+
+pub mod diagnostics;
+mod index;
+pub(crate) mod open_elements;
+pub mod tokenizer;
+pub(crate) mod tree_builder;
+
+use index::ElementIndex;
+
+/// Extracts tags, text and attribute values from an HTML page
+///
+/// The HTML is parsed once, in [`Parser::new`], into a [`Document`] along with
+/// an index of its elements by tag name, `id` and class. Every query reuses
+/// them, so running many queries against the same page costs little more than
+/// running one. Queries take `&self`, and a `Parser` is `Send` and `Sync`, so
+/// one parsed page can be shared between threads.
+///
+/// A `Parser<'a>` created from a `&'a str` borrows the HTML instead of copying
+/// it, and results are slices of the source: HTML comes back as `&str`, and
+/// attribute values as [`Cow`]s that are only owned when decoding character
+/// references changed the text.
+///
+/// # Examples
+///
+/// ```
+/// # use tagparser::parser::Parser;
+/// use std::borrow::Cow;
+///
+/// let html = "<a href='/one'>One</a><a href='/a&amp;b'>Two</a>";
+/// let parser = Parser::new(html);
+///
+/// let links: Vec<&str> = parser.parse_tags("a");
+/// assert_eq!(links, vec!["<a href='/one'>One</a>", "<a href='/a&amp;b'>Two</a>"]);
+///
+/// let hrefs = parser.extract_attribute_values("a", "href");
+/// assert!(matches!(hrefs[0], Cow::Borrowed("/one")));
+/// assert!(matches!(&hrefs[1], Cow::Owned(href) if href == "/a&b"));
+/// ```
+///
+/// Queries can run from several threads at once:
+///
+/// ```
+/// # use tagparser::parser::Parser;
+/// use std::thread;
+///
+/// let parser = Parser::new("<a href='/one'>One</a><p class='intro'>Hello</p>".to_string());
+///
+/// thread::scope(|scope| {
+///     scope.spawn(|| assert_eq!(parser.extract_attribute_values("a", "href"), vec!["/one"]));
+///     scope.spawn(|| assert_eq!(parser.find_by_class("intro")[0].text, "Hello"));
+/// });
+/// ```
+pub struct Parser<'a> {
+    document: Document<'a>,
+    index: ElementIndex,
+    decode: bool,
+    case_sensitive: bool,
+    encoding: Option<DetectedEncoding>,
 }
 
-impl Parser {
-    pub fn new(html: String) -> Self {
-        Parser { html }
+impl<'a> Parser<'a> {
+    /// Parses `html` and indexes its elements
+    ///
+    /// `html` may be a `&'a str`, which the parser borrows, or a `String`,
+    /// which it takes ownership of. Either way the HTML isn't copied.
+    pub fn new(html: impl Into<Cow<'a, str>>) -> Self {
+        let document = Document::from_source(html.into());
+        let index = ElementIndex::new(&document);
+        Parser { document, index, decode: true, case_sensitive: false, encoding: None }
+    }
+
+    /// Parses HTML given as bytes in any encoding
+    ///
+    /// The encoding is detected as browsers do it: from a byte order mark, then
+    /// `hint`, then a `<meta charset>` or `<meta http-equiv="Content-Type">`
+    /// declaration near the start of the page (see [`encoding::sniff`]). The
+    /// bytes are transcoded to UTF-8, and are only copied when they aren't UTF-8
+    /// already. [`encoding`](Parser::encoding) tells which encoding was picked.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - The HTML document
+    /// * `hint` - An encoding label known from elsewhere, such as the `charset`
+    ///   of an HTTP `Content-Type` header
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tagparser::parser::Parser;
+    /// use tagparser::EncodingSource;
+    ///
+    /// let html = b"<meta charset='windows-1251'><p>\xcf\xf0\xe8\xe2\xe5\xf2</p>";
+    /// let parser = Parser::from_bytes(html, None);
+    ///
+    /// assert_eq!(parser.extract_tag_content("p"), vec!["Привет"]);
+    /// assert_eq!(parser.encoding().unwrap().name, "windows-1251");
+    /// assert_eq!(parser.encoding().unwrap().source, EncodingSource::Meta);
+    /// ```
+    pub fn from_bytes(bytes: &'a [u8], hint: Option<&str>) -> Self {
+        let (html, detected) = encoding::decode(bytes, hint);
+        Parser { encoding: Some(detected), ..Parser::new(html) }
+    }
+
+    /// The encoding the HTML was read in, for a parser made with
+    /// [`from_bytes`](Parser::from_bytes)
+    ///
+    /// Returns `None` when the HTML was given as text.
+    pub fn encoding(&self) -> Option<DetectedEncoding> {
+        self.encoding
+    }
+
+    /// Turns decoding of character references in returned values on or off
+    ///
+    /// Decoding is on by default, so attribute values and text come back with
+    /// `&amp;`, `&#39;`, `&eacute;` and friends replaced by the characters they
+    /// stand for. Turn it off to get values exactly as written in the source.
+    /// Attribute values are compared with the filter in `parse_tags_with_attr`
+    /// in the same form. Methods that return HTML are never decoded.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use tagparser::parser::Parser;
+    /// let html = "<a href='/search?q=rust&amp;page=2' title='Caf&eacute;'>Link</a>";
+    ///
+    /// let parser = Parser::new(html.to_string());
+    /// assert_eq!(parser.extract_attribute_values("a", "href"), vec!["/search?q=rust&page=2"]);
+    ///
+    /// let raw = Parser::new(html.to_string()).decode_entities(false);
+    /// assert_eq!(raw.extract_attribute_values("a", "title"), vec!["Caf&eacute;"]);
+    /// ```
+    pub fn decode_entities(mut self, decode: bool) -> Self {
+        self.decode = decode;
+        self
+    }
+
+    /// Turns case-sensitive matching of tag and attribute names on or off
+    ///
+    /// HTML names are ASCII case-insensitive, so by default `parse_tags("div")`
+    /// also finds `<DIV>` and `<Div>`, and `href` matches `HREF=`. Turn
+    /// case-sensitive matching on for XML or XHTML input, where `<Item>` and
+    /// `<item>` are different elements. This only changes which names match a
+    /// query: the document is still parsed as HTML, and CSS selectors and XPath
+    /// expressions always ignore case.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use tagparser::parser::Parser;
+    /// let xml = "<Item ID='1'>First</Item><item id='2'>Second</item>";
+    ///
+    /// let parser = Parser::new(xml);
+    /// assert_eq!(parser.extract_tag_content("item"), vec!["First", "Second"]);
+    ///
+    /// let exact = Parser::new(xml).case_sensitive(true);
+    /// assert_eq!(exact.extract_tag_content("item"), vec!["Second"]);
+    /// assert_eq!(exact.extract_attribute_values("Item", "ID"), vec!["1"]);
+    /// ```
+    pub fn case_sensitive(mut self, case_sensitive: bool) -> Self {
+        self.case_sensitive = case_sensitive;
+        self
+    }
+
+    /// The parsed element tree
+    ///
+    /// The [`Document`] gives access to every element, text and comment node
+    /// along with parent, child and sibling navigation.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use tagparser::parser::Parser;
+    /// let parser = Parser::new("<div><p>First</p><p>Second</p></div>".to_string());
+    /// let document = parser.document();
+    ///
+    /// let first = document.elements().find(|node| node.name() == Some("p")).unwrap();
+    /// assert_eq!(first.parent().unwrap().name(), Some("div"));
+    /// assert_eq!(first.next_sibling().unwrap().text(), "Second");
+    /// ```
+    pub fn document(&self) -> &Document<'a> {
+        &self.document
+    }
+
+    /// Returns every complete `tag` element in document order
+    ///
+    /// Void elements such as `<img>` and elements whose end tag was implied,
+    /// like an unclosed `<li>`, count as complete. Other elements that are never
+    /// closed (and aren't written as `<tag/>`) are left out.
+    fn elements(&self, tag: &str) -> impl Iterator<Item = Node<'_>> + '_ {
+        // The index ignores case, so exact names are checked separately
+        let exact = self.case_sensitive.then(|| tag.to_string());
+        self.complete(self.index.by_name(tag))
+            .filter(move |node| exact.as_deref().is_none_or(|tag| node.name() == Some(tag)))
+    }
+
+    /// The complete elements among `ids`, in the same order
+    fn complete<'s>(&'s self, ids: &'s [NodeId]) -> impl Iterator<Item = Node<'s>> + 's {
+        ids.iter().map(|id| self.document.node(*id)).filter(|node| node.is_complete())
     }
 
     /// Parses HTML content and extracts all tags of the specified type
@@ -17,7 +222,7 @@ impl Parser {
     /// 
     /// # Returns
     /// 
-    /// A vector of strings containing all matching HTML tags
+    /// The source of each matching element, borrowed from the parser
     /// 
     /// # Example
     /// 
@@ -27,69 +232,66 @@ impl Parser {
     /// # use tagparser::parser::Parser;
     /// # 
     /// let html = "<a href='https://example.com'>Link</a><p>Paragraph</p>";
-    /// let mut parser = Parser::new(html.to_string());
+    /// let parser = Parser::new(html.to_string());
     /// 
     /// // Get all links
-    /// let links = parser.parse_tags("a".to_string());
+    /// let links = parser.parse_tags("a");
     /// assert_eq!(links, vec!["<a href='https://example.com'>Link</a>"]);
     /// 
     /// // Get all paragraphs
-    /// let paragraphs = parser.parse_tags("p".to_string());
+    /// let paragraphs = parser.parse_tags("p");
     /// assert_eq!(paragraphs, vec!["<p>Paragraph</p>"]);
     /// ```
-    pub fn parse_tags(&mut self, tag: String) -> Vec<String> {
-        // Create two patterns:
-        // 1. For regular tags with closing tag: <tag>...</tag>
-        // 2. For self-closing tags with closing slash: <tag/>
-        let pattern_regular = format!(r"<{}[^>]*>.*?</{}[^>]*>", tag, tag);
-        let pattern_self_closing = format!(r"<{}[^>]*/>", tag);
-        
-        // Get all regular tags
-        let mut results = Regex::new(&pattern_regular)
-            .unwrap()
-            .find_iter(&self.html)
-            .map(|x| x.as_str().to_string())
-            .collect::<Vec<String>>();
-        
-        // Add all self-closing tags
-        let self_closing_tags = Regex::new(&pattern_self_closing)
-            .unwrap()
-            .find_iter(&self.html)
-            .map(|x| x.as_str().to_string())
-            .collect::<Vec<String>>();
-        
-        results.extend(self_closing_tags);
-        
-        // For self-closing tags without closing slash (HTML5) use a different approach
-        // Find all opening tags that don't have a corresponding closing tag
-        let pattern_opening = format!(r"<{}[^>]*>", tag);
-        let opening_tags = Regex::new(&pattern_opening)
-            .unwrap()
-            .find_iter(&self.html)
-            .map(|x| x.as_str().to_string())
-            .collect::<Vec<String>>();
-        
-        // Check each opening tag
-        for opening_tag in opening_tags {
-            // If the tag is already in the results (as part of a regular tag or self-closing tag), skip it
-            let is_part_of_existing_tag = results.iter().any(|existing_tag| existing_tag.contains(&opening_tag));
-            
-            // For tests with malformed HTML, we should not add opening tags without closing tags
-            // Check if there is a closing tag for this opening tag
-            let closing_tag = format!("</{}", tag);
-            let has_closing_tag = self.html.contains(&closing_tag);
-            
-            if !is_part_of_existing_tag && has_closing_tag {
-                // If the tag is not part of an existing tag and has a closing tag, add it as a self-closing tag
-                results.push(opening_tag);
-            }
-        }
-        
-        results
+    pub fn parse_tags(&self, tag: &str) -> Vec<&str> {
+        self.iter_tags(tag).collect()
+    }
+
+    /// Returns the matches of [`parse_tags`](Parser::parse_tags) one at a time
+    ///
+    /// Elements are looked at lazily, in document order, so `next`, `take` or
+    /// `find` stop as soon as they have what they need instead of collecting
+    /// every match first.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use tagparser::parser::Parser;
+    /// let parser = Parser::new("<a href='/1'>1</a><a href='/2'>2</a><a href='/3'>3</a>");
+    ///
+    /// assert_eq!(parser.iter_tags("a").next(), Some("<a href='/1'>1</a>"));
+    /// assert_eq!(parser.iter_tags("a").take(2).count(), 2);
+    /// assert_eq!(parser.iter_tags("a").find(|link| link.contains("/3")), Some("<a href='/3'>3</a>"));
+    /// ```
+    pub fn iter_tags(&self, tag: &str) -> impl Iterator<Item = &str> + '_ {
+        self.elements(tag).map(|node| node.outer_html())
+    }
+
+    /// Like [`parse_tags`](Parser::parse_tags), but returns an error for a tag
+    /// name that could never appear in HTML instead of an empty result
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use tagparser::parser::Parser;
+    /// # use tagparser::Error;
+    /// let parser = Parser::new("<p>Paragraph</p>".to_string());
+    ///
+    /// assert_eq!(parser.try_parse_tags("p"), Ok(vec!["<p>Paragraph</p>"]));
+    /// assert_eq!(parser.try_parse_tags(""), Err(Error::InvalidTagName("".to_string())));
+    /// ```
+    pub fn try_parse_tags(&self, tag: &str) -> Result<Vec<&str>, Error> {
+        check_tag_name(tag)?;
+        Ok(self.parse_tags(tag))
     }
 
     /// Filters HTML tags by attribute name and optionally by attribute value
     /// 
+    /// Attributes are read the way browsers read them: values may be double-quoted,
+    /// single-quoted or unquoted (`colspan=2`), there may be whitespace around the `=`,
+    /// and attributes without a value (`<input disabled>`) have an empty value.
+    /// The attribute name must match a whole name, compared ASCII case-insensitively,
+    /// so `href` matches `HREF` but not `data-href`. The value must match exactly.
+    /// 
     /// # Arguments
     /// 
     /// * `tag` - The HTML tag name to search for (e.g., "a", "div", "img")
@@ -100,7 +302,7 @@ impl Parser {
     /// 
     /// # Returns
     /// 
-    /// A vector of strings containing the matching HTML tags
+    /// The source of each matching element, borrowed from the parser
     /// 
     /// # Examples
     /// 
@@ -115,18 +317,18 @@ impl Parser {
     ///     <a class="social" href="https://twitter.com">Twitter</a>
     /// "#;
     /// 
-    /// let mut parser = Parser::new(html.to_string());
+    /// let parser = Parser::new(html.to_string());
     /// 
     /// // Example 1: Find all links with href attribute (any value)
-    /// let links_with_href = parser.parse_tags_with_attr("a".to_string(), "href", None);
+    /// let links_with_href = parser.parse_tags_with_attr("a", "href", None);
     /// // Returns all three links
     /// 
     /// // Example 2: Find links with class="social"
-    /// let social_links = parser.parse_tags_with_attr("a".to_string(), "class", Some("social"));
+    /// let social_links = parser.parse_tags_with_attr("a", "class", Some("social"));
     /// // Returns only: <a class="social" href="https://twitter.com">Twitter</a>
     /// 
     /// // Example 3: Find links to a specific URL
-    /// let github_links = parser.parse_tags_with_attr("a".to_string(), "href", Some("https://github.com"));
+    /// let github_links = parser.parse_tags_with_attr("a", "href", Some("https://github.com"));
     /// // Returns only: <a href="https://github.com">GitHub</a>
     /// ```
     /// 
@@ -141,24 +343,247 @@ impl Parser {
     /// # Find all links with href pointing to github.com
     /// tagparser "<html>...</html>" "a" "href" "https://github.com"
     /// ```
-    pub fn parse_tags_with_attr(&mut self, tag: String, attr_name: &str, attr_value: Option<&str>) -> Vec<String> {
-        let all_tags = self.parse_tags(tag);
-        
-        all_tags.into_iter().filter(|tag_str| {
-            // Check if the tag contains the attribute
-            let attr_pattern = match attr_value {
-                Some(value) => format!(r#"{}=["']{}["']"#, attr_name, value),
-                None => format!(r#"{}=["'][^"']*["']"#, attr_name),
-            };
-            
-            Regex::new(&attr_pattern).unwrap().is_match(tag_str)
-        }).collect()
+    pub fn parse_tags_with_attr(&self, tag: &str, attr_name: &str, attr_value: Option<&str>) -> Vec<&str> {
+        self.elements_with_attr(tag, attr_name, attr_value)
+            .map(|node| node.outer_html())
+            .collect()
+    }
+
+    /// Like [`parse_tags_with_attr`](Parser::parse_tags_with_attr), but returns an
+    /// error for a tag or attribute name that could never appear in HTML
+    ///
+    /// The attribute value is compared literally, so any value is accepted.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use tagparser::parser::Parser;
+    /// # use tagparser::Error;
+    /// let parser = Parser::new("<a class='a.b'>Dot</a><a class='axb'>X</a>".to_string());
+    ///
+    /// let dotted = parser.try_parse_tags_with_attr("a", "class", Some("a.b"));
+    /// assert_eq!(dotted, Ok(vec!["<a class='a.b'>Dot</a>"]));
+    ///
+    /// let invalid = parser.try_parse_tags_with_attr("a", "class=", None);
+    /// assert_eq!(invalid, Err(Error::InvalidAttributeName("class=".to_string())));
+    /// ```
+    pub fn try_parse_tags_with_attr(
+        &self,
+        tag: &str,
+        attr_name: &str,
+        attr_value: Option<&str>,
+    ) -> Result<Vec<&str>, Error> {
+        check_tag_name(tag)?;
+        check_attribute_name(attr_name)?;
+        Ok(self.parse_tags_with_attr(tag, attr_name, attr_value))
+    }
+
+    /// Returns every complete `tag` element that has the attribute, optionally
+    /// with exactly the given value
+    fn elements_with_attr<'s: 'q, 'q>(
+        &'s self,
+        tag: &'q str,
+        attr_name: &'q str,
+        attr_value: Option<&'q str>,
+    ) -> impl Iterator<Item = Node<'s>> + 'q {
+        let (decode, case_sensitive) = (self.decode, self.case_sensitive);
+        self.elements(tag).filter(move |node| {
+            match (attribute_value(node, attr_name, decode, case_sensitive), attr_value) {
+                (Some(value), Some(wanted)) => value == wanted,
+                (found, _) => found.is_some(),
+            }
+        })
+    }
+
+    /// Returns every complete `tag` element whose attribute passes a test
+    ///
+    /// Where [`parse_tags_with_attr`](Parser::parse_tags_with_attr) only checks
+    /// that an attribute is present or has exactly some value, this accepts any
+    /// [`AttrMatch`]: prefix, suffix, substring, word and dash-prefix matches,
+    /// regular expressions, and case-insensitive versions of each.
+    ///
+    /// # Arguments
+    ///
+    /// * `tag` - The HTML tag name to search for (e.g., "a", "img")
+    /// * `attr_name` - The attribute to test (e.g., "href", "src", "class")
+    /// * `matcher` - The test the attribute's value must pass
+    ///
+    /// # Returns
+    ///
+    /// The source of each matching element, borrowed from the parser
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tagparser::parser::Parser;
+    /// use tagparser::AttrMatch;
+    ///
+    /// let html = r#"
+    ///     <a href="https://rust-lang.org" class="nav primary">Rust</a>
+    ///     <a href="http://example.com" class="nav">Example</a>
+    ///     <img src="/logo.PNG"><img src="/photo.jpg">
+    /// "#;
+    /// let parser = Parser::new(html);
+    ///
+    /// let secure = parser.parse_tags_matching("a", "href", &AttrMatch::Prefix("https://".to_string()));
+    /// assert_eq!(secure, vec![r#"<a href="https://rust-lang.org" class="nav primary">Rust</a>"#]);
+    ///
+    /// let primary = parser.parse_tags_matching("a", "class", &AttrMatch::Word("primary".to_string()));
+    /// assert_eq!(primary.len(), 1);
+    ///
+    /// let png = AttrMatch::Suffix(".png".to_string()).ignore_case();
+    /// assert_eq!(parser.parse_tags_matching("img", "src", &png), vec![r#"<img src="/logo.PNG">"#]);
+    /// ```
+    pub fn parse_tags_matching(&self, tag: &str, attr_name: &str, matcher: &AttrMatch) -> Vec<&str> {
+        self.elements_matching(tag, attr_name, matcher)
+            .map(|node| node.outer_html())
+            .collect()
+    }
+
+    /// Like [`parse_tags_matching`](Parser::parse_tags_matching), but returns an
+    /// error for a tag or attribute name that could never appear in HTML
+    pub fn try_parse_tags_matching(&self, tag: &str, attr_name: &str, matcher: &AttrMatch) -> Result<Vec<&str>, Error> {
+        check_tag_name(tag)?;
+        check_attribute_name(attr_name)?;
+        Ok(self.parse_tags_matching(tag, attr_name, matcher))
+    }
+
+    /// Returns every complete `tag` element whose attribute passes `matcher`
+    fn elements_matching<'s: 'q, 'q>(
+        &'s self,
+        tag: &'q str,
+        attr_name: &'q str,
+        matcher: &'q AttrMatch,
+    ) -> impl Iterator<Item = Node<'s>> + 'q {
+        let (decode, case_sensitive) = (self.decode, self.case_sensitive);
+        self.elements(tag).filter(move |node| {
+            attribute_value(node, attr_name, decode, case_sensitive).is_some_and(|value| matcher.matches(&value))
+        })
+    }
+
+    /// Finds all tags of the specified type, like [`parse_tags`](Parser::parse_tags),
+    /// but returns them as [`Tag`]s
+    ///
+    /// # Arguments
+    ///
+    /// * `tag` - The HTML tag name to search for (e.g., "a", "p", "div")
+    ///
+    /// # Returns
+    ///
+    /// A vector of [`Tag`]s in document order
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use tagparser::parser::Parser;
+    /// #
+    /// let html = "<a href='https://example.com' rel='nofollow'>Link</a>";
+    /// let parser = Parser::new(html.to_string());
+    ///
+    /// let links = parser.find_tags("a");
+    /// assert_eq!(links[0].attribute("rel"), Some("nofollow"));
+    /// assert_eq!(links[0].text, "Link");
+    /// ```
+    pub fn find_tags(&self, tag: &str) -> Vec<Tag> {
+        self.elements(tag)
+            .map(|node| Tag::new(node, self.decode))
+            .collect()
+    }
+
+    /// Filters tags by attribute like [`parse_tags_with_attr`](Parser::parse_tags_with_attr),
+    /// but returns them as [`Tag`]s
+    ///
+    /// # Arguments
+    ///
+    /// * `tag` - The HTML tag name to search for (e.g., "a", "div", "img")
+    /// * `attr_name` - The attribute name to filter by (e.g., "href", "class", "id")
+    /// * `attr_value` - Optional attribute value to filter by
+    ///
+    /// # Returns
+    ///
+    /// A vector of the matching [`Tag`]s in document order
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use tagparser::parser::Parser;
+    /// #
+    /// let html = r#"<img src="a.png" alt="A"/><img src="b.png" class="icon" alt="B"/>"#;
+    /// let parser = Parser::new(html.to_string());
+    ///
+    /// let icons = parser.find_tags_with_attr("img", "class", Some("icon"));
+    /// assert_eq!(icons[0].attribute("alt"), Some("B"));
+    /// ```
+    pub fn find_tags_with_attr(&self, tag: &str, attr_name: &str, attr_value: Option<&str>) -> Vec<Tag> {
+        self.elements_with_attr(tag, attr_name, attr_value)
+            .map(|node| Tag::new(node, self.decode))
+            .collect()
+    }
+
+    /// Filters tags with an [`AttrMatch`] like
+    /// [`parse_tags_matching`](Parser::parse_tags_matching), but returns them as [`Tag`]s
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use tagparser::parser::Parser;
+    /// use tagparser::AttrMatch;
+    ///
+    /// let parser = Parser::new("<p lang='en-GB'>Colour</p><p lang='fr'>Couleur</p>");
+    ///
+    /// let english = parser.find_tags_matching("p", "lang", &AttrMatch::DashPrefix("en".to_string()));
+    /// assert_eq!(english[0].text, "Colour");
+    /// ```
+    pub fn find_tags_matching(&self, tag: &str, attr_name: &str, matcher: &AttrMatch) -> Vec<Tag> {
+        self.elements_matching(tag, attr_name, matcher)
+            .map(|node| Tag::new(node, self.decode))
+            .collect()
+    }
+
+    /// Finds the element with the given `id` attribute
+    ///
+    /// The lookup uses the index built when the parser was created, so it
+    /// doesn't walk the document. If several elements share the id, the first
+    /// one is returned.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use tagparser::parser::Parser;
+    /// let parser = Parser::new("<div id='main'><p id='intro'>Hello</p></div>".to_string());
+    ///
+    /// assert_eq!(parser.find_by_id("intro").unwrap().text, "Hello");
+    /// assert!(parser.find_by_id("missing").is_none());
+    /// ```
+    pub fn find_by_id(&self, id: &str) -> Option<Tag> {
+        self.complete(self.index.by_id(id)).next().map(|node| Tag::new(node, self.decode))
+    }
+
+    /// Finds all elements that have `class` among their classes, in document order
+    ///
+    /// Like [`find_by_id`](Parser::find_by_id), this is answered from the index.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use tagparser::parser::Parser;
+    /// let parser = Parser::new("<a class='btn primary'>Save</a><a class='btn'>Cancel</a>".to_string());
+    ///
+    /// let buttons: Vec<String> = parser.find_by_class("btn").into_iter().map(|tag| tag.text).collect();
+    /// assert_eq!(buttons, vec!["Save", "Cancel"]);
+    /// assert_eq!(parser.find_by_class("primary").len(), 1);
+    /// ```
+    pub fn find_by_class(&self, class: &str) -> Vec<Tag> {
+        self.complete(self.index.by_class(class)).map(|node| Tag::new(node, self.decode)).collect()
     }
 
     /// Extracts the content (text) from inside HTML tags of the specified type
     /// 
     /// This method returns only the text content between the opening and closing tags,
-    /// without the tags themselves or any HTML attributes.
+    /// without the tags themselves or any HTML attributes. When the closing tag was
+    /// left out, as in `<li>One<li>Two`, the content runs to where the standard implies it.
+    /// The content of `<div><div>x</div>y</div>` is `<div>x</div>y` for the outer element,
+    /// since each element ends at its own end tag, and `a` never matches `<abbr>`.
     /// 
     /// # Arguments
     /// 
@@ -166,7 +591,7 @@ impl Parser {
     /// 
     /// # Returns
     /// 
-    /// A vector of strings containing the text content of all matching tags
+    /// The content of each matching element, borrowed from the parser
     /// 
     /// # Examples
     /// 
@@ -181,35 +606,82 @@ impl Parser {
     ///     <div class="container">Some content</div>
     /// "#;
     /// 
-    /// let mut parser = Parser::new(html.to_string());
+    /// let parser = Parser::new(html.to_string());
     /// 
     /// // Extract content from links
-    /// let link_texts = parser.extract_tag_content("a".to_string());
+    /// let link_texts = parser.extract_tag_content("a");
     /// assert_eq!(link_texts, vec!["GitHub"]);
     /// 
     /// // Extract content from paragraphs (includes nested HTML)
-    /// let paragraph_texts = parser.extract_tag_content("p".to_string());
+    /// let paragraph_texts = parser.extract_tag_content("p");
     /// assert_eq!(paragraph_texts, vec!["This is a <strong>paragraph</strong> with some text."]);
     /// 
     /// // Extract content from divs
-    /// let div_texts = parser.extract_tag_content("div".to_string());
+    /// let div_texts = parser.extract_tag_content("div");
     /// assert_eq!(div_texts, vec!["Some content"]);
     /// ```
-    pub fn extract_tag_content(&mut self, tag: String) -> Vec<String> {
-        // Create a regex pattern that captures the content between tags
-        let pattern = format!(r"<{}.*?>(.*?)</{}.*?>", tag, tag);
-        
-        // Find all matches and extract the captured group (content)
-        Regex::new(&pattern)
-            .unwrap()
-            .captures_iter(&self.html)
-            .map(|cap| cap[1].to_string())
+    pub fn extract_tag_content(&self, tag: &str) -> Vec<&str> {
+        // Void and self-closing tags have no content to extract
+        self.elements(tag)
+            .filter(|node| !node.is_self_closing() && !node.is_void())
+            .map(|node| node.inner_html())
+            .collect()
+    }
+
+    /// Like [`extract_tag_content`](Parser::extract_tag_content), but returns an
+    /// error for a tag name that could never appear in HTML
+    pub fn try_extract_tag_content(&self, tag: &str) -> Result<Vec<&str>, Error> {
+        check_tag_name(tag)?;
+        Ok(self.extract_tag_content(tag))
+    }
+
+    /// Extracts the plain text of HTML tags of the specified type
+    ///
+    /// Unlike [`extract_tag_content`](Parser::extract_tag_content), nested markup is
+    /// stripped: see [`Node::text_content`] for how the text is rendered. Character
+    /// references are decoded unless decoding was turned off with
+    /// [`decode_entities`](Parser::decode_entities).
+    ///
+    /// # Arguments
+    ///
+    /// * `tag` - The HTML tag name to search for (e.g., "p", "article", "body")
+    ///
+    /// # Returns
+    ///
+    /// A vector of strings containing the plain text of all matching tags
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tagparser::parser::Parser;
+    /// #
+    /// let html = r#"
+    ///     <p>This is a <strong>paragraph</strong> with some text.</p>
+    ///     <ul>
+    ///         <li>One</li>
+    ///         <li>Two &amp; three</li>
+    ///     </ul>
+    /// "#;
+    ///
+    /// let parser = Parser::new(html.to_string());
+    ///
+    /// let paragraphs = parser.text_content("p");
+    /// assert_eq!(paragraphs, vec!["This is a paragraph with some text."]);
+    ///
+    /// let list = parser.text_content("ul");
+    /// assert_eq!(list, vec!["One\nTwo & three"]);
+    /// ```
+    pub fn text_content(&self, tag: &str) -> Vec<String> {
+        self.elements(tag)
+            .map(|node| text::text_content(&node, self.decode))
             .collect()
     }
 
     /// Extracts attribute values from HTML tags of the specified type
     /// 
     /// This method returns the values of the specified attribute from all matching tags.
+    /// Attribute names are matched as in [`parse_tags_with_attr`](Parser::parse_tags_with_attr),
+    /// and attributes without a value give an empty string.
     /// 
     /// # Arguments
     /// 
@@ -218,8 +690,9 @@ impl Parser {
     /// 
     /// # Returns
     /// 
-    /// A vector of strings containing the attribute values from all matching tags.
-    /// Returns an empty vector if no matching tags or attributes are found.
+    /// The attribute value of each matching tag that has the attribute, borrowed
+    /// from the parser unless decoding changed it. Returns an empty vector if no
+    /// matching tags or attributes are found.
     /// 
     /// # Examples
     /// 
@@ -234,35 +707,435 @@ impl Parser {
     ///     <a class="social" href="https://twitter.com">Twitter</a>
     /// "#;
     /// 
-    /// let mut parser = Parser::new(html.to_string());
+    /// let parser = Parser::new(html.to_string());
     /// 
     /// // Extract all href values from links
-    /// let hrefs = parser.extract_attribute_values("a".to_string(), "href");
+    /// let hrefs = parser.extract_attribute_values("a", "href");
     /// assert_eq!(
     ///     vec!["https://github.com", "https://rust-lang.org", "https://twitter.com"],
     ///     hrefs
     /// );
     /// 
     /// // Extract all class values from links
-    /// let classes = parser.extract_attribute_values("a".to_string(), "class");
+    /// let classes = parser.extract_attribute_values("a", "class");
     /// assert_eq!(
     ///     vec!["official", "social"],
     ///     classes
     /// );
     /// ```
-    pub fn extract_attribute_values(&mut self, tag: String, attr_name: &str) -> Vec<String> {
-        // First get all tags of the specified type
-        let all_tags = self.parse_tags(tag);
-        
-        // Create a regex pattern to extract the attribute value
-        let attr_pattern = format!(r#"{}=["']([^"']*)["']"#, attr_name);
-        let re = Regex::new(&attr_pattern).unwrap();
-        
-        // Extract attribute values from all matching tags
-        all_tags.iter()
-            .filter_map(|tag_str| {
-                re.captures(tag_str).map(|cap| cap[1].to_string())
-            })
-            .collect()
+    pub fn extract_attribute_values(&self, tag: &str, attr_name: &str) -> Vec<Cow<'_, str>> {
+        self.iter_attribute_values(tag, attr_name).collect()
+    }
+
+    /// Returns the values of [`extract_attribute_values`](Parser::extract_attribute_values)
+    /// one at a time, in document order
+    ///
+    /// Like [`iter_tags`](Parser::iter_tags), elements are only looked at when
+    /// the next value is asked for.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use tagparser::parser::Parser;
+    /// let parser = Parser::new("<a>No link</a><a href='/first'>1</a><a href='/second'>2</a>");
+    ///
+    /// let first = parser.iter_attribute_values("a", "href").next();
+    /// assert_eq!(first.as_deref(), Some("/first"));
+    /// ```
+    pub fn iter_attribute_values<'s: 'q, 'q>(
+        &'s self,
+        tag: &str,
+        attr_name: &'q str,
+    ) -> impl Iterator<Item = Cow<'s, str>> + 'q {
+        let (decode, case_sensitive) = (self.decode, self.case_sensitive);
+        self.elements(tag).filter_map(move |node| attribute_value(&node, attr_name, decode, case_sensitive))
+    }
+
+    /// Like [`extract_attribute_values`](Parser::extract_attribute_values), but
+    /// returns an error for a tag or attribute name that could never appear in HTML
+    pub fn try_extract_attribute_values(&self, tag: &str, attr_name: &str) -> Result<Vec<Cow<'_, str>>, Error> {
+        check_tag_name(tag)?;
+        check_attribute_name(attr_name)?;
+        Ok(self.extract_attribute_values(tag, attr_name))
+    }
+
+    /// Finds all elements matching a CSS selector
+    ///
+    /// # Arguments
+    ///
+    /// * `selector` - A CSS selector list (e.g., "div.article > h2 a[href^='https']")
+    ///
+    /// # Returns
+    ///
+    /// The source of each matching element, borrowed from the parser, or a
+    /// [`SelectorError`] if the selector can't be parsed
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tagparser::parser::Parser;
+    /// #
+    /// let html = r#"
+    ///     <ul>
+    ///         <li>First</li>
+    ///         <li class="active">Second</li>
+    ///     </ul>
+    ///     <input type="hidden" name="token"/><input type="text" name="query"/>
+    /// "#;
+    ///
+    /// let parser = Parser::new(html.to_string());
+    ///
+    /// let second = parser.select("ul li:nth-child(2)").unwrap();
+    /// assert_eq!(second, vec![r#"<li class="active">Second</li>"#]);
+    ///
+    /// let visible = parser.select("input:not([type=hidden])").unwrap();
+    /// assert_eq!(visible, vec![r#"<input type="text" name="query"/>"#]);
+    /// ```
+    pub fn select(&self, selector: &str) -> Result<Vec<&str>, SelectorError> {
+        Ok(self.iter_select(selector)?.collect())
+    }
+
+    /// Returns the matches of [`select`](Parser::select) one at a time
+    ///
+    /// The selector is parsed up front, so an invalid one is reported right
+    /// away. Elements are then matched lazily, in document order, and matching
+    /// stops when the iterator is dropped.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use tagparser::parser::Parser;
+    /// let parser = Parser::new("<ul><li>1</li><li class='new'>2</li><li class='new'>3</li></ul>");
+    ///
+    /// let newest = parser.iter_select("li.new").unwrap().next();
+    /// assert_eq!(newest, Some("<li class='new'>2</li>"));
+    /// assert!(parser.iter_select("li[").is_err());
+    /// ```
+    pub fn iter_select(&self, selector: &str) -> Result<impl Iterator<Item = &str> + '_, SelectorError> {
+        let selector = Selector::parse(selector)?;
+        Ok(self
+            .document
+            .root()
+            .descendants()
+            .filter(move |node| selector.matches(node))
+            .map(|node| node.outer_html()))
+    }
+
+    /// Finds all elements matching a CSS selector, like [`select`](Parser::select),
+    /// but returns them as [`Tag`]s
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use tagparser::parser::Parser;
+    /// #
+    /// let parser = Parser::new("<ul><li id='a'>A</li><li id='b'>B</li></ul>".to_string());
+    /// let items = parser.select_tags("li:last-child").unwrap();
+    /// assert_eq!(items[0].attribute("id"), Some("b"));
+    /// ```
+    pub fn select_tags(&self, selector: &str) -> Result<Vec<Tag>, SelectorError> {
+        let nodes = self.document.select(selector)?;
+        Ok(nodes.into_iter().map(|node| Tag::new(node, self.decode)).collect())
+    }
+
+    /// Returns every complete element matched by a [`Filter`], in document order
+    ///
+    /// A filter combines tests on tag names, attribute values, text and depth
+    /// with and, or and not, so questions that would take several
+    /// `parse_tags_with_attr` calls and set operations on the results are
+    /// answered in one pass. Names and values are compared following
+    /// [`case_sensitive`](Parser::case_sensitive) and
+    /// [`decode_entities`](Parser::decode_entities).
+    ///
+    /// # Arguments
+    ///
+    /// * `filter` - The filter elements must match, built in code or with [`Filter::parse`]
+    ///
+    /// # Returns
+    ///
+    /// The source of each matching element, borrowed from the parser
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tagparser::parser::Parser;
+    /// use tagparser::Filter;
+    ///
+    /// let html = r#"
+    ///     <ul><li><a class="nav" href="/docs">Docs</a></li></ul>
+    ///     <p><a href="https://example.com" rel="external" target="_blank">Out</a></p>
+    ///     <a href="https://rust-lang.org" rel="external">Rust</a>
+    /// "#;
+    /// let parser = Parser::new(html);
+    ///
+    /// let filter = Filter::parse("tag=a and @rel=external and not @target=_blank").unwrap();
+    /// assert_eq!(parser.filter(&filter), vec![r#"<a href="https://rust-lang.org" rel="external">Rust</a>"#]);
+    ///
+    /// let nested = Filter::parse("tag=a and (depth>1 or text*=out i)").unwrap();
+    /// assert_eq!(parser.filter(&nested).len(), 2);
+    /// ```
+    pub fn filter(&self, filter: &Filter) -> Vec<&str> {
+        self.filtered(filter).map(|node| node.outer_html()).collect()
+    }
+
+    /// Finds the elements matched by a [`Filter`] like [`filter`](Parser::filter),
+    /// but returns them as [`Tag`]s
+    pub fn filter_tags(&self, filter: &Filter) -> Vec<Tag> {
+        self.filtered(filter).map(|node| Tag::new(node, self.decode)).collect()
+    }
+
+    /// Returns every complete element matched by `filter`
+    fn filtered<'s: 'q, 'q>(&'s self, filter: &'q Filter) -> impl Iterator<Item = Node<'s>> + 'q {
+        // Only the elements with the required name, if any, need testing
+        let candidates: Box<dyn Iterator<Item = Node<'s>> + 's> = match filter.required_tag() {
+            Some(tag) => Box::new(self.elements(tag)),
+            None => Box::new(self.document.elements().filter(|node| node.is_complete())),
+        };
+        let (decode, case_sensitive) = (self.decode, self.case_sensitive);
+        candidates.filter(move |node| filter.test(node, decode, case_sensitive))
     }
+
+    /// Extracts every table in the document, in document order
+    ///
+    /// Each table is laid out as a rectangular grid with `colspan` and `rowspan`
+    /// expanded; see [`Table`] for how headers are found. A table nested in a
+    /// cell of another is returned as a table of its own, after the one around it.
+    ///
+    /// # Returns
+    ///
+    /// A vector of [`Table`]s, which can be written out with [`Table::to_csv`]
+    /// and [`Table::to_json`]
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tagparser::parser::Parser;
+    /// #
+    /// let html = r#"
+    ///     <table>
+    ///         <thead><tr><th>City</th><th>Stations</th></tr></thead>
+    ///         <tbody>
+    ///             <tr><td>Paris</td><td><table><tr><td>Nord</td><td>Est</td></tr></table></td></tr>
+    ///             <tr><td colspan="2">Closed for renovation</td></tr>
+    ///         </tbody>
+    ///     </table>
+    /// "#;
+    ///
+    /// let parser = Parser::new(html);
+    /// let tables = parser.tables();
+    /// assert_eq!(tables.len(), 2);
+    /// assert_eq!(tables[0].headers, vec!["City", "Stations"]);
+    /// assert_eq!(tables[0].rows[1], vec!["Closed for renovation", "Closed for renovation"]);
+    /// assert_eq!(tables[1].rows, vec![vec!["Nord", "Est"]]);
+    /// ```
+    pub fn tables(&self) -> Vec<Table> {
+        self.elements("table").map(|node| Table::new(node, self.decode)).collect()
+    }
+
+    /// Extracts the tables matched by a CSS selector, like [`tables`](Parser::tables)
+    ///
+    /// Elements other than tables that the selector matches are left out.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use tagparser::parser::Parser;
+    /// #
+    /// let parser = Parser::new("<table id='a'><tr><td>A</td></tr></table><table id='b'><tr><td>B</td></tr></table>");
+    /// let tables = parser.select_tables("#b").unwrap();
+    /// assert_eq!(tables[0].rows, vec![vec!["B"]]);
+    /// ```
+    pub fn select_tables(&self, selector: &str) -> Result<Vec<Table>, SelectorError> {
+        let nodes = self.document.select(selector)?;
+        Ok(nodes
+            .into_iter()
+            .filter(|node| node.name().is_some_and(|name| name.eq_ignore_ascii_case("table")))
+            .map(|node| Table::new(node, self.decode))
+            .collect())
+    }
+
+    /// Extracts every form in the document with its controls, in document order
+    ///
+    /// Each [`Form`] holds its action, method and encoding type, and every
+    /// control with its name, type, value, checked and disabled state and label,
+    /// as they are when the page loads. [`Form::to_urlencoded`] gives what the
+    /// browser would send if the form were submitted as it is.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tagparser::parser::Parser;
+    /// #
+    /// let html = r#"
+    ///     <form action="/login" method="post">
+    ///         <input type="hidden" name="token" value="a1b2">
+    ///         <label>User <input name="user"></label>
+    ///         <label>Remember me <input type="checkbox" name="remember"></label>
+    ///         <textarea name="note" disabled>Hi</textarea>
+    ///     </form>
+    /// "#;
+    ///
+    /// let parser = Parser::new(html);
+    /// let login = &parser.forms()[0];
+    /// assert_eq!(login.controls.len(), 4);
+    /// assert_eq!(login.controls[1].label.as_deref(), Some("User"));
+    /// assert!(login.controls[3].disabled);
+    /// assert_eq!(login.to_urlencoded(), "token=a1b2&user=");
+    /// ```
+    pub fn forms(&self) -> Vec<Form> {
+        self.elements("form").map(|node| Form::new(node, self.decode)).collect()
+    }
+
+    /// Extracts the forms matched by a CSS selector, like [`forms`](Parser::forms)
+    ///
+    /// Elements other than forms that the selector matches are left out.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use tagparser::parser::Parser;
+    /// #
+    /// let parser = Parser::new("<form id='search'><input name='q'></form><form id='login'></form>");
+    /// let forms = parser.select_forms("#search").unwrap();
+    /// assert_eq!(forms[0].controls[0].name, "q");
+    /// ```
+    pub fn select_forms(&self, selector: &str) -> Result<Vec<Form>, SelectorError> {
+        let nodes = self.document.select(selector)?;
+        Ok(nodes
+            .into_iter()
+            .filter(|node| node.name().is_some_and(|name| name.eq_ignore_ascii_case("form")))
+            .map(|node| Form::new(node, self.decode))
+            .collect())
+    }
+
+    /// Collects the metadata of the page: title, description, keywords,
+    /// canonical link, robots, viewport, charset, language, icons and the
+    /// OpenGraph, Twitter card and article properties
+    ///
+    /// Character references in the values are decoded unless decoding was
+    /// turned off with [`decode_entities`](Parser::decode_entities).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use tagparser::parser::Parser;
+    /// #
+    /// let html = r#"
+    ///     <title>Docs</title>
+    ///     <meta name="robots" content="noindex">
+    ///     <meta name="keywords" content="rust, html , parser">
+    ///     <meta property="article:tag" content="rust">
+    ///     <meta property="article:tag" content="web">
+    /// "#;
+    ///
+    /// let metadata = Parser::new(html).metadata();
+    /// assert_eq!(metadata.robots.as_deref(), Some("noindex"));
+    /// assert_eq!(metadata.keywords, vec!["rust", "html", "parser"]);
+    /// assert_eq!(metadata.properties_named("article:tag").count(), 2);
+    /// ```
+    pub fn metadata(&self) -> Metadata {
+        Metadata::new(&self.document, self.decode)
+    }
+
+    /// Evaluates an XPath 1.0 expression against the whole document
+    ///
+    /// # Arguments
+    ///
+    /// * `expression` - An XPath expression (e.g., "//table[@id='prices']//tr[position()>1]/td[2]/text()")
+    ///
+    /// # Returns
+    ///
+    /// For a node-set, the HTML of each element, the text of each text or comment
+    /// node and the value of each attribute, in document order. These are borrowed
+    /// from the parser unless decoding character references changed them, which
+    /// can be turned off with [`decode_entities`](Parser::decode_entities).
+    /// Strings, numbers and booleans are returned as a single string. An
+    /// [`XPathError`] is returned if the expression is invalid.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tagparser::parser::Parser;
+    /// #
+    /// let html = r#"
+    ///     <ul>
+    ///         <li><a href="/one">One</a></li>
+    ///         <li><a href="/two">Two</a></li>
+    ///     </ul>
+    /// "#;
+    ///
+    /// let parser = Parser::new(html.to_string());
+    ///
+    /// let hrefs = parser.xpath("//li/a/@href").unwrap();
+    /// assert_eq!(hrefs, vec!["/one", "/two"]);
+    ///
+    /// let last = parser.xpath("//li[last()]").unwrap();
+    /// assert_eq!(last, vec![r#"<li><a href="/two">Two</a></li>"#]);
+    ///
+    /// let found = parser.xpath("boolean(//a[starts-with(@href, '/t')])").unwrap();
+    /// assert_eq!(found, vec!["true"]);
+    /// ```
+    pub fn xpath(&self, expression: &str) -> Result<Vec<Cow<'_, str>>, XPathError> {
+        let result = match self.document.xpath(expression)? {
+            Value::NodeSet(nodes) => nodes
+                .iter()
+                .map(|node| match node {
+                    XPathNode::Node(node) if node.is_element() => Cow::Borrowed(node.outer_html()),
+                    XPathNode::Node(node) if self.decode => node
+                        .as_text()
+                        .or_else(|| node.as_comment().map(Cow::Borrowed))
+                        .unwrap_or_else(|| Cow::Owned(node.text())),
+                    XPathNode::Node(node) => node
+                        .as_raw_text()
+                        .or_else(|| node.as_comment())
+                        .map_or_else(|| Cow::Owned(node.raw_text()), Cow::Borrowed),
+                    XPathNode::Attribute { value, .. } if self.decode => decode_attribute(value),
+                    XPathNode::Attribute { value, .. } => Cow::Borrowed(*value),
+                })
+                .collect(),
+            value => vec![Cow::Owned(value.to_string_value())],
+        };
+        Ok(result)
+    }
+
+    /// Checks the HTML for markup the parser had to recover from
+    ///
+    /// The parser never rejects input: misnested tags, stray end tags,
+    /// unterminated attribute values and the like are repaired the way browsers
+    /// repair them. This method reports each repair, which makes it usable as a
+    /// validator for generated HTML.
+    ///
+    /// # Returns
+    ///
+    /// A vector of [`ParseDiagnostic`]s in source order; empty if the HTML is well-formed
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tagparser::parser::Parser;
+    /// # use tagparser::DiagnosticKind;
+    /// #
+    /// let html = "<div>\n  <b><i>Bold</b> italic</i>\n</span></div>";
+    /// let parser = Parser::new(html.to_string());
+    ///
+    /// let diagnostics = parser.diagnostics();
+    /// assert_eq!(diagnostics.len(), 2);
+    /// assert_eq!(diagnostics[0].kind, DiagnosticKind::MisnestedTag);
+    /// assert_eq!(diagnostics[0].to_string(), "2:6: <i> is closed by </b> before its own end tag");
+    /// assert_eq!(diagnostics[1].to_string(), "3:1: </span> doesn't close any open element and is ignored");
+    /// ```
+    pub fn diagnostics(&self) -> Vec<ParseDiagnostic> {
+        self.document.diagnostics().to_vec()
+    }
+}
+
+/// Value of an element's attribute, decoded or exactly as written, with the
+/// name compared exactly or ignoring ASCII case
+pub(crate) fn attribute_value<'a>(node: &Node<'a>, name: &str, decode: bool, case_sensitive: bool) -> Option<Cow<'a, str>> {
+    let raw = if case_sensitive {
+        node.raw_attributes().find(|(attr_name, _)| *attr_name == name).map(|(_, value)| value)
+    } else {
+        node.raw_attribute(name)
+    }?;
+    Some(if decode { decode_attribute(raw) } else { Cow::Borrowed(raw) })
 }
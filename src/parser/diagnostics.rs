@@ -0,0 +1,79 @@
+use std::fmt;
+
+use crate::dom::Location;
+use crate::parser::tokenizer::Span;
+
+/// The kind of problem a [`ParseDiagnostic`] reports
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DiagnosticKind {
+    /// An end tag that doesn't close any open element, such as a stray `</span>`
+    /// or the end tag of a void element; it is ignored
+    StrayEndTag,
+    /// An element closed by the end of an element it is nested in, or by a
+    /// start tag, before its own end tag, as the `<i>` in `<b><i></b></i>`
+    MisnestedTag,
+    /// An element whose end tag is required but still open at the end of the input
+    UnclosedElement,
+    /// A tag cut off by the end of the input, often by an attribute value
+    /// that is missing its closing quote; the tag is dropped
+    UnterminatedTag,
+    /// A comment, CDATA section or doctype cut off by the end of the input
+    UnterminatedComment,
+    /// An attribute repeated within one tag; only the first is kept
+    DuplicateAttribute,
+    /// Two attributes with no whitespace between them, as in `<a href="x"class="y">`
+    MissingWhitespace,
+    /// A non-void element written as `<tag/>`; it is parsed as empty, where a browser would
+    /// ignore the slash and keep the element open
+    SelfClosingNonVoid,
+    /// Markup such as `<?xml ...?>` or `<!foo>` that HTML reads as a comment
+    BogusComment,
+}
+
+/// A problem found while parsing, with where it is in the source
+///
+/// The parser never fails: it recovers from malformed markup the way browsers
+/// do and records what it had to recover from as diagnostics.
+///
+/// # Examples
+///
+/// ```
+/// use tagparser::{DiagnosticKind, Document};
+///
+/// let document = Document::parse("<p><b><i>Text</b> more</i></p>");
+/// let kinds: Vec<DiagnosticKind> = document.diagnostics().iter().map(|d| d.kind).collect();
+///
+/// // The `<i>` is reopened around " more", so its end tag isn't stray
+/// assert_eq!(kinds, vec![DiagnosticKind::MisnestedTag]);
+/// assert_eq!(document.diagnostics()[0].to_string(), "1:7: <i> is closed by </b> before its own end tag");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ParseDiagnostic {
+    pub kind: DiagnosticKind,
+    /// A description of the problem
+    pub message: String,
+    /// Byte range of the markup the diagnostic is about
+    pub span: Span,
+    /// Line and column of both ends of `span`
+    pub location: Location,
+}
+
+impl fmt::Display for ParseDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.location.start, self.message)
+    }
+}
+
+/// A diagnostic whose line and column haven't been worked out yet
+#[derive(Debug, Clone)]
+pub(crate) struct RawDiagnostic {
+    pub(crate) kind: DiagnosticKind,
+    pub(crate) message: String,
+    pub(crate) span: Span,
+}
+
+impl RawDiagnostic {
+    pub(crate) fn new(kind: DiagnosticKind, message: String, span: Span) -> Self {
+        RawDiagnostic { kind, message, span }
+    }
+}
@@ -0,0 +1,63 @@
+use std::collections::HashMap;
+
+use crate::dom::{Document, NodeId};
+
+/// Element ids grouped by tag name, `id` attribute and class
+///
+/// The index is built in a single pass over the document so that queries can
+/// go straight to the elements they are about instead of walking the tree.
+/// Each list is in document order.
+#[derive(Debug, Default)]
+pub(crate) struct ElementIndex {
+    by_name: HashMap<String, Vec<NodeId>>,
+    by_id: HashMap<String, Vec<NodeId>>,
+    by_class: HashMap<String, Vec<NodeId>>,
+}
+
+impl ElementIndex {
+    pub(crate) fn new(document: &Document) -> Self {
+        let mut index = ElementIndex::default();
+
+        for node in document.elements() {
+            let id = node.id();
+            if let Some(name) = node.name() {
+                index.by_name.entry(name.to_ascii_lowercase()).or_default().push(id);
+            }
+            if let Some(value) = node.attribute("id") {
+                index.by_id.entry(value.into_owned()).or_default().push(id);
+            }
+            if let Some(classes) = node.attribute("class") {
+                for class in classes.split_ascii_whitespace() {
+                    let elements = index.by_class.entry(class.to_string()).or_default();
+                    // `class="a a"` lists the element once
+                    if elements.last() != Some(&id) {
+                        elements.push(id);
+                    }
+                }
+            }
+        }
+
+        index
+    }
+
+    /// Elements with this tag name, compared ASCII case-insensitively
+    pub(crate) fn by_name(&self, name: &str) -> &[NodeId] {
+        let found = if name.bytes().any(|b| b.is_ascii_uppercase()) {
+            self.by_name.get(&name.to_ascii_lowercase())
+        } else {
+            self.by_name.get(name)
+        };
+        found.map_or(&[], Vec::as_slice)
+    }
+
+    /// Elements whose decoded `id` attribute is `id`
+    pub(crate) fn by_id(&self, id: &str) -> &[NodeId] {
+        self.by_id.get(id).map_or(&[], Vec::as_slice)
+    }
+
+    /// Elements that have `class` among the whitespace-separated words of
+    /// their decoded `class` attribute
+    pub(crate) fn by_class(&self, class: &str) -> &[NodeId] {
+        self.by_class.get(class).map_or(&[], Vec::as_slice)
+    }
+}
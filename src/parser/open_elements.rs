@@ -0,0 +1,493 @@
+use std::borrow::Cow;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use crate::parser::diagnostics::{DiagnosticKind, RawDiagnostic};
+use crate::parser::tokenizer::{is_raw_text_element, Attribute, Span};
+use crate::parser::tree_builder::{has_optional_end_tag, is_void_element};
+
+/// Start tags that close an open `<p>`
+const CLOSES_P: &[&str] = &[
+    "address", "article", "aside", "blockquote", "center", "details", "dialog", "dir", "div", "dl", "dd", "dt",
+    "fieldset", "figcaption", "figure", "footer", "form", "h1", "h2", "h3", "h4", "h5", "h6", "header", "hgroup",
+    "hr", "li", "listing", "main", "menu", "nav", "ol", "p", "plaintext", "pre", "search", "section", "summary",
+    "table", "ul", "xmp",
+];
+
+const HEADINGS: &[&str] = &["h1", "h2", "h3", "h4", "h5", "h6"];
+
+/// Elements that most end tags can't close an element across ("scope" in the standard)
+const DEFAULT_SCOPE: &[&str] = &["applet", "caption", "html", "marquee", "object", "table", "td", "template", "th"];
+
+/// The scope a `</p>` can't close an element across ("button scope" in the standard)
+const BUTTON_SCOPE: &[&str] = &[
+    "applet", "button", "caption", "html", "marquee", "object", "table", "td", "template", "th",
+];
+
+/// The scope a `</li>` can't close an element across ("list item scope" in the standard)
+const LIST_ITEM_SCOPE: &[&str] = &[
+    "applet", "caption", "html", "marquee", "object", "ol", "table", "td", "template", "th", "ul",
+];
+
+/// Table elements other than `<table>` itself, whose end tags only close
+/// elements inside the same table
+const TABLE_PARTS: &[&str] = &["caption", "colgroup", "tbody", "td", "tfoot", "th", "thead", "tr"];
+
+/// Inline formatting elements such as `<b>` and `<a>`
+const FORMATTING: &[&str] = &[
+    "a", "b", "big", "code", "em", "font", "i", "nobr", "s", "small", "strike", "strong", "tt", "u",
+];
+
+/// Elements that keep formatting from outside them out of their content
+/// (those that put a "marker" on the list of active formatting elements)
+const MARKERS: &[&str] = &["applet", "caption", "marquee", "object", "td", "template", "th"];
+
+/// Special elements whose start tags reopen formatting elements all the same
+const REOPENS_FORMATTING: &[&str] = &[
+    "applet", "area", "br", "button", "embed", "img", "input", "keygen", "marquee", "object", "select", "wbr",
+];
+
+/// Table elements whose content is rows and cells rather than text, where
+/// formatting elements are never reopened
+const TABLE_CONTEXT: &[&str] = &["table", "tbody", "tfoot", "thead", "tr"];
+
+/// Elements that stop the search for an open `<li>`, `<dd>` or `<dt>` to close
+/// (the "special" category of the standard, less `address`, `div` and `p`)
+const SPECIAL: &[&str] = &[
+    "applet", "area", "article", "aside", "base", "basefont", "bgsound", "blockquote", "body", "br", "button",
+    "caption", "center", "col", "colgroup", "dd", "details", "dir", "dl", "dt", "embed", "fieldset", "figcaption",
+    "figure", "footer", "form", "frame", "frameset", "h1", "h2", "h3", "h4", "h5", "h6", "head", "header", "hgroup",
+    "hr", "html", "iframe", "img", "input", "keygen", "li", "link", "listing", "main", "marquee", "menu", "meta",
+    "nav", "noembed", "noframes", "noscript", "object", "ol", "param", "plaintext", "pre", "script", "search",
+    "section", "select", "source", "style", "summary", "table", "tbody", "td", "template", "textarea", "tfoot", "th",
+    "thead", "title", "tr", "track", "ul", "wbr", "xmp",
+];
+
+/// Elements that belong in `<head>`; any other start tag ends an open `<head>`
+const HEAD_CONTENT: &[&str] = &[
+    "base", "basefont", "bgsound", "link", "meta", "noframes", "noscript", "script", "style", "template", "title",
+];
+
+fn is_one_of(name: &str, names: &[&str]) -> bool {
+    names.iter().any(|candidate| name.eq_ignore_ascii_case(candidate))
+}
+
+/// A hash of a start tag's attributes that doesn't depend on their order or
+/// on the case of their names
+fn hash_attributes(attributes: &[Attribute]) -> u64 {
+    let mut pairs: Vec<(String, &str)> =
+        attributes.iter().map(|attr| (attr.name.to_ascii_lowercase(), attr.value)).collect();
+    pairs.sort_unstable();
+    let mut hasher = DefaultHasher::new();
+    pairs.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Returns `true` for inline formatting elements such as `<b>` and `<a>`,
+/// which are reopened when closed before their own end tag
+fn is_formatting_element(name: &str) -> bool {
+    is_one_of(name, FORMATTING)
+}
+
+/// Returns `true` for the elements of the standard's "special" category
+fn is_special(name: &str) -> bool {
+    is_one_of(name, SPECIAL) || is_one_of(name, &["address", "div", "p"])
+}
+
+/// An element whose start tag has been read but which hasn't been closed yet
+#[derive(Debug)]
+struct OpenElement<'a> {
+    id: usize,
+    name: Cow<'a, str>,
+    start_tag: Span,
+    /// Opened again after being closed before its end tag, without a start tag of its own
+    reopened: bool,
+}
+
+/// An entry of the list of active formatting elements
+#[derive(Debug)]
+enum Formatting<'a> {
+    /// A formatting element that hasn't met its own end tag yet, with a hash
+    /// of its attributes to tell identical elements apart; `id` is the latest
+    /// copy of the element `original` if it has been reopened
+    Element { id: usize, original: usize, name: Cow<'a, str>, attributes: u64 },
+    /// The start of a table cell, caption or the like, which formatting from
+    /// outside it doesn't enter
+    Marker,
+}
+
+/// An element taken off the stack of open elements
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Closed {
+    /// The id the element was pushed with
+    pub(crate) id: usize,
+    /// Where the element's content stops
+    pub(crate) at: usize,
+    /// The end tag that closed it, unless it was closed implicitly
+    pub(crate) end_tag: Option<Span>,
+    /// Set for a formatting element closed before its end tag that may still
+    /// be reopened; whether its end tag comes after all is told by a [`Settled`]
+    pub(crate) pending: bool,
+}
+
+/// What became of a formatting element closed before its end tag, once it
+/// can no longer be reopened
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Settled {
+    /// The id the element was first pushed with
+    pub(crate) id: usize,
+    /// Whether its end tag was found, closing a reopened copy or ignored
+    pub(crate) ended: bool,
+}
+
+/// The stack of open elements, with the rules for closing them
+///
+/// Start and end tags are paired with a stack of open elements: an end tag
+/// closes the nearest open element with the same name, together with any
+/// elements opened after it that are still open. As in browsers, the search
+/// stops at certain boundaries so that an end tag can't reach outside its
+/// element (a `</p>` in a table cell can't close a paragraph outside the
+/// table, for example). End tags that match nothing are ignored.
+///
+/// The end tags the HTML standard lets authors leave out are implied the way
+/// browsers imply them: a new `<li>` closes the previous one, a block element
+/// closes an open `<p>`, a `<tr>` closes the open row and cell, and so on.
+///
+/// Formatting elements such as `<b>` and `<i>` that are closed before their own
+/// end tag stay active, as they do in browsers: in `<b><i>x</b>y</i>` the `</b>`
+/// closes the `<i>` with it, and the `<i>` is opened again around `y` before
+/// its end tag is reached. The caller asks which elements to reopen with
+/// [`formatting_to_reopen`](OpenElements::formatting_to_reopen) before text
+/// and start tags, and creates them with [`reopen`](OpenElements::reopen).
+/// An element closed this way is settled once it can't be reopened any more,
+/// telling whether its end tag was found after all.
+///
+/// The stack only knows element names and ids, so it serves both the tree
+/// builder and the streaming parser, which keeps no tree. Closed elements and
+/// diagnostics are collected for the caller to take.
+#[derive(Debug, Default)]
+pub(crate) struct OpenElements<'a> {
+    stack: Vec<OpenElement<'a>>,
+    /// The list of active formatting elements, outermost first
+    formatting: Vec<Formatting<'a>>,
+    closed: Vec<Closed>,
+    settled: Vec<Settled>,
+    diagnostics: Vec<RawDiagnostic>,
+}
+
+impl<'a> OpenElements<'a> {
+    pub(crate) fn new() -> Self {
+        OpenElements::default()
+    }
+
+    /// Id of the innermost open element
+    pub(crate) fn current_id(&self) -> Option<usize> {
+        self.stack.last().map(|element| element.id)
+    }
+
+    /// Name of the innermost open element
+    pub(crate) fn current_name(&self) -> Option<&str> {
+        self.stack.last().map(|element| element.name.as_ref())
+    }
+
+    /// Opens an element, which contains everything up to its end
+    pub(crate) fn push(&mut self, id: usize, name: Cow<'a, str>, attributes: &[Attribute], start_tag: Span) {
+        if is_formatting_element(&name) {
+            let attributes = hash_attributes(attributes);
+            // As in browsers, only three identical formatting elements are
+            // kept active, so that repeated misnesting can't pile up copies
+            let same: Vec<usize> = self
+                .formatting
+                .iter()
+                .enumerate()
+                .rev()
+                .take_while(|(_, entry)| !matches!(entry, Formatting::Marker))
+                .filter(|(_, entry)| {
+                    matches!(entry, Formatting::Element { name: other, attributes: hash, .. }
+                        if other.eq_ignore_ascii_case(&name) && *hash == attributes)
+                })
+                .map(|(index, _)| index)
+                .collect();
+            if same.len() >= 3 {
+                self.drop_formatting(same[same.len() - 1], false);
+            }
+            self.formatting.push(Formatting::Element { id, original: id, name: name.clone(), attributes });
+        } else if is_one_of(&name, MARKERS) {
+            self.formatting.push(Formatting::Marker);
+        }
+        self.stack.push(OpenElement { id, name, start_tag, reopened: false });
+    }
+
+    /// Ids of the formatting elements that were closed before their end tags
+    /// and must be opened again before a start tag called `start_tag`, or
+    /// before text if that is `None`, outermost first
+    ///
+    /// Each one is opened with [`reopen`](OpenElements::reopen), in order,
+    /// before the tag or text itself.
+    pub(crate) fn formatting_to_reopen(&self, start_tag: Option<&str>) -> Vec<usize> {
+        if start_tag.is_some_and(|name| is_special(name) && !is_one_of(name, REOPENS_FORMATTING))
+            || self.current_is(TABLE_CONTEXT)
+            || self.current_name().is_some_and(is_raw_text_element)
+        {
+            return Vec::new();
+        }
+        let mut ids = Vec::new();
+        for entry in self.formatting.iter().rev() {
+            match entry {
+                Formatting::Element { id, .. } if !self.stack.iter().any(|open| open.id == *id) => ids.push(*id),
+                _ => break,
+            }
+        }
+        ids.reverse();
+        ids
+    }
+
+    /// Opens the formatting element `original` again as a new element `id`
+    /// that starts at offset `at`, where it has no start tag of its own
+    pub(crate) fn reopen(&mut self, original: usize, id: usize, at: usize) {
+        let entry = self.formatting_position(original).map(|position| &mut self.formatting[position]);
+        let Some(Formatting::Element { id: active, name, .. }) = entry else {
+            return;
+        };
+        *active = id;
+        let name = name.clone();
+        self.stack.push(OpenElement { id, name, start_tag: Span::new(at, at), reopened: true });
+    }
+
+    /// Takes the elements closed so far, innermost first for each tag
+    pub(crate) fn take_closed(&mut self) -> Vec<Closed> {
+        std::mem::take(&mut self.closed)
+    }
+
+    /// Takes the formatting elements settled so far
+    pub(crate) fn take_settled(&mut self) -> Vec<Settled> {
+        std::mem::take(&mut self.settled)
+    }
+
+    /// Takes the problems found so far
+    pub(crate) fn take_diagnostics(&mut self) -> Vec<RawDiagnostic> {
+        std::mem::take(&mut self.diagnostics)
+    }
+
+    /// Closes the open elements whose end tags are implied by a start tag
+    /// called `name` at offset `at`
+    ///
+    /// The element for the start tag itself is pushed separately, with
+    /// [`push`](OpenElements::push), unless it is void or self-closing.
+    pub(crate) fn start_tag(&mut self, name: &str, at: usize) {
+        let start_tag = format!("<{}>", name);
+        if is_one_of(name, CLOSES_P) {
+            if let Some(index) = self.find_open(&["p"], BUTTON_SCOPE) {
+                self.close_from(index, at, &start_tag);
+            }
+        }
+        // Headings don't nest
+        if is_one_of(name, HEADINGS) && self.current_is(HEADINGS) {
+            self.close_from(self.stack.len() - 1, at, &start_tag);
+        }
+
+        let closed: Option<(&[&str], &[&str])> = match name.to_ascii_lowercase().as_str() {
+            "li" => {
+                self.close_list_item(&["li"], at, &start_tag);
+                None
+            }
+            "dd" | "dt" => {
+                self.close_list_item(&["dd", "dt"], at, &start_tag);
+                None
+            }
+            "option" => Some((&["option"], &["select", "datalist", "optgroup"])),
+            "optgroup" => Some((&["option", "optgroup"], &["select"])),
+            "tr" => Some((&["tr"], &["html", "table", "template", "tbody", "tfoot", "thead"])),
+            "td" | "th" => Some((&["td", "th"], &["html", "table", "template", "tr"])),
+            "caption" | "colgroup" | "tbody" | "tfoot" | "thead" => {
+                Some((&["caption", "colgroup", "tbody", "tfoot", "thead"], &["html", "table", "template"]))
+            }
+            "rb" | "rtc" => Some((&["rb", "rp", "rt", "rtc"], &["ruby"])),
+            "rp" | "rt" => Some((&["rb", "rp", "rt"], &["ruby", "rtc"])),
+            "body" => Some((&["head"], &[])),
+            _ => None,
+        };
+        if let Some((targets, boundaries)) = closed {
+            if let Some(index) = self.find_open(targets, boundaries) {
+                self.close_from(index, at, &start_tag);
+            }
+        }
+
+        // An open `<head>` ends at the first element that doesn't belong in it
+        if self.current_is(&["head"]) && !is_one_of(name, HEAD_CONTENT) {
+            self.close_from(self.stack.len() - 1, at, &start_tag);
+        }
+    }
+
+    /// Closes the element an end tag called `name` belongs to, along with the
+    /// elements still open inside it, or ignores the end tag if it has none
+    ///
+    /// As in browsers, the search for the element stops at certain boundaries,
+    /// so a `</p>` inside a table cell never closes a paragraph outside the table.
+    pub(crate) fn end_tag(&mut self, name: &str, span: Span) {
+        let index = if is_void_element(name) {
+            None
+        } else if name.eq_ignore_ascii_case("p") {
+            self.find_open(&["p"], BUTTON_SCOPE)
+        } else if name.eq_ignore_ascii_case("li") {
+            self.find_open(&["li"], LIST_ITEM_SCOPE)
+        } else if is_one_of(name, HEADINGS) {
+            // Any heading closes any other
+            self.find_open(HEADINGS, DEFAULT_SCOPE)
+        } else if name.eq_ignore_ascii_case("table") {
+            self.find_open(&["table"], &["html", "template"])
+        } else if is_one_of(name, TABLE_PARTS) {
+            self.find_open(&[name], &["html", "table", "template"])
+        } else if is_special(name) || is_one_of(name, FORMATTING) {
+            self.find_open(&[name], DEFAULT_SCOPE)
+        } else {
+            // Any other end tag closes the innermost open element with its
+            // name, unless a special element such as a `<div>` is in the way
+            let mut found = None;
+            for index in (0..self.stack.len()).rev() {
+                let open = &self.stack[index].name;
+                if open.eq_ignore_ascii_case(name) {
+                    found = Some(index);
+                    break;
+                }
+                if is_special(open) {
+                    break;
+                }
+            }
+            found
+        };
+
+        let Some(index) = index else {
+            // A formatting element closed before this end tag isn't reopened any more
+            if let Some(position) = self.formatting.iter().rposition(|entry| {
+                matches!(entry, Formatting::Element { name: active, .. } if active.eq_ignore_ascii_case(name))
+            }) {
+                if !self.formatting[position..].iter().any(|entry| matches!(entry, Formatting::Marker)) {
+                    self.drop_formatting(position, true);
+                }
+            }
+            self.report(DiagnosticKind::StrayEndTag, format!("</{}> doesn't close any open element and is ignored", name), span);
+            return;
+        };
+
+        let end_tag = format!("</{}>", name);
+        self.close_from(index + 1, span.start, &end_tag);
+        let element = self.stack.pop().unwrap();
+        if !element.name.eq_ignore_ascii_case(name) {
+            let message = format!("<{}> is closed by {}", element.name, end_tag);
+            self.report(DiagnosticKind::MisnestedTag, message, span);
+        }
+        if let Some(position) = self.formatting_position(element.id) {
+            // The end tag of a reopened element ends the original too
+            if let Formatting::Element { original, .. } = self.formatting.remove(position) {
+                if original != element.id {
+                    self.settled.push(Settled { id: original, ended: true });
+                }
+            }
+        }
+        self.clear_formatting_to_marker(&element);
+        self.closed.push(Closed { id: element.id, at: span.start, end_tag: Some(span), pending: false });
+    }
+
+    /// Closes whatever is still open at the end of the input, at offset `end`
+    pub(crate) fn finish(&mut self, end: usize) {
+        while !self.formatting.is_empty() {
+            self.drop_formatting(self.formatting.len() - 1, false);
+        }
+        while let Some(element) = self.stack.pop() {
+            if !has_optional_end_tag(&element.name) && !element.reopened {
+                let message = format!("<{}> is never closed", element.name);
+                self.report(DiagnosticKind::UnclosedElement, message, element.start_tag);
+            }
+            self.closed.push(Closed { id: element.id, at: end, end_tag: None, pending: false });
+        }
+    }
+
+    /// Closes the open `<li>` (or `<dd>` and `<dt>`) that a new one replaces,
+    /// unless it is outside the nearest enclosing list or other special element
+    fn close_list_item(&mut self, targets: &[&str], at: usize, cause: &str) {
+        for index in (0..self.stack.len()).rev() {
+            let name = &self.stack[index].name;
+            if is_one_of(name, targets) {
+                self.close_from(index, at, cause);
+                return;
+            }
+            if is_one_of(name, SPECIAL) {
+                return;
+            }
+        }
+    }
+
+    /// Index in the stack of the innermost element named in `targets`, as
+    /// long as no element named in `boundaries` is open inside it
+    fn find_open(&self, targets: &[&str], boundaries: &[&str]) -> Option<usize> {
+        for index in (0..self.stack.len()).rev() {
+            let name = &self.stack[index].name;
+            if is_one_of(name, targets) {
+                return Some(index);
+            }
+            if is_one_of(name, boundaries) {
+                return None;
+            }
+        }
+        None
+    }
+
+    fn current_is(&self, names: &[&str]) -> bool {
+        self.current_name().is_some_and(|name| is_one_of(name, names))
+    }
+
+    /// Closes the element at `index` in the stack, and everything opened
+    /// inside it, without an end tag; `cause` is the tag that closes them
+    fn close_from(&mut self, index: usize, at: usize, cause: &str) {
+        let closed: Vec<OpenElement> = self.stack.drain(index..).collect();
+        for element in closed.into_iter().rev() {
+            // A reopened element was reported when it was first closed
+            if !has_optional_end_tag(&element.name) && !element.reopened {
+                let message = format!("<{}> is closed by {} before its own end tag", element.name, cause);
+                self.report(DiagnosticKind::MisnestedTag, message, element.start_tag);
+            }
+            self.clear_formatting_to_marker(&element);
+            let pending = self.formatting_position(element.id).is_some_and(|position| {
+                matches!(self.formatting[position], Formatting::Element { original, .. } if original == element.id)
+            });
+            self.closed.push(Closed { id: element.id, at, end_tag: None, pending });
+        }
+    }
+
+    /// Drops the formatting elements that became active inside `element`
+    /// once it closes, if it is a table cell or another element with a marker
+    fn clear_formatting_to_marker(&mut self, element: &OpenElement) {
+        if is_one_of(&element.name, MARKERS) {
+            while let Some(entry) = self.formatting.last() {
+                if matches!(entry, Formatting::Marker) {
+                    self.formatting.pop();
+                    break;
+                }
+                self.drop_formatting(self.formatting.len() - 1, false);
+            }
+        }
+    }
+
+    /// Index in the list of active formatting elements of the element `id`
+    fn formatting_position(&self, id: usize) -> Option<usize> {
+        self.formatting
+            .iter()
+            .rposition(|entry| matches!(entry, Formatting::Element { id: active, .. } if *active == id))
+    }
+
+    /// Removes the entry at `index` from the list of active formatting
+    /// elements, settling its element if it was closed before its end tag
+    fn drop_formatting(&mut self, index: usize, ended: bool) {
+        if let Formatting::Element { original, .. } = self.formatting.remove(index) {
+            // Still open elements are closed as usual
+            if !self.stack.iter().any(|open| open.id == original) {
+                self.settled.push(Settled { id: original, ended });
+            }
+        }
+    }
+
+    fn report(&mut self, kind: DiagnosticKind, message: String, span: Span) {
+        self.diagnostics.push(RawDiagnostic::new(kind, message, span));
+    }
+}
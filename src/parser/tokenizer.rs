@@ -0,0 +1,712 @@
+use crate::parser::diagnostics::{DiagnosticKind, RawDiagnostic};
+
+/// A byte range in the source HTML
+///
+/// `start` is inclusive and `end` is exclusive, so `&html[span.start..span.end]`
+/// gives back the exact source text the span covers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Span { start, end }
+    }
+
+    /// Length of the span in bytes
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    /// Returns `true` if the span covers no bytes
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+}
+
+/// A single attribute of a start tag, exactly as written in the source
+///
+/// `value` is the raw value without its surrounding quotes. Attributes written
+/// without a value (`<input disabled>`) have an empty `value`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Attribute<'a> {
+    pub name: &'a str,
+    pub value: &'a str,
+    pub span: Span,
+}
+
+/// An opening tag such as `<a href="https://github.com">` or `<br/>`
+///
+/// When a tag repeats an attribute name (compared ASCII case-insensitively),
+/// only the first occurrence is kept, as browsers do.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StartTag<'a> {
+    pub name: &'a str,
+    pub attributes: Vec<Attribute<'a>>,
+    pub self_closing: bool,
+    pub span: Span,
+}
+
+impl<'a> StartTag<'a> {
+    /// Returns the attribute with the given name, compared ASCII case-insensitively
+    pub fn attribute(&self, name: &str) -> Option<&Attribute<'a>> {
+        self.attributes.iter().find(|attr| attr.name.eq_ignore_ascii_case(name))
+    }
+}
+
+/// A closing tag such as `</a>`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EndTag<'a> {
+    pub name: &'a str,
+    pub span: Span,
+}
+
+/// Elements whose content is raw text: it runs to the matching end tag, and
+/// neither tags nor character references are recognised inside it
+const RAW_TEXT: &[&str] = &["script", "style", "xmp", "iframe", "noembed", "noframes"];
+
+/// Elements whose content is escapable raw text: it runs to the matching end
+/// tag and holds no tags, but character references are still decoded
+const ESCAPABLE_RAW_TEXT: &[&str] = &["textarea", "title"];
+
+/// Returns `true` if the text inside an element with this name is taken
+/// literally, without decoding character references
+pub(crate) fn is_raw_text_element(name: &str) -> bool {
+    RAW_TEXT.iter().chain(["plaintext"].iter()).any(|raw| name.eq_ignore_ascii_case(raw))
+}
+
+/// A token produced by the [`Tokenizer`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Token<'a> {
+    /// An opening (or self-closing) tag
+    StartTag(StartTag<'a>),
+    /// A closing tag
+    EndTag(EndTag<'a>),
+    /// A run of character data between tags
+    Text { text: &'a str, span: Span },
+    /// A comment; `text` excludes the `<!--` and `-->` delimiters
+    ///
+    /// `<![CDATA[ ... ]]>` sections are also comments, as they are in HTML
+    /// content; their `text` excludes the `<![CDATA[` and `]]>` delimiters.
+    Comment { text: &'a str, span: Span },
+    /// A doctype declaration; `text` is what follows the `DOCTYPE` keyword
+    Doctype { text: &'a str, span: Span },
+}
+
+impl Token<'_> {
+    /// The source span covered by this token
+    pub fn span(&self) -> Span {
+        match self {
+            Token::StartTag(tag) => tag.span,
+            Token::EndTag(tag) => tag.span,
+            Token::Text { span, .. } | Token::Comment { span, .. } | Token::Doctype { span, .. } => *span,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Data,
+    TagOpen,
+    EndTagOpen,
+    TagName,
+    BeforeAttributeName,
+    AttributeName,
+    AfterAttributeName,
+    BeforeAttributeValue,
+    AttributeValueDoubleQuoted,
+    AttributeValueSingleQuoted,
+    AttributeValueUnquoted,
+    AfterAttributeValueQuoted,
+    SelfClosingStartTag,
+    MarkupDeclarationOpen,
+    Comment,
+    CData,
+    BogusComment,
+    Doctype,
+    /// Inside a raw-text or escapable raw-text element, up to its end tag
+    RawText,
+    /// Inside `<plaintext>`, which runs to the end of the input
+    PlainText,
+}
+
+/// The tag currently being built by the tokenizer
+#[derive(Debug, Default)]
+struct PendingTag {
+    is_end: bool,
+    name_start: usize,
+    name_end: usize,
+    self_closing: bool,
+    attributes: Vec<PendingAttribute>,
+    attribute: Option<PendingAttribute>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct PendingAttribute {
+    start: usize,
+    name_end: usize,
+    value: Span,
+    end: usize,
+}
+
+/// A state-machine HTML tokenizer
+///
+/// The tokenizer walks the input once and yields [`Token`]s in document order.
+/// It follows the tokenization states of the HTML specification closely enough
+/// that quoted attribute values may contain `>`, tags may span several lines,
+/// and a `<` that doesn't start a tag is kept as text.
+///
+/// The content of `<script>`, `<style>`, `<textarea>`, `<title>` and the other
+/// raw-text elements is a single text token that runs to the element's end
+/// tag, so markup inside a script string is never mistaken for a tag. Comments
+/// and CDATA sections are likewise single tokens.
+///
+/// Tokens borrow from the input, so tokenizing never copies the document.
+///
+/// # Examples
+///
+/// ```
+/// use tagparser::parser::tokenizer::{Token, Tokenizer};
+///
+/// let html = "<a href='x>y'>Link</a><!-- note -->";
+/// let tokens: Vec<Token> = Tokenizer::new(html).collect();
+///
+/// match &tokens[0] {
+///     Token::StartTag(tag) => {
+///         assert_eq!(tag.name, "a");
+///         assert_eq!(tag.attribute("href").unwrap().value, "x>y");
+///     }
+///     _ => unreachable!(),
+/// }
+/// assert!(matches!(tokens[1], Token::Text { text: "Link", .. }));
+/// assert!(matches!(tokens[2], Token::EndTag(_)));
+/// assert!(matches!(tokens[3], Token::Comment { text: " note ", .. }));
+/// ```
+pub struct Tokenizer<'a> {
+    input: &'a str,
+    pos: usize,
+    state: State,
+    text_start: usize,
+    tag_start: usize,
+    tag: PendingTag,
+    /// Name of the raw-text element whose content is being read
+    raw_text_element: &'a str,
+    diagnostics: Vec<RawDiagnostic>,
+}
+
+impl<'a> Tokenizer<'a> {
+    pub fn new(input: &'a str) -> Self {
+        Tokenizer {
+            input,
+            pos: 0,
+            state: State::Data,
+            text_start: 0,
+            tag_start: 0,
+            tag: PendingTag::default(),
+            raw_text_element: "",
+            diagnostics: Vec::new(),
+        }
+    }
+
+    /// Takes the problems found in the input read so far
+    pub(crate) fn take_diagnostics(&mut self) -> Vec<RawDiagnostic> {
+        std::mem::take(&mut self.diagnostics)
+    }
+
+    fn report(&mut self, kind: DiagnosticKind, message: String, span: Span) {
+        self.diagnostics.push(RawDiagnostic::new(kind, message, span));
+    }
+
+    /// Emits the text collected so far, up to (but not including) `end`
+    fn take_text(&mut self, end: usize) -> Option<Token<'a>> {
+        let start = self.text_start;
+        self.text_start = end;
+        if start < end {
+            Some(Token::Text {
+                text: &self.input[start..end],
+                span: Span::new(start, end),
+            })
+        } else {
+            None
+        }
+    }
+
+    fn begin_tag(&mut self, is_end: bool) {
+        self.tag = PendingTag {
+            is_end,
+            name_start: self.pos,
+            ..PendingTag::default()
+        };
+        self.state = State::TagName;
+    }
+
+    fn begin_attribute(&mut self) {
+        self.tag.attribute = Some(PendingAttribute {
+            start: self.pos,
+            name_end: self.pos,
+            value: Span::new(self.pos, self.pos),
+            end: self.pos,
+        });
+    }
+
+    /// Adds the pending attribute to the tag, unless the tag already has one
+    /// with the same name, in which case the first one wins
+    fn finish_attribute(&mut self) {
+        if let Some(mut attribute) = self.tag.attribute.take() {
+            attribute.end = attribute.end.max(attribute.name_end);
+            let name = &self.input[attribute.start..attribute.name_end];
+            let duplicate = self
+                .tag
+                .attributes
+                .iter()
+                .any(|other| self.input[other.start..other.name_end].eq_ignore_ascii_case(name));
+            if duplicate {
+                let span = Span::new(attribute.start, attribute.end);
+                self.report(
+                    DiagnosticKind::DuplicateAttribute,
+                    format!("duplicate attribute `{}`; the first one is kept", name),
+                    span,
+                );
+            } else {
+                self.tag.attributes.push(attribute);
+            }
+        }
+    }
+
+    /// Finishes the current tag at the `>` under the cursor
+    fn emit_tag(&mut self) -> Token<'a> {
+        self.finish_attribute();
+        self.pos += 1;
+        self.state = State::Data;
+        self.text_start = self.pos;
+
+        let span = Span::new(self.tag_start, self.pos);
+        let name = &self.input[self.tag.name_start..self.tag.name_end];
+        if self.tag.is_end {
+            return Token::EndTag(EndTag { name, span });
+        }
+
+        let attributes = self
+            .tag
+            .attributes
+            .iter()
+            .map(|attr| Attribute {
+                name: &self.input[attr.start..attr.name_end],
+                value: &self.input[attr.value.start..attr.value.end],
+                span: Span::new(attr.start, attr.end),
+            })
+            .collect();
+
+        // The content of raw-text elements is read as text up to the end tag
+        if !self.tag.self_closing {
+            if name.eq_ignore_ascii_case("plaintext") {
+                self.state = State::PlainText;
+            } else if RAW_TEXT.iter().chain(ESCAPABLE_RAW_TEXT).any(|raw| name.eq_ignore_ascii_case(raw)) {
+                self.raw_text_element = name;
+                self.state = State::RawText;
+            }
+        }
+
+        Token::StartTag(StartTag {
+            name,
+            attributes,
+            self_closing: self.tag.self_closing,
+            span,
+        })
+    }
+
+    /// Finds the end tag that closes the current raw-text element, returning
+    /// the offset of its `<`, or the end of the input if there is none
+    fn raw_text_end(&self) -> usize {
+        let name = self.raw_text_element;
+        self.input[self.pos..]
+            .match_indices("</")
+            .map(|(index, _)| self.pos + index)
+            .find(|&start| {
+                let after = &self.input.as_bytes()[start + 2..];
+                after.len() >= name.len()
+                    && after[..name.len()].eq_ignore_ascii_case(name.as_bytes())
+                    && matches!(
+                        after.get(name.len()),
+                        Some(b'\t' | b'\n' | b'\x0C' | b'\r' | b' ' | b'/' | b'>')
+                    )
+            })
+            .unwrap_or(self.input.len())
+    }
+
+    fn set_attribute_name_end(&mut self) {
+        if let Some(attribute) = self.tag.attribute.as_mut() {
+            attribute.name_end = self.pos;
+            attribute.value = Span::new(self.pos, self.pos);
+            attribute.end = self.pos;
+        }
+    }
+
+    fn set_attribute_value_start(&mut self) {
+        if let Some(attribute) = self.tag.attribute.as_mut() {
+            attribute.value = Span::new(self.pos, self.pos);
+        }
+    }
+
+    fn set_attribute_value_end(&mut self) {
+        if let Some(attribute) = self.tag.attribute.as_mut() {
+            attribute.value.end = self.pos;
+        }
+    }
+
+    fn set_attribute_end(&mut self) {
+        if let Some(attribute) = self.tag.attribute.as_mut() {
+            attribute.end = self.pos;
+        }
+    }
+
+    /// Drops a tag that was cut off by the end of the input
+    fn discard_tag(&mut self) {
+        let message = match self.state {
+            State::AttributeValueDoubleQuoted | State::AttributeValueSingleQuoted => {
+                "attribute value is missing its closing quote; the tag is dropped"
+            }
+            _ => "tag is cut off by the end of the input and dropped",
+        };
+        let span = Span::new(self.tag_start, self.input.len());
+        self.report(DiagnosticKind::UnterminatedTag, message.to_string(), span);
+        self.state = State::Data;
+        self.text_start = self.pos;
+    }
+
+    /// Finishes a comment-like token whose content ends at `content_end` and
+    /// whose closing delimiter ends at `end`
+    fn emit_markup(&mut self, content_start: usize, content_end: usize, end: usize) -> Token<'a> {
+        let text = &self.input[content_start..content_end];
+        let span = Span::new(self.tag_start, end);
+        if content_end == self.input.len() {
+            let what = match self.state {
+                State::Doctype => "doctype",
+                State::CData => "CDATA section",
+                _ => "comment",
+            };
+            self.report(
+                DiagnosticKind::UnterminatedComment,
+                format!("{} is cut off by the end of the input", what),
+                span,
+            );
+        }
+        let token = if self.state == State::Doctype {
+            Token::Doctype { text: text.trim(), span }
+        } else {
+            Token::Comment { text, span }
+        };
+        self.pos = end;
+        self.state = State::Data;
+        self.text_start = end;
+        token
+    }
+}
+
+impl<'a> Iterator for Tokenizer<'a> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Token<'a>> {
+        let bytes = self.input.as_bytes();
+
+        loop {
+            let c = bytes.get(self.pos).copied();
+
+            match self.state {
+                State::Data => match c {
+                    None => return self.take_text(self.pos),
+                    Some(b'<') => {
+                        self.tag_start = self.pos;
+                        self.pos += 1;
+                        self.state = State::TagOpen;
+                    }
+                    Some(_) => self.pos += 1,
+                },
+
+                State::TagOpen => match c {
+                    Some(b'!') => {
+                        self.pos += 1;
+                        self.state = State::MarkupDeclarationOpen;
+                        if let Some(text) = self.take_text(self.tag_start) {
+                            return Some(text);
+                        }
+                    }
+                    Some(b'/') => {
+                        self.pos += 1;
+                        self.state = State::EndTagOpen;
+                    }
+                    Some(c) if c.is_ascii_alphabetic() => {
+                        self.begin_tag(false);
+                        if let Some(text) = self.take_text(self.tag_start) {
+                            return Some(text);
+                        }
+                    }
+                    Some(b'?') => {
+                        self.state = State::BogusComment;
+                        if let Some(text) = self.take_text(self.tag_start) {
+                            return Some(text);
+                        }
+                    }
+                    // Not a tag after all: the `<` stays part of the text
+                    _ => self.state = State::Data,
+                },
+
+                State::EndTagOpen => match c {
+                    Some(c) if c.is_ascii_alphabetic() => {
+                        self.begin_tag(true);
+                        if let Some(text) = self.take_text(self.tag_start) {
+                            return Some(text);
+                        }
+                    }
+                    Some(b'>') => {
+                        // `</>` is dropped entirely
+                        let text = self.take_text(self.tag_start);
+                        self.pos += 1;
+                        let span = Span::new(self.tag_start, self.pos);
+                        self.report(DiagnosticKind::StrayEndTag, "empty end tag `</>` is ignored".to_string(), span);
+                        self.text_start = self.pos;
+                        self.state = State::Data;
+                        if text.is_some() {
+                            return text;
+                        }
+                    }
+                    None => self.state = State::Data,
+                    Some(_) => {
+                        self.state = State::BogusComment;
+                        if let Some(text) = self.take_text(self.tag_start) {
+                            return Some(text);
+                        }
+                    }
+                },
+
+                State::TagName => match c {
+                    Some(b'\t' | b'\n' | b'\x0C' | b'\r' | b' ') => {
+                        self.tag.name_end = self.pos;
+                        self.state = State::BeforeAttributeName;
+                    }
+                    Some(b'/') => {
+                        self.tag.name_end = self.pos;
+                        self.pos += 1;
+                        self.state = State::SelfClosingStartTag;
+                    }
+                    Some(b'>') => {
+                        self.tag.name_end = self.pos;
+                        return Some(self.emit_tag());
+                    }
+                    None => self.discard_tag(),
+                    Some(_) => self.pos += 1,
+                },
+
+                State::BeforeAttributeName => match c {
+                    Some(b'\t' | b'\n' | b'\x0C' | b'\r' | b' ') => self.pos += 1,
+                    Some(b'/' | b'>') | None => self.state = State::AfterAttributeName,
+                    Some(_) => {
+                        self.begin_attribute();
+                        // A leading `=` is part of the attribute name
+                        self.pos += 1;
+                        self.state = State::AttributeName;
+                    }
+                },
+
+                State::AttributeName => match c {
+                    Some(b'\t' | b'\n' | b'\x0C' | b'\r' | b' ' | b'/' | b'>') | None => {
+                        self.set_attribute_name_end();
+                        self.state = State::AfterAttributeName;
+                    }
+                    Some(b'=') => {
+                        self.set_attribute_name_end();
+                        self.pos += 1;
+                        self.state = State::BeforeAttributeValue;
+                    }
+                    Some(_) => self.pos += 1,
+                },
+
+                State::AfterAttributeName => match c {
+                    Some(b'\t' | b'\n' | b'\x0C' | b'\r' | b' ') => self.pos += 1,
+                    Some(b'/') => {
+                        self.finish_attribute();
+                        self.pos += 1;
+                        self.state = State::SelfClosingStartTag;
+                    }
+                    Some(b'=') => {
+                        self.pos += 1;
+                        self.state = State::BeforeAttributeValue;
+                    }
+                    Some(b'>') => return Some(self.emit_tag()),
+                    None => self.discard_tag(),
+                    Some(_) => {
+                        self.finish_attribute();
+                        self.begin_attribute();
+                        self.pos += 1;
+                        self.state = State::AttributeName;
+                    }
+                },
+
+                State::BeforeAttributeValue => match c {
+                    Some(b'\t' | b'\n' | b'\x0C' | b'\r' | b' ') => self.pos += 1,
+                    Some(b'"') => {
+                        self.pos += 1;
+                        self.set_attribute_value_start();
+                        self.state = State::AttributeValueDoubleQuoted;
+                    }
+                    Some(b'\'') => {
+                        self.pos += 1;
+                        self.set_attribute_value_start();
+                        self.state = State::AttributeValueSingleQuoted;
+                    }
+                    Some(b'>') => return Some(self.emit_tag()),
+                    _ => {
+                        self.set_attribute_value_start();
+                        self.state = State::AttributeValueUnquoted;
+                    }
+                },
+
+                State::AttributeValueDoubleQuoted | State::AttributeValueSingleQuoted => {
+                    let quote = if self.state == State::AttributeValueDoubleQuoted { b'"' } else { b'\'' };
+                    match c {
+                        Some(c) if c == quote => {
+                            self.set_attribute_value_end();
+                            self.pos += 1;
+                            self.set_attribute_end();
+                            self.state = State::AfterAttributeValueQuoted;
+                        }
+                        None => self.discard_tag(),
+                        Some(_) => self.pos += 1,
+                    }
+                }
+
+                State::AttributeValueUnquoted => match c {
+                    Some(b'\t' | b'\n' | b'\x0C' | b'\r' | b' ') => {
+                        self.set_attribute_value_end();
+                        self.set_attribute_end();
+                        self.finish_attribute();
+                        self.state = State::BeforeAttributeName;
+                    }
+                    Some(b'>') => {
+                        self.set_attribute_value_end();
+                        self.set_attribute_end();
+                        return Some(self.emit_tag());
+                    }
+                    None => self.discard_tag(),
+                    Some(_) => self.pos += 1,
+                },
+
+                State::AfterAttributeValueQuoted => match c {
+                    Some(b'\t' | b'\n' | b'\x0C' | b'\r' | b' ') => {
+                        self.finish_attribute();
+                        self.state = State::BeforeAttributeName;
+                    }
+                    Some(b'/') => {
+                        self.finish_attribute();
+                        self.pos += 1;
+                        self.state = State::SelfClosingStartTag;
+                    }
+                    Some(b'>') => return Some(self.emit_tag()),
+                    None => self.discard_tag(),
+                    Some(_) => {
+                        let span = Span::new(self.pos, self.pos);
+                        self.report(DiagnosticKind::MissingWhitespace, "missing whitespace between attributes".to_string(), span);
+                        self.finish_attribute();
+                        self.state = State::BeforeAttributeName;
+                    }
+                },
+
+                State::SelfClosingStartTag => match c {
+                    Some(b'>') => {
+                        self.tag.self_closing = true;
+                        return Some(self.emit_tag());
+                    }
+                    None => self.discard_tag(),
+                    Some(_) => self.state = State::BeforeAttributeName,
+                },
+
+                State::MarkupDeclarationOpen => {
+                    let rest = &self.input[self.pos..];
+                    if rest.starts_with("--") {
+                        self.pos += 2;
+                        self.state = State::Comment;
+                    } else if rest.starts_with("[CDATA[") {
+                        self.pos += 7;
+                        self.state = State::CData;
+                    } else if rest.len() >= 7 && rest.as_bytes()[..7].eq_ignore_ascii_case(b"doctype") {
+                        self.pos += 7;
+                        self.state = State::Doctype;
+                    } else {
+                        self.state = State::BogusComment;
+                    }
+                }
+
+                State::Comment => {
+                    let start = self.pos;
+                    let rest = &self.input[start..];
+
+                    // `<!-->` and `<!--->` close the comment immediately
+                    if rest.starts_with('>') {
+                        return Some(self.emit_markup(start, start, start + 1));
+                    }
+                    if rest.starts_with("->") {
+                        return Some(self.emit_markup(start, start, start + 2));
+                    }
+
+                    let token = match (rest.find("-->"), rest.find("--!>")) {
+                        (Some(a), Some(b)) if b < a => self.emit_markup(start, start + b, start + b + 4),
+                        (Some(a), _) => self.emit_markup(start, start + a, start + a + 3),
+                        (None, Some(b)) => self.emit_markup(start, start + b, start + b + 4),
+                        (None, None) => self.emit_markup(start, self.input.len(), self.input.len()),
+                    };
+                    return Some(token);
+                }
+
+                State::CData => {
+                    let start = self.pos;
+                    let token = match self.input[start..].find("]]>") {
+                        Some(end) => self.emit_markup(start, start + end, start + end + 3),
+                        None => self.emit_markup(start, self.input.len(), self.input.len()),
+                    };
+                    return Some(token);
+                }
+
+                State::RawText => {
+                    self.pos = self.raw_text_end();
+                    // The end tag itself is read in the data state
+                    self.state = State::Data;
+                    if let Some(text) = self.take_text(self.pos) {
+                        return Some(text);
+                    }
+                }
+
+                State::PlainText => {
+                    self.pos = self.input.len();
+                    self.state = State::Data;
+                    if let Some(text) = self.take_text(self.pos) {
+                        return Some(text);
+                    }
+                }
+
+                State::BogusComment | State::Doctype => {
+                    // Bogus comments keep everything after `<!` or `</`, and the `?` of `<?`
+                    let start = match self.state {
+                        State::Doctype => self.pos,
+                        _ if self.input[self.tag_start..].starts_with("<?") => self.tag_start + 1,
+                        _ => self.tag_start + 2,
+                    };
+                    let token = match self.input[start..].find('>') {
+                        Some(end) => self.emit_markup(start, start + end, start + end + 1),
+                        None => self.emit_markup(start, self.input.len(), self.input.len()),
+                    };
+                    if let Token::Comment { span, .. } = token {
+                        let message = if self.input[span.start..].starts_with("<?") {
+                            "processing instruction is read as a comment"
+                        } else {
+                            "malformed markup is read as a comment"
+                        };
+                        self.report(DiagnosticKind::BogusComment, message.to_string(), span);
+                    }
+                    return Some(token);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,216 @@
+use std::borrow::Cow;
+
+use crate::dom::{Document, ElementData, NodeData, NodeId, NodeValue};
+use crate::parser::diagnostics::{DiagnosticKind, RawDiagnostic};
+use crate::parser::open_elements::{Closed, OpenElements, Settled};
+use crate::parser::tokenizer::{is_raw_text_element, Span, Token, Tokenizer};
+
+/// Elements that never have content or an end tag, such as `<br>` and `<img>`
+const VOID: &[&str] = &[
+    "area", "base", "basefont", "bgsound", "br", "col", "embed", "frame", "hr", "img", "input", "keygen", "link",
+    "meta", "param", "source", "track", "wbr",
+];
+
+/// Elements whose end tag may be left out, to be implied by what follows them
+const OPTIONAL_END_TAG: &[&str] = &[
+    "body", "caption", "colgroup", "dd", "dt", "head", "html", "li", "optgroup", "option", "p", "rb", "rp", "rt",
+    "rtc", "tbody", "td", "tfoot", "th", "thead", "tr",
+];
+
+fn is_one_of(name: &str, names: &[&str]) -> bool {
+    names.iter().any(|candidate| name.eq_ignore_ascii_case(candidate))
+}
+
+/// Returns `true` for elements that never have content or an end tag
+pub(crate) fn is_void_element(name: &str) -> bool {
+    is_one_of(name, VOID)
+}
+
+/// Returns `true` for elements whose end tag may be left out
+pub(crate) fn has_optional_end_tag(name: &str) -> bool {
+    is_one_of(name, OPTIONAL_END_TAG)
+}
+
+/// Builds the element tree for `source`
+///
+/// Start and end tags are paired the way browsers pair them, by the rules of
+/// [`OpenElements`]: misnested and stray end tags are recovered from, left-out
+/// end tags are implied, and whatever is still open at the end of the input
+/// is closed there. Everything that had to be recovered from is reported as a
+/// diagnostic.
+///
+/// Void elements such as `<br>` and `<img>` are never opened. Formatting
+/// elements closed before their end tags are opened again around the content
+/// that follows, as copies without a start tag in the source.
+pub(crate) fn build(source: Cow<'_, str>) -> Document<'_> {
+    let (nodes, diagnostics) = TreeBuilder::new(&source).run();
+    Document::from_nodes(source, nodes, diagnostics)
+}
+
+struct TreeBuilder<'a> {
+    source: &'a str,
+    nodes: Vec<NodeData>,
+    open: OpenElements<'a>,
+    diagnostics: Vec<RawDiagnostic>,
+}
+
+impl<'a> TreeBuilder<'a> {
+    fn new(source: &'a str) -> Self {
+        let root = NodeData {
+            value: NodeValue::Document,
+            span: Span::new(0, source.len()),
+            parent: None,
+            first_child: None,
+            last_child: None,
+            prev_sibling: None,
+            next_sibling: None,
+        };
+        TreeBuilder { source, nodes: vec![root], open: OpenElements::new(), diagnostics: Vec::new() }
+    }
+
+    fn run(mut self) -> (Vec<NodeData>, Vec<RawDiagnostic>) {
+        let mut tokenizer = Tokenizer::new(self.source);
+        for token in tokenizer.by_ref() {
+            match token {
+                Token::StartTag(tag) => {
+                    let element = ElementData {
+                        name: self.span_of(tag.name),
+                        attributes: tag
+                            .attributes
+                            .iter()
+                            .map(|attr| (self.span_of(attr.name), self.span_of(attr.value)))
+                            .collect(),
+                        start_tag: tag.span,
+                        end_tag: None,
+                        self_closing: tag.self_closing,
+                        content: Span::new(tag.span.end, tag.span.end),
+                        reopened: false,
+                        misnested_end_tag: false,
+                    };
+                    if tag.self_closing && !is_void_element(tag.name) {
+                        self.report(
+                            DiagnosticKind::SelfClosingNonVoid,
+                            format!("<{}/> is read as an empty element here, but browsers ignore the slash on non-void elements", tag.name),
+                            tag.span,
+                        );
+                    }
+                    self.open.start_tag(tag.name, tag.span.start);
+                    self.close_elements();
+                    self.reopen_formatting(Some(tag.name), tag.span.start);
+                    let id = self.append(NodeValue::Element(element), tag.span);
+                    if !tag.self_closing && !is_void_element(tag.name) {
+                        self.open.push(id.0, Cow::Borrowed(tag.name), &tag.attributes, tag.span);
+                    }
+                }
+                Token::EndTag(tag) => {
+                    self.open.end_tag(tag.name, tag.span);
+                    self.close_elements();
+                }
+                Token::Text { span, .. } => {
+                    self.reopen_formatting(None, span.start);
+                    // Only raw-text elements hold text that must not be decoded
+                    let raw = self.open.current_name().is_some_and(is_raw_text_element);
+                    self.append(NodeValue::Text { raw }, span);
+                }
+                Token::Comment { text, span } => {
+                    let text = self.span_of(text);
+                    self.append(NodeValue::Comment(text), span);
+                }
+                Token::Doctype { text, span } => {
+                    let text = self.span_of(text);
+                    self.append(NodeValue::Doctype(text), span);
+                }
+            }
+        }
+
+        self.open.finish(self.source.len());
+        self.close_elements();
+
+        // Problems found by the tokenizer and the tree builder, in source order
+        let mut diagnostics = tokenizer.take_diagnostics();
+        diagnostics.append(&mut self.diagnostics);
+        diagnostics.append(&mut self.open.take_diagnostics());
+        diagnostics.sort_by_key(|diagnostic| diagnostic.span.start);
+        (self.nodes, diagnostics)
+    }
+
+    /// Ends the elements the stack of open elements has closed, and marks
+    /// the misnested formatting elements whose end tags were found later
+    fn close_elements(&mut self) {
+        for Closed { id, at, end_tag, .. } in self.open.take_closed() {
+            self.close(NodeId(id), at, end_tag);
+        }
+        for Settled { id, ended } in self.open.take_settled() {
+            if let NodeValue::Element(element) = &mut self.nodes[id].value {
+                element.misnested_end_tag = ended;
+            }
+        }
+    }
+
+    /// Opens again the formatting elements that were closed before their end
+    /// tags, ahead of a start tag called `start_tag` or of text at `at`
+    ///
+    /// Each copy has the name and attributes of the original element and an
+    /// empty start tag at `at`, since nothing in the source opens it.
+    fn reopen_formatting(&mut self, start_tag: Option<&str>, at: usize) {
+        for original in self.open.formatting_to_reopen(start_tag) {
+            let NodeValue::Element(element) = &self.nodes[original].value else {
+                continue;
+            };
+            let element = ElementData {
+                start_tag: Span::new(at, at),
+                end_tag: None,
+                self_closing: false,
+                content: Span::new(at, at),
+                reopened: true,
+                ..element.clone()
+            };
+            let id = self.append(NodeValue::Element(element), Span::new(at, at));
+            self.open.reopen(original, id.0, at);
+        }
+    }
+
+    fn report(&mut self, kind: DiagnosticKind, message: String, span: Span) {
+        self.diagnostics.push(RawDiagnostic::new(kind, message, span));
+    }
+
+    /// Position of `part`, a slice of the source, within the source
+    fn span_of(&self, part: &str) -> Span {
+        let start = part.as_ptr() as usize - self.source.as_ptr() as usize;
+        Span::new(start, start + part.len())
+    }
+
+    /// Appends a node as the last child of the current open element
+    fn append(&mut self, value: NodeValue, span: Span) -> NodeId {
+        let parent = self.open.current_id().map_or(NodeId(0), NodeId);
+        let id = NodeId(self.nodes.len());
+        let prev_sibling = self.nodes[parent.0].last_child;
+
+        self.nodes.push(NodeData {
+            value,
+            span,
+            parent: Some(parent),
+            first_child: None,
+            last_child: None,
+            prev_sibling,
+            next_sibling: None,
+        });
+
+        match prev_sibling {
+            Some(prev) => self.nodes[prev.0].next_sibling = Some(id),
+            None => self.nodes[parent.0].first_child = Some(id),
+        }
+        self.nodes[parent.0].last_child = Some(id);
+        id
+    }
+
+    /// Ends an element at `at`, the position where its content stops
+    fn close(&mut self, id: NodeId, at: usize, end_tag: Option<Span>) {
+        let data = &mut self.nodes[id.0];
+        if let NodeValue::Element(element) = &mut data.value {
+            element.content = Span::new(element.start_tag.end, at);
+            element.end_tag = end_tag;
+            data.span = Span::new(element.start_tag.start, end_tag.map_or(at, |span| span.end));
+        }
+    }
+}
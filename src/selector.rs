@@ -0,0 +1,691 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::dom::Node;
+
+/// An error found while parsing a CSS selector
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SelectorError {
+    /// What went wrong
+    pub message: String,
+    /// Byte offset in the selector where the problem was found
+    pub position: usize,
+}
+
+impl fmt::Display for SelectorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid selector at position {}: {}", self.position, self.message)
+    }
+}
+
+impl std::error::Error for SelectorError {}
+
+/// A parsed CSS selector list such as `div.article > h2, ul li:nth-child(2)`
+///
+/// Supported syntax:
+///
+/// * type (`a`), universal (`*`), class (`.nav`) and id (`#main`) selectors
+/// * attribute selectors: `[href]`, `[type=text]`, `[class~=btn]`, `[lang|=en]`,
+///   `[href^="https"]`, `[src$=".png"]`, `[title*=rust]`, with an optional `i`
+///   flag for case-insensitive values (`[type="TEXT" i]`)
+/// * combinators: descendant (` `), child (`>`), next sibling (`+`) and
+///   subsequent sibling (`~`)
+/// * selector lists separated by commas
+/// * pseudo-classes: `:root`, `:empty`, `:scope`, `:first-child`, `:last-child`,
+///   `:only-child`, `:first-of-type`, `:last-of-type`, `:only-of-type`,
+///   `:nth-child(An+B [of S])`, `:nth-last-child()`, `:nth-of-type()`,
+///   `:nth-last-of-type()`, `:not()`, `:is()`, `:where()` and `:has()`
+///
+/// Type selectors and attribute names match ASCII case-insensitively, as they
+/// do for HTML documents in browsers.
+///
+/// # Examples
+///
+/// ```
+/// use tagparser::{Document, Selector};
+///
+/// let document = Document::parse("<ul><li>One</li><li class='active'>Two</li></ul>");
+/// let selector = Selector::parse("ul > li:nth-child(2).active").unwrap();
+///
+/// let matches: Vec<String> = document
+///     .elements()
+///     .filter(|node| selector.matches(node))
+///     .map(|node| node.text())
+///     .collect();
+/// assert_eq!(matches, vec!["Two"]);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Selector {
+    list: Vec<Complex>,
+}
+
+impl Selector {
+    /// Parses a selector list
+    pub fn parse(selector: &str) -> Result<Selector, SelectorError> {
+        let mut parser = SelectorParser { input: selector, pos: 0 };
+        parser.skip_whitespace();
+        let list = parser.parse_list(false)?;
+        parser.skip_whitespace();
+        if let Some(c) = parser.peek() {
+            return Err(parser.error(format!("unexpected character '{}'", c)));
+        }
+        Ok(Selector { list })
+    }
+
+    /// Returns `true` if the node is an element matched by any selector in the list
+    pub fn matches(&self, node: &Node) -> bool {
+        node.is_element() && matches_any(node, &self.list, None)
+    }
+}
+
+impl FromStr for Selector {
+    type Err = SelectorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Selector::parse(s)
+    }
+}
+
+/// Compound selectors joined by combinators, e.g. `div.article > h2 a`
+///
+/// `combinators[i]` sits between `compounds[i]` and `compounds[i + 1]`.
+#[derive(Debug, Clone, PartialEq)]
+struct Complex {
+    compounds: Vec<Compound>,
+    combinators: Vec<Combinator>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Combinator {
+    Descendant,
+    Child,
+    NextSibling,
+    SubsequentSibling,
+}
+
+/// A type selector followed by any number of simple selectors, e.g. `a.nav[href]`
+#[derive(Debug, Clone, PartialEq, Default)]
+struct Compound {
+    /// `None` for `*` or when no type selector was given
+    name: Option<String>,
+    simple: Vec<Simple>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Simple {
+    Id(String),
+    Class(String),
+    Attribute {
+        name: String,
+        operator: Option<(AttrOperator, String)>,
+        case_insensitive: bool,
+    },
+    Pseudo(Pseudo),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AttrOperator {
+    Equals,
+    Includes,
+    DashMatch,
+    Prefix,
+    Suffix,
+    Substring,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Pseudo {
+    Root,
+    Empty,
+    Scope,
+    FirstChild,
+    LastChild,
+    OnlyChild,
+    FirstOfType,
+    LastOfType,
+    OnlyOfType,
+    NthChild(Nth, Option<Vec<Complex>>),
+    NthLastChild(Nth, Option<Vec<Complex>>),
+    NthOfType(Nth),
+    NthLastOfType(Nth),
+    Not(Vec<Complex>),
+    Is(Vec<Complex>),
+    Has(Vec<Complex>),
+}
+
+/// The `An+B` argument of the `:nth-*` pseudo-classes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Nth {
+    a: i64,
+    b: i64,
+}
+
+impl Nth {
+    /// Returns `true` if the 1-based `index` equals `a*n + b` for some `n >= 0`
+    fn matches(&self, index: i64) -> bool {
+        if self.a == 0 {
+            return index == self.b;
+        }
+        // Arguments too large for the arithmetic can't match any element
+        let Some(diff) = index.checked_sub(self.b) else {
+            return false;
+        };
+        diff.checked_rem(self.a) == Some(0) && diff.checked_div(self.a).is_some_and(|n| n >= 0)
+    }
+}
+
+struct SelectorParser<'s> {
+    input: &'s str,
+    pos: usize,
+}
+
+impl SelectorParser<'_> {
+    fn error(&self, message: impl Into<String>) -> SelectorError {
+        SelectorError { message: message.into(), position: self.pos }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.input[self.pos..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        if self.peek() == Some(expected) {
+            self.pos += expected.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), SelectorError> {
+        if self.eat(expected) {
+            Ok(())
+        } else {
+            Err(self.error(format!("expected '{}'", expected)))
+        }
+    }
+
+    /// Skips whitespace and returns `true` if there was any
+    fn skip_whitespace(&mut self) -> bool {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_whitespace()) {
+            self.pos += 1;
+        }
+        self.pos > start
+    }
+
+    /// Parses comma-separated complex selectors; `relative` allows a leading
+    /// combinator as used by `:has()`
+    fn parse_list(&mut self, relative: bool) -> Result<Vec<Complex>, SelectorError> {
+        let mut list = Vec::new();
+        loop {
+            self.skip_whitespace();
+            list.push(self.parse_complex(relative)?);
+            self.skip_whitespace();
+            if !self.eat(',') {
+                return Ok(list);
+            }
+        }
+    }
+
+    fn parse_complex(&mut self, relative: bool) -> Result<Complex, SelectorError> {
+        let mut compounds = Vec::new();
+        let mut combinators = Vec::new();
+
+        if relative {
+            // `:has(> a)` is matched as `:scope > a`
+            compounds.push(Compound {
+                name: None,
+                simple: vec![Simple::Pseudo(Pseudo::Scope)],
+            });
+            combinators.push(self.parse_combinator().unwrap_or(Combinator::Descendant));
+            self.skip_whitespace();
+        }
+        compounds.push(self.parse_compound()?);
+
+        loop {
+            let had_whitespace = self.skip_whitespace();
+            let combinator = match self.peek() {
+                None | Some(',') | Some(')') => break,
+                Some('>') | Some('+') | Some('~') => {
+                    let combinator = self.parse_combinator().unwrap();
+                    self.skip_whitespace();
+                    combinator
+                }
+                Some(_) if had_whitespace => Combinator::Descendant,
+                Some(c) => return Err(self.error(format!("unexpected character '{}'", c))),
+            };
+            combinators.push(combinator);
+            compounds.push(self.parse_compound()?);
+        }
+
+        Ok(Complex { compounds, combinators })
+    }
+
+    fn parse_combinator(&mut self) -> Option<Combinator> {
+        let combinator = match self.peek()? {
+            '>' => Combinator::Child,
+            '+' => Combinator::NextSibling,
+            '~' => Combinator::SubsequentSibling,
+            _ => return None,
+        };
+        self.pos += 1;
+        Some(combinator)
+    }
+
+    fn parse_compound(&mut self) -> Result<Compound, SelectorError> {
+        let mut compound = Compound::default();
+        let mut empty = true;
+
+        if self.eat('*') {
+            empty = false;
+        } else if self.peek().is_some_and(is_ident_start) {
+            compound.name = Some(self.parse_ident()?);
+            empty = false;
+        }
+
+        loop {
+            let simple = match self.peek() {
+                Some('#') => {
+                    self.pos += 1;
+                    Simple::Id(self.parse_ident()?)
+                }
+                Some('.') => {
+                    self.pos += 1;
+                    Simple::Class(self.parse_ident()?)
+                }
+                Some('[') => self.parse_attribute()?,
+                Some(':') => Simple::Pseudo(self.parse_pseudo()?),
+                _ => break,
+            };
+            compound.simple.push(simple);
+            empty = false;
+        }
+
+        if empty {
+            return Err(match self.peek() {
+                Some(c) => self.error(format!("expected a selector but found '{}'", c)),
+                None => self.error("expected a selector"),
+            });
+        }
+        Ok(compound)
+    }
+
+    fn parse_ident(&mut self) -> Result<String, SelectorError> {
+        let mut ident = String::new();
+        if self.peek() == Some('-') {
+            ident.push('-');
+            self.pos += 1;
+        }
+        if !self.peek().is_some_and(|c| is_ident_start(c) || c == '-') {
+            return Err(self.error("expected an identifier"));
+        }
+        while let Some(c) = self.peek() {
+            if c == '\\' {
+                self.pos += 1;
+                ident.push(self.parse_escape()?);
+            } else if is_ident_char(c) {
+                ident.push(c);
+                self.pos += c.len_utf8();
+            } else {
+                break;
+            }
+        }
+        Ok(ident)
+    }
+
+    /// Parses the part of an escape after the backslash: up to six hex digits
+    /// followed by optional whitespace, or any other single character
+    fn parse_escape(&mut self) -> Result<char, SelectorError> {
+        let hex: String = self.input[self.pos..]
+            .chars()
+            .take_while(|c| c.is_ascii_hexdigit())
+            .take(6)
+            .collect();
+        if hex.is_empty() {
+            return self.bump().ok_or_else(|| self.error("unterminated escape"));
+        }
+        self.pos += hex.len();
+        if self.peek().is_some_and(|c| c.is_ascii_whitespace()) {
+            self.pos += 1;
+        }
+        let code = u32::from_str_radix(&hex, 16).unwrap();
+        Ok(char::from_u32(code).filter(|c| *c != '\0').unwrap_or('\u{FFFD}'))
+    }
+
+    fn parse_string(&mut self) -> Result<String, SelectorError> {
+        let quote = self.bump().unwrap();
+        let mut value = String::new();
+        loop {
+            match self.bump() {
+                Some(c) if c == quote => return Ok(value),
+                Some('\\') => value.push(self.parse_escape()?),
+                Some(c) => value.push(c),
+                None => return Err(self.error("unterminated string")),
+            }
+        }
+    }
+
+    fn parse_attribute(&mut self) -> Result<Simple, SelectorError> {
+        self.expect('[')?;
+        self.skip_whitespace();
+        let name = self.parse_ident()?;
+        self.skip_whitespace();
+
+        let operator = match self.peek() {
+            Some(']') => None,
+            Some('=') => Some(AttrOperator::Equals),
+            Some(c) => {
+                let operator = match c {
+                    '~' => AttrOperator::Includes,
+                    '|' => AttrOperator::DashMatch,
+                    '^' => AttrOperator::Prefix,
+                    '$' => AttrOperator::Suffix,
+                    '*' => AttrOperator::Substring,
+                    _ => return Err(self.error(format!("unexpected character '{}' in attribute selector", c))),
+                };
+                self.pos += 1;
+                if self.peek() != Some('=') {
+                    return Err(self.error("expected '='"));
+                }
+                Some(operator)
+            }
+            None => return Err(self.error("unterminated attribute selector")),
+        };
+
+        let mut case_insensitive = false;
+        let operator = match operator {
+            Some(operator) => {
+                self.expect('=')?;
+                self.skip_whitespace();
+                let value = match self.peek() {
+                    Some('"') | Some('\'') => self.parse_string()?,
+                    _ => self.parse_ident()?,
+                };
+                self.skip_whitespace();
+                if let Some(flag @ ('i' | 'I' | 's' | 'S')) = self.peek() {
+                    self.pos += 1;
+                    case_insensitive = flag.eq_ignore_ascii_case(&'i');
+                    self.skip_whitespace();
+                }
+                Some((operator, value))
+            }
+            None => None,
+        };
+
+        self.expect(']')?;
+        Ok(Simple::Attribute { name, operator, case_insensitive })
+    }
+
+    fn parse_pseudo(&mut self) -> Result<Pseudo, SelectorError> {
+        self.expect(':')?;
+        if self.peek() == Some(':') {
+            return Err(self.error("pseudo-elements are not supported"));
+        }
+        let start = self.pos;
+        let name = self.parse_ident()?.to_ascii_lowercase();
+
+        let pseudo = match name.as_str() {
+            "root" => Pseudo::Root,
+            "empty" => Pseudo::Empty,
+            "scope" => Pseudo::Scope,
+            "first-child" => Pseudo::FirstChild,
+            "last-child" => Pseudo::LastChild,
+            "only-child" => Pseudo::OnlyChild,
+            "first-of-type" => Pseudo::FirstOfType,
+            "last-of-type" => Pseudo::LastOfType,
+            "only-of-type" => Pseudo::OnlyOfType,
+            "nth-child" | "nth-last-child" | "nth-of-type" | "nth-last-of-type" => {
+                self.expect('(')?;
+                self.skip_whitespace();
+                let nth = self.parse_nth()?;
+                self.skip_whitespace();
+
+                let mut of = None;
+                if name.ends_with("child") && self.input[self.pos..].starts_with("of") {
+                    self.pos += 2;
+                    if !self.skip_whitespace() {
+                        return Err(self.error("expected whitespace after 'of'"));
+                    }
+                    of = Some(self.parse_list(false)?);
+                    self.skip_whitespace();
+                }
+                self.expect(')')?;
+
+                match name.as_str() {
+                    "nth-child" => Pseudo::NthChild(nth, of),
+                    "nth-last-child" => Pseudo::NthLastChild(nth, of),
+                    "nth-of-type" => Pseudo::NthOfType(nth),
+                    _ => Pseudo::NthLastOfType(nth),
+                }
+            }
+            "not" | "is" | "where" | "has" => {
+                self.expect('(')?;
+                let list = self.parse_list(name == "has")?;
+                self.skip_whitespace();
+                self.expect(')')?;
+
+                match name.as_str() {
+                    "not" => Pseudo::Not(list),
+                    "has" => Pseudo::Has(list),
+                    _ => Pseudo::Is(list),
+                }
+            }
+            _ => {
+                return Err(SelectorError {
+                    message: format!("unsupported pseudo-class ':{}'", name),
+                    position: start,
+                })
+            }
+        };
+        Ok(pseudo)
+    }
+
+    /// Parses `odd`, `even` or an `An+B` expression such as `2n+1`, `-n + 3` or `4`
+    fn parse_nth(&mut self) -> Result<Nth, SelectorError> {
+        let start = self.pos;
+        let rest = &self.input[start..];
+
+        for (keyword, nth) in [("odd", Nth { a: 2, b: 1 }), ("even", Nth { a: 2, b: 0 })] {
+            if rest.as_bytes().get(..keyword.len()).is_some_and(|word| word.eq_ignore_ascii_case(keyword.as_bytes())) {
+                self.pos += keyword.len();
+                return Ok(nth);
+            }
+        }
+
+        let len = rest
+            .find(|c: char| !(c.is_ascii_digit() || c.is_ascii_whitespace() || matches!(c, 'n' | 'N' | '+' | '-')))
+            .unwrap_or(rest.len());
+        let raw = rest[..len].trim_end();
+        let expression: String = raw
+            .chars()
+            .filter(|c| !c.is_ascii_whitespace())
+            .collect::<String>()
+            .to_ascii_lowercase();
+        let invalid = || SelectorError {
+            message: format!("invalid nth expression '{}'", raw),
+            position: start,
+        };
+
+        let nth = match expression.find('n') {
+            _ if expression.is_empty() => return Err(invalid()),
+            Some(n) => {
+                let a = match &expression[..n] {
+                    "" | "+" => 1,
+                    "-" => -1,
+                    a => a.parse().map_err(|_| invalid())?,
+                };
+                let b = match &expression[n + 1..] {
+                    "" => 0,
+                    b if b.starts_with(['+', '-']) => b.parse().map_err(|_| invalid())?,
+                    _ => return Err(invalid()),
+                };
+                Nth { a, b }
+            }
+            None => Nth { a: 0, b: expression.parse().map_err(|_| invalid())? },
+        };
+
+        self.pos += raw.len();
+        Ok(nth)
+    }
+}
+
+fn is_ident_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_' || c == '\\' || !c.is_ascii()
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '-' || !c.is_ascii()
+}
+
+fn matches_any(node: &Node, list: &[Complex], scope: Option<&Node>) -> bool {
+    list.iter().any(|complex| matches_complex(node, complex, complex.compounds.len() - 1, scope))
+}
+
+/// Matches `node` against `complex.compounds[index]` and, through the
+/// combinators, everything to the left of it
+fn matches_complex(node: &Node, complex: &Complex, index: usize, scope: Option<&Node>) -> bool {
+    if !matches_compound(node, &complex.compounds[index], scope) {
+        return false;
+    }
+    if index == 0 {
+        return true;
+    }
+
+    match complex.combinators[index - 1] {
+        Combinator::Descendant => node
+            .ancestors()
+            .filter(|ancestor| ancestor.is_element())
+            .any(|ancestor| matches_complex(&ancestor, complex, index - 1, scope)),
+        Combinator::Child => node
+            .parent()
+            .filter(|parent| parent.is_element())
+            .is_some_and(|parent| matches_complex(&parent, complex, index - 1, scope)),
+        Combinator::NextSibling => node
+            .prev_siblings()
+            .find(|sibling| sibling.is_element())
+            .is_some_and(|sibling| matches_complex(&sibling, complex, index - 1, scope)),
+        Combinator::SubsequentSibling => node
+            .prev_siblings()
+            .filter(|sibling| sibling.is_element())
+            .any(|sibling| matches_complex(&sibling, complex, index - 1, scope)),
+    }
+}
+
+fn matches_compound(node: &Node, compound: &Compound, scope: Option<&Node>) -> bool {
+    if let Some(name) = &compound.name {
+        if !node.name().is_some_and(|node_name| node_name.eq_ignore_ascii_case(name)) {
+            return false;
+        }
+    }
+    compound.simple.iter().all(|simple| matches_simple(node, simple, scope))
+}
+
+fn matches_simple(node: &Node, simple: &Simple, scope: Option<&Node>) -> bool {
+    match simple {
+        Simple::Id(id) => node.attribute("id").is_some_and(|value| value == id.as_str()),
+        Simple::Class(class) => node
+            .attribute("class")
+            .is_some_and(|classes| classes.split_ascii_whitespace().any(|c| c == class)),
+        Simple::Attribute { name, operator, case_insensitive } => match node.attribute(name) {
+            None => false,
+            Some(value) => match operator {
+                None => true,
+                Some((operator, expected)) => {
+                    if *case_insensitive {
+                        matches_attribute(*operator, &value.to_ascii_lowercase(), &expected.to_ascii_lowercase())
+                    } else {
+                        matches_attribute(*operator, &value, expected)
+                    }
+                }
+            },
+        },
+        Simple::Pseudo(pseudo) => matches_pseudo(node, pseudo, scope),
+    }
+}
+
+fn matches_attribute(operator: AttrOperator, value: &str, expected: &str) -> bool {
+    match operator {
+        AttrOperator::Equals => value == expected,
+        AttrOperator::Includes => {
+            !expected.is_empty()
+                && !expected.contains(|c: char| c.is_ascii_whitespace())
+                && value.split_ascii_whitespace().any(|word| word == expected)
+        }
+        AttrOperator::DashMatch => {
+            value == expected || (value.starts_with(expected) && value[expected.len()..].starts_with('-'))
+        }
+        AttrOperator::Prefix => !expected.is_empty() && value.starts_with(expected),
+        AttrOperator::Suffix => !expected.is_empty() && value.ends_with(expected),
+        AttrOperator::Substring => !expected.is_empty() && value.contains(expected),
+    }
+}
+
+fn same_name(a: &Node, b: &Node) -> bool {
+    match (a.name(), b.name()) {
+        (Some(a), Some(b)) => a.eq_ignore_ascii_case(b),
+        _ => false,
+    }
+}
+
+/// 1-based position of `node` among the sibling elements accepted by `keep`,
+/// counted from the start or, with `from_end`, from the end
+fn sibling_index(node: &Node, from_end: bool, keep: impl Fn(&Node) -> bool) -> i64 {
+    let siblings = if from_end { node.next_siblings() } else { node.prev_siblings() };
+    1 + siblings.filter(|sibling| sibling.is_element() && keep(sibling)).count() as i64
+}
+
+fn matches_pseudo(node: &Node, pseudo: &Pseudo, scope: Option<&Node>) -> bool {
+    let any = |_: &Node| true;
+    let of_type = |sibling: &Node| same_name(node, sibling);
+
+    match pseudo {
+        Pseudo::Root => node.parent().is_some_and(|parent| !parent.is_element()),
+        Pseudo::Empty => node
+            .children()
+            .all(|child| child.is_comment() || child.as_raw_text().is_some_and(str::is_empty)),
+        Pseudo::Scope => match scope {
+            Some(scope) => node == scope,
+            None => node.parent().is_some_and(|parent| !parent.is_element()),
+        },
+        Pseudo::FirstChild => sibling_index(node, false, any) == 1,
+        Pseudo::LastChild => sibling_index(node, true, any) == 1,
+        Pseudo::OnlyChild => sibling_index(node, false, any) == 1 && sibling_index(node, true, any) == 1,
+        Pseudo::FirstOfType => sibling_index(node, false, of_type) == 1,
+        Pseudo::LastOfType => sibling_index(node, true, of_type) == 1,
+        Pseudo::OnlyOfType => sibling_index(node, false, of_type) == 1 && sibling_index(node, true, of_type) == 1,
+        Pseudo::NthChild(nth, of) | Pseudo::NthLastChild(nth, of) => {
+            let from_end = matches!(pseudo, Pseudo::NthLastChild(..));
+            match of {
+                Some(list) => {
+                    matches_any(node, list, scope)
+                        && nth.matches(sibling_index(node, from_end, |sibling| matches_any(sibling, list, scope)))
+                }
+                None => nth.matches(sibling_index(node, from_end, any)),
+            }
+        }
+        Pseudo::NthOfType(nth) => nth.matches(sibling_index(node, false, of_type)),
+        Pseudo::NthLastOfType(nth) => nth.matches(sibling_index(node, true, of_type)),
+        Pseudo::Not(list) => !matches_any(node, list, scope),
+        Pseudo::Is(list) => matches_any(node, list, scope),
+        Pseudo::Has(list) => list.iter().any(|relative| {
+            // Sibling combinators can reach past the node itself
+            let reaches_siblings = matches!(
+                relative.combinators[0],
+                Combinator::NextSibling | Combinator::SubsequentSibling
+            );
+            let container = if reaches_siblings { node.parent() } else { Some(*node) };
+            container.is_some_and(|container| {
+                container.descendants().filter(|candidate| candidate.is_element()).any(|candidate| {
+                    matches_complex(&candidate, relative, relative.compounds.len() - 1, Some(node))
+                })
+            })
+        }),
+    }
+}
@@ -0,0 +1,538 @@
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::io::{self, Read};
+use std::mem;
+
+use encoding_rs::Decoder;
+
+use crate::attr_match::AttrMatch;
+use crate::encoding::{self, DetectedEncoding, PRESCAN_LENGTH};
+use crate::entities::decode_attribute;
+use crate::error::{check_attribute_name, check_tag_name, Error};
+use crate::parser::open_elements::{Closed, OpenElements, Settled};
+use crate::parser::tokenizer::{Span, StartTag, Token, Tokenizer};
+use crate::parser::tree_builder::{has_optional_end_tag, is_void_element};
+
+/// How many bytes are read from the input at a time
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Finds tags and attribute values in HTML read from any [`Read`]er, without
+/// holding the whole document in memory
+///
+/// The input is read a chunk at a time and matches are yielded as soon as the
+/// element they belong to has been closed, so memory use is bounded by the
+/// chunk size, the nesting depth and the size of the matches themselves rather
+/// than by the size of the document. This makes it possible to search
+/// multi-gigabyte exports or piped input.
+///
+/// Elements are paired with their end tags by the same rules as [`Parser`](crate::Parser),
+/// including implied end tags and recovery from misnested markup, so a stream
+/// query gives exactly the results of the `Parser` method with the same name.
+/// Only queries that can be answered without looking ahead are supported: tags
+/// by name, tags by attribute, and attribute values.
+///
+/// The input may be in any encoding. It is detected from the first 1024 bytes
+/// as [`Parser::from_bytes`](crate::Parser::from_bytes) does, and the input is
+/// transcoded to UTF-8 as it is read.
+///
+/// # Examples
+///
+/// ```
+/// use tagparser::StreamParser;
+///
+/// let html = "<ul><li><a href='/one'>One</a><li><a href='/two'>Two</a></ul>";
+///
+/// let links: Vec<String> = StreamParser::new(html.as_bytes())
+///     .parse_tags("li")
+///     .collect::<Result<_, _>>()
+///     .unwrap();
+/// assert_eq!(links, vec!["<li><a href='/one'>One</a>", "<li><a href='/two'>Two</a>"]);
+///
+/// let first = StreamParser::new(html.as_bytes()).extract_attribute_values("a", "href").next();
+/// assert_eq!(first.unwrap().unwrap(), "/one");
+/// ```
+pub struct StreamParser<R> {
+    reader: R,
+    decode: bool,
+    case_sensitive: bool,
+    hint: Option<String>,
+}
+
+impl<R: Read> StreamParser<R> {
+    /// Creates a parser that reads HTML from `reader`
+    ///
+    /// The reader is read in large chunks, so it doesn't need to be buffered.
+    pub fn new(reader: R) -> Self {
+        StreamParser { reader, decode: true, case_sensitive: false, hint: None }
+    }
+
+    /// Turns case-sensitive matching of tag and attribute names on or off
+    ///
+    /// See [`Parser::case_sensitive`](crate::Parser::case_sensitive).
+    pub fn case_sensitive(mut self, case_sensitive: bool) -> Self {
+        self.case_sensitive = case_sensitive;
+        self
+    }
+
+    /// Sets the encoding label to read the input in, unless it starts with a
+    /// byte order mark
+    ///
+    /// See [`Parser::from_bytes`](crate::Parser::from_bytes).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tagparser::StreamParser;
+    ///
+    /// let html: &[u8] = b"<p>Caf\xe9</p>";
+    /// let mut matches = StreamParser::new(html).encoding_hint("latin1").parse_tags("p");
+    ///
+    /// assert_eq!(matches.next().unwrap().unwrap(), "<p>Café</p>");
+    /// assert_eq!(matches.encoding().unwrap().name, "windows-1252");
+    /// ```
+    pub fn encoding_hint(mut self, hint: &str) -> Self {
+        self.hint = Some(hint.to_string());
+        self
+    }
+
+    /// Turns decoding of character references in attribute values on or off
+    ///
+    /// See [`Parser::decode_entities`](crate::Parser::decode_entities).
+    pub fn decode_entities(mut self, decode: bool) -> Self {
+        self.decode = decode;
+        self
+    }
+
+    /// Yields the source of every complete `tag` element, like
+    /// [`Parser::parse_tags`](crate::Parser::parse_tags)
+    pub fn parse_tags(self, tag: &str) -> Matches<R> {
+        self.matches(Query { tag: tag.to_string(), attr_name: None, matcher: AttrMatch::Exists, values: false })
+    }
+
+    /// Like [`parse_tags`](StreamParser::parse_tags), but returns an error for
+    /// a tag name that could never appear in HTML
+    pub fn try_parse_tags(self, tag: &str) -> Result<Matches<R>, Error> {
+        check_tag_name(tag)?;
+        Ok(self.parse_tags(tag))
+    }
+
+    /// Yields the source of every complete `tag` element that has the attribute,
+    /// optionally with exactly the given value, like
+    /// [`Parser::parse_tags_with_attr`](crate::Parser::parse_tags_with_attr)
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tagparser::StreamParser;
+    ///
+    /// let html = "<a href='/'>Home</a><a class='nav' href='/docs'>Docs</a>";
+    /// let mut nav = StreamParser::new(html.as_bytes()).parse_tags_with_attr("a", "class", Some("nav"));
+    ///
+    /// assert_eq!(nav.next().unwrap().unwrap(), "<a class='nav' href='/docs'>Docs</a>");
+    /// assert!(nav.next().is_none());
+    /// ```
+    pub fn parse_tags_with_attr(self, tag: &str, attr_name: &str, attr_value: Option<&str>) -> Matches<R> {
+        let matcher = attr_value.map_or(AttrMatch::Exists, |value| AttrMatch::Equals(value.to_string()));
+        self.parse_tags_matching(tag, attr_name, &matcher)
+    }
+
+    /// Like [`parse_tags_with_attr`](StreamParser::parse_tags_with_attr), but
+    /// returns an error for a tag or attribute name that could never appear in HTML
+    pub fn try_parse_tags_with_attr(
+        self,
+        tag: &str,
+        attr_name: &str,
+        attr_value: Option<&str>,
+    ) -> Result<Matches<R>, Error> {
+        check_tag_name(tag)?;
+        check_attribute_name(attr_name)?;
+        Ok(self.parse_tags_with_attr(tag, attr_name, attr_value))
+    }
+
+    /// Yields the source of every complete `tag` element whose attribute passes
+    /// `matcher`, like [`Parser::parse_tags_matching`](crate::Parser::parse_tags_matching)
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use tagparser::{AttrMatch, StreamParser};
+    ///
+    /// let html = "<a href='http://old.example'>Old</a><a href='https://example.com'>New</a>";
+    /// let secure = AttrMatch::Prefix("https://".to_string());
+    /// let mut links = StreamParser::new(html.as_bytes()).parse_tags_matching("a", "href", &secure);
+    ///
+    /// assert_eq!(links.next().unwrap().unwrap(), "<a href='https://example.com'>New</a>");
+    /// assert!(links.next().is_none());
+    /// ```
+    pub fn parse_tags_matching(self, tag: &str, attr_name: &str, matcher: &AttrMatch) -> Matches<R> {
+        self.matches(Query {
+            tag: tag.to_string(),
+            attr_name: Some(attr_name.to_string()),
+            matcher: matcher.clone(),
+            values: false,
+        })
+    }
+
+    /// Like [`parse_tags_matching`](StreamParser::parse_tags_matching), but
+    /// returns an error for a tag or attribute name that could never appear in HTML
+    pub fn try_parse_tags_matching(self, tag: &str, attr_name: &str, matcher: &AttrMatch) -> Result<Matches<R>, Error> {
+        check_tag_name(tag)?;
+        check_attribute_name(attr_name)?;
+        Ok(self.parse_tags_matching(tag, attr_name, matcher))
+    }
+
+    /// Yields the value of the attribute for every complete `tag` element that
+    /// has it, like [`Parser::extract_attribute_values`](crate::Parser::extract_attribute_values)
+    pub fn extract_attribute_values(self, tag: &str, attr_name: &str) -> Matches<R> {
+        self.matches(Query {
+            tag: tag.to_string(),
+            attr_name: Some(attr_name.to_string()),
+            matcher: AttrMatch::Exists,
+            values: true,
+        })
+    }
+
+    /// Like [`extract_attribute_values`](StreamParser::extract_attribute_values),
+    /// but returns an error for a tag or attribute name that could never appear in HTML
+    pub fn try_extract_attribute_values(self, tag: &str, attr_name: &str) -> Result<Matches<R>, Error> {
+        check_tag_name(tag)?;
+        check_attribute_name(attr_name)?;
+        Ok(self.extract_attribute_values(tag, attr_name))
+    }
+
+    fn matches(self, query: Query) -> Matches<R> {
+        Matches {
+            reader: self.reader,
+            query,
+            decode: self.decode,
+            case_sensitive: self.case_sensitive,
+            hint: self.hint,
+            encoding: None,
+            decoder: None,
+            buffer: String::new(),
+            offset: 0,
+            scanned: 0,
+            pending: Vec::new(),
+            chunk: Vec::new(),
+            open: OpenElements::new(),
+            next_id: 0,
+            candidates: VecDeque::new(),
+            finished: false,
+        }
+    }
+}
+
+/// What a stream is searched for
+#[derive(Debug)]
+struct Query {
+    tag: String,
+    attr_name: Option<String>,
+    /// The test the attribute's value must pass
+    matcher: AttrMatch,
+    /// Yield attribute values instead of the elements' source
+    values: bool,
+}
+
+impl Query {
+    /// Checks a start tag against the query
+    ///
+    /// Returns `None` if the tag doesn't match, and otherwise the attribute
+    /// value to yield when the query extracts values.
+    fn check(&self, tag: &StartTag, decode: bool, case_sensitive: bool) -> Option<Option<String>> {
+        let same_name = |a: &str, b: &str| if case_sensitive { a == b } else { a.eq_ignore_ascii_case(b) };
+        if !same_name(tag.name, &self.tag) {
+            return None;
+        }
+        let Some(attr_name) = &self.attr_name else {
+            return Some(None);
+        };
+        let raw = tag.attributes.iter().find(|attr| same_name(attr.name, attr_name))?.value;
+        let value = if decode { decode_attribute(raw) } else { Cow::Borrowed(raw) };
+        if !self.matcher.matches(&value) {
+            return None;
+        }
+        Some(self.values.then(|| value.into_owned()))
+    }
+}
+
+/// A matching element, in document order
+#[derive(Debug)]
+enum Candidate {
+    /// Not closed yet; `value` is the attribute value to yield, if the query
+    /// extracts values
+    Open { id: usize, start: usize, optional_end_tag: bool, value: Option<String> },
+    /// A formatting element closed at `end` before its end tag, which counts
+    /// as complete if the end tag is found after it is reopened
+    Pending { id: usize, start: usize, end: usize, value: Option<String> },
+    /// Closed, with what to yield, or `None` if the element turned out to be
+    /// incomplete
+    Done(Option<String>),
+}
+
+/// The matches of a [`StreamParser`] query, read from the input as they are asked for
+///
+/// Each item is a match, in document order, or the error that stopped reading.
+/// Nothing more is yielded after an error.
+pub struct Matches<R> {
+    reader: R,
+    query: Query,
+    decode: bool,
+    case_sensitive: bool,
+    hint: Option<String>,
+    encoding: Option<DetectedEncoding>,
+    decoder: Option<Decoder>,
+    /// Input that is still needed, starting at byte `offset` of the stream
+    buffer: String,
+    offset: usize,
+    /// Position in `buffer` up to which tokens have been handled
+    scanned: usize,
+    /// Bytes read before the encoding was known
+    pending: Vec<u8>,
+    /// Space to read into
+    chunk: Vec<u8>,
+    open: OpenElements<'static>,
+    next_id: usize,
+    candidates: VecDeque<Candidate>,
+    finished: bool,
+}
+
+impl<R> Matches<R> {
+    /// The encoding the input is read in
+    ///
+    /// Returns `None` until the first match or error has been asked for, since
+    /// the encoding is only known once the start of the input has been read.
+    pub fn encoding(&self) -> Option<DetectedEncoding> {
+        self.encoding
+    }
+}
+
+impl<R: Read> Matches<R> {
+    /// Reads the next chunk and handles the tokens it completes
+    fn advance(&mut self) -> io::Result<()> {
+        let eof = self.read_chunk()?;
+        self.scan(eof);
+        if eof {
+            self.open.finish(self.offset + self.buffer.len());
+            self.close_candidates();
+            self.finished = true;
+        }
+        self.discard_consumed();
+        Ok(())
+    }
+
+    /// Appends the next chunk of input to the buffer, returning `true` at the
+    /// end of the input
+    fn read_chunk(&mut self) -> io::Result<bool> {
+        // Read more at once while a single token keeps growing, so that it
+        // isn't tokenized over and over
+        let size = CHUNK_SIZE.max(self.buffer.len() - self.scanned);
+        if self.chunk.len() < size {
+            self.chunk.resize(size, 0);
+        }
+        let read = loop {
+            match self.reader.read(&mut self.chunk[..size]) {
+                Ok(read) => break read,
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+                Err(error) => return Err(error),
+            }
+        };
+        let eof = read == 0;
+
+        if let Some(decoder) = &mut self.decoder {
+            transcode(decoder, &self.chunk[..read], &mut self.buffer, eof);
+            return Ok(eof);
+        }
+        // Hold the input back until there is enough of it to detect the encoding
+        self.pending.extend_from_slice(&self.chunk[..read]);
+        if self.pending.len() < PRESCAN_LENGTH && !eof {
+            return Ok(false);
+        }
+        let detected = encoding::sniff(&self.pending, self.hint.as_deref());
+        let mut decoder = detected.new_decoder();
+        transcode(&mut decoder, &mem::take(&mut self.pending), &mut self.buffer, eof);
+        self.encoding = Some(detected);
+        self.decoder = Some(decoder);
+        Ok(eof)
+    }
+
+    /// Handles the tokens after `scanned` that can't change with more input,
+    /// or all of them at the end of the input
+    fn scan(&mut self, eof: bool) {
+        let base = self.offset + self.scanned;
+        let input = &self.buffer[self.scanned..];
+        let tokens: Vec<Token> = Tokenizer::new(input).collect();
+        let complete = if eof { tokens.len() } else { complete_tokens(&tokens) };
+
+        for token in &tokens[..complete] {
+            match token {
+                Token::StartTag(tag) => {
+                    let start = base + tag.span.start;
+                    self.open.start_tag(tag.name, start);
+                    close(&mut self.candidates, &mut self.open, &self.buffer, self.offset);
+                    reopen(&mut self.open, &mut self.next_id, Some(tag.name), start);
+
+                    let id = self.next_id;
+                    self.next_id += 1;
+                    let opened = !tag.self_closing && !is_void_element(tag.name);
+                    if opened {
+                        let span = Span::new(start, base + tag.span.end);
+                        self.open.push(id, Cow::Owned(tag.name.to_string()), &tag.attributes, span);
+                    }
+
+                    if let Some(value) = self.query.check(tag, self.decode, self.case_sensitive) {
+                        let candidate = if opened {
+                            Candidate::Open { id, start, optional_end_tag: has_optional_end_tag(tag.name), value }
+                        } else {
+                            // Void and self-closing elements are nothing but their start tag
+                            let html = &input[tag.span.start..tag.span.end];
+                            Candidate::Done(Some(value.unwrap_or_else(|| html.to_string())))
+                        };
+                        self.candidates.push_back(candidate);
+                    }
+                }
+                Token::EndTag(tag) => {
+                    self.open.end_tag(tag.name, Span::new(base + tag.span.start, base + tag.span.end));
+                    close(&mut self.candidates, &mut self.open, &self.buffer, self.offset);
+                }
+                Token::Text { span, .. } => reopen(&mut self.open, &mut self.next_id, None, base + span.start),
+                _ => {}
+            }
+        }
+
+        self.scanned += match tokens.get(complete) {
+            Some(token) => token.span().start,
+            // Nothing is kept back at the end of the input, and without tokens
+            // nothing is complete yet
+            None if eof => input.len(),
+            None => 0,
+        };
+        // Problems in the markup aren't reported by the stream
+        self.open.take_diagnostics();
+    }
+
+    fn close_candidates(&mut self) {
+        close(&mut self.candidates, &mut self.open, &self.buffer, self.offset);
+    }
+
+    /// Drops the input that has been handled and isn't part of an open match
+    fn discard_consumed(&mut self) {
+        let scanned = self.offset + self.scanned;
+        let keep = self
+            .candidates
+            .iter()
+            .find_map(|candidate| match candidate {
+                Candidate::Open { start, value: None, .. } | Candidate::Pending { start, value: None, .. } => Some(*start),
+                _ => None,
+            })
+            .map_or(scanned, |start| start.min(scanned));
+        let consumed = keep - self.offset;
+        self.buffer.drain(..consumed);
+        self.offset += consumed;
+        self.scanned -= consumed;
+    }
+}
+
+impl<R: Read> Iterator for Matches<R> {
+    type Item = io::Result<String>;
+
+    fn next(&mut self) -> Option<io::Result<String>> {
+        loop {
+            while matches!(self.candidates.front(), Some(Candidate::Done(_))) {
+                if let Some(Candidate::Done(Some(found))) = self.candidates.pop_front() {
+                    return Some(Ok(found));
+                }
+            }
+            if self.finished {
+                return None;
+            }
+            if let Err(error) = self.advance() {
+                self.finished = true;
+                self.candidates.clear();
+                return Some(Err(error));
+            }
+        }
+    }
+}
+
+/// Decodes `bytes` onto the end of `buffer`, replacing malformed sequences
+/// with U+FFFD
+///
+/// A character cut off at the end of `bytes` is completed by the next call,
+/// unless this is the `last` one.
+fn transcode(decoder: &mut Decoder, bytes: &[u8], buffer: &mut String, last: bool) {
+    let needed = decoder.max_utf8_buffer_length(bytes.len()).expect("chunk fits in memory");
+    buffer.reserve(needed);
+    let (_, read, _) = decoder.decode_to_string(bytes, buffer, last);
+    debug_assert_eq!(read, bytes.len());
+}
+
+/// The number of leading tokens that more input can't change
+///
+/// The last token may be cut off by the end of the chunk. Text after a start
+/// tag is kept back with the tag, because the content of raw-text elements
+/// such as `<script>` is only read as text after their start tag.
+fn complete_tokens(tokens: &[Token]) -> usize {
+    match tokens {
+        [] => 0,
+        [.., Token::StartTag(_), Token::Text { .. }] => tokens.len() - 2,
+        _ => tokens.len() - 1,
+    }
+}
+
+/// Opens again the formatting elements that were closed before their end
+/// tags, ahead of a start tag called `start_tag` or of text at `at`
+///
+/// The copies are never matches, as in the tree built by [`Parser`](crate::Parser),
+/// but must be opened so that later tags pair up the same way.
+fn reopen(open: &mut OpenElements, next_id: &mut usize, start_tag: Option<&str>, at: usize) {
+    for original in open.formatting_to_reopen(start_tag) {
+        open.reopen(original, *next_id, at);
+        *next_id += 1;
+    }
+}
+
+/// Resolves the candidates for elements that have been closed
+///
+/// Elements that were closed without their end tag, other than those whose
+/// end tag may be left out, are incomplete and are dropped, as they are by
+/// [`Parser`](crate::Parser). Formatting elements closed by misnested tags
+/// wait until it is known whether their end tag comes later.
+fn close(candidates: &mut VecDeque<Candidate>, open: &mut OpenElements, buffer: &str, offset: usize) {
+    for Closed { id, at, end_tag, pending } in open.take_closed() {
+        let Some(candidate) = candidates
+            .iter_mut()
+            .rev()
+            .find(|candidate| matches!(candidate, Candidate::Open { id: open, .. } if *open == id))
+        else {
+            continue;
+        };
+        let Candidate::Open { start, optional_end_tag, value, .. } = candidate else {
+            continue;
+        };
+        let found = if end_tag.is_some() || *optional_end_tag {
+            let end = end_tag.map_or(at, |span| span.end);
+            Some(value.take().unwrap_or_else(|| buffer[*start - offset..end - offset].to_string()))
+        } else if pending {
+            *candidate = Candidate::Pending { id, start: *start, end: at, value: value.take() };
+            continue;
+        } else {
+            None
+        };
+        *candidate = Candidate::Done(found);
+    }
+
+    for Settled { id, ended } in open.take_settled() {
+        let Some(candidate) = candidates
+            .iter_mut()
+            .rev()
+            .find(|candidate| matches!(candidate, Candidate::Pending { id: pending, .. } if *pending == id))
+        else {
+            continue;
+        };
+        let Candidate::Pending { start, end, value, .. } = candidate else {
+            continue;
+        };
+        let found = ended.then(|| value.take().unwrap_or_else(|| buffer[*start - offset..*end - offset].to_string()));
+        *candidate = Candidate::Done(found);
+    }
+}
@@ -0,0 +1,267 @@
+//! Extraction of `<table>` elements into rectangular grids of text
+//!
+//! Rows are read from the table's own `<tr>` elements, directly or inside
+//! `<thead>`, `<tbody>` and `<tfoot>`, so the rows of a nested table never leak
+//! into the table around it. Cells spanning several columns or rows are copied
+//! into every slot they cover, as a browser lays them out.
+
+use std::fmt::Write;
+
+use crate::dom::Node;
+use crate::text;
+
+/// Largest `colspan` honoured, as in browsers
+const MAX_COLSPAN: usize = 1000;
+
+/// Largest `rowspan` honoured, as in browsers
+const MAX_ROWSPAN: usize = 65534;
+
+/// A table found in a page, with its cells laid out in a grid
+///
+/// Every row has the same number of cells: cells with a `colspan` or `rowspan`
+/// are repeated in each slot they cover, and short rows are padded with empty
+/// strings. The text of each cell is rendered like
+/// [`Node::text_content`](crate::Node::text_content).
+///
+/// Headers come from the rows of the `<thead>`, or, for a table without one,
+/// from the leading rows made only of `<th>` cells. Several header rows are
+/// merged into one, joining the labels stacked in each column with a space.
+///
+/// # Examples
+///
+/// ```
+/// use tagparser::extract_tables;
+///
+/// let html = r#"
+///     <table>
+///         <tr><th>Name</th><th colspan="2">Score</th></tr>
+///         <tr><td rowspan="2">Ann</td><td>7</td><td>9</td></tr>
+///         <tr><td>8</td><td>10</td></tr>
+///     </table>
+/// "#.to_string();
+/// let tables = extract_tables(html);
+///
+/// assert_eq!(tables[0].headers, vec!["Name", "Score", "Score"]);
+/// assert_eq!(tables[0].rows, vec![vec!["Ann", "7", "9"], vec!["Ann", "8", "10"]]);
+/// assert_eq!(tables[0].to_csv(), "Name,Score,Score\nAnn,7,9\nAnn,8,10\n");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Table {
+    /// The column headers; empty if the table has no header rows
+    pub headers: Vec<String>,
+    /// The text of each cell, row by row, not including the header rows
+    pub rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    /// The number of columns
+    pub fn width(&self) -> usize {
+        self.headers.len().max(self.rows.first().map_or(0, Vec::len))
+    }
+
+    /// Writes the table as CSV, one line per row with the headers first
+    ///
+    /// Fields containing a comma, a quote or a line break are quoted, with
+    /// quotes doubled, as described in RFC 4180. Lines end with `\n`.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::new();
+        let header = (!self.headers.is_empty()).then_some(&self.headers);
+        for row in header.into_iter().chain(&self.rows) {
+            let fields: Vec<String> = row.iter().map(|field| csv_field(field)).collect();
+            csv.push_str(&fields.join(","));
+            csv.push('\n');
+        }
+        csv
+    }
+
+    /// Writes the table as a JSON object with `headers` and `rows` arrays
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tagparser::Table;
+    ///
+    /// let table = Table { headers: vec!["Quote".to_string()], rows: vec![vec!["\"Hi\"".to_string()]] };
+    /// assert_eq!(table.to_json(), r#"{"headers":["Quote"],"rows":[["\"Hi\""]]}"#);
+    /// ```
+    pub fn to_json(&self) -> String {
+        let rows: Vec<String> = self.rows.iter().map(|row| json_array(row)).collect();
+        format!("{{\"headers\":{},\"rows\":[{}]}}", json_array(&self.headers), rows.join(","))
+    }
+}
+
+impl Table {
+    /// Lays out the cells of a `<table>` element, rendering their text with
+    /// character references decoded if `decode` is set
+    pub(crate) fn new(node: Node<'_>, decode: bool) -> Table {
+        let has_thead = node.children().any(|child| is_named(&child, "thead"));
+        let mut headers: Vec<Vec<String>> = Vec::new();
+        let mut rows: Vec<Vec<String>> = Vec::new();
+
+        for (section, group) in row_groups(node) {
+            let mut spans = Vec::new();
+            for tr in group {
+                let row = lay_out_row(tr, &mut spans, decode);
+                // Without a <thead>, leading rows of <th> cells are the header rows
+                let header = match section {
+                    Some(name) if has_thead => name.eq_ignore_ascii_case("thead"),
+                    _ => !has_thead && rows.is_empty() && is_header_row(tr),
+                };
+                if header {
+                    headers.push(row);
+                } else {
+                    rows.push(row);
+                }
+            }
+        }
+
+        let width = headers.iter().chain(&rows).map(Vec::len).max().unwrap_or(0);
+        for row in headers.iter_mut().chain(&mut rows) {
+            row.resize(width, String::new());
+        }
+        Table { headers: merge_headers(headers, width), rows }
+    }
+}
+
+impl From<Node<'_>> for Table {
+    /// Lays out the cells of a `<table>` element
+    ///
+    /// Any other node produces an empty table.
+    fn from(node: Node<'_>) -> Self {
+        Table::new(node, true)
+    }
+}
+
+fn is_named(node: &Node, name: &str) -> bool {
+    node.name().is_some_and(|node_name| node_name.eq_ignore_ascii_case(name))
+}
+
+/// Groups the rows of a table by the section they're in, `None` for rows
+/// written directly in the table; spans never reach across groups
+fn row_groups<'a>(table: Node<'a>) -> Vec<(Option<&'a str>, Vec<Node<'a>>)> {
+    let mut groups: Vec<(Option<&'a str>, Vec<Node<'a>>)> = Vec::new();
+    for child in table.children() {
+        if is_named(&child, "tr") {
+            match groups.last_mut() {
+                Some((None, rows)) => rows.push(child),
+                _ => groups.push((None, vec![child])),
+            }
+        } else if ["thead", "tbody", "tfoot"].iter().any(|name| is_named(&child, name)) {
+            let rows = child.children().filter(|row| is_named(row, "tr")).collect();
+            groups.push((child.name(), rows));
+        }
+    }
+    groups
+}
+
+fn cells<'a>(tr: Node<'a>) -> impl Iterator<Item = Node<'a>> {
+    tr.children().filter(|cell| is_named(cell, "td") || is_named(cell, "th"))
+}
+
+fn is_header_row(tr: Node) -> bool {
+    let mut cells = cells(tr).peekable();
+    cells.peek().is_some() && cells.all(|cell| is_named(&cell, "th"))
+}
+
+/// Lays out one row, filling the slots taken by cells from earlier rows
+///
+/// `spans` holds, for each column, the text of the cell reaching down into it
+/// and how many more rows it covers.
+fn lay_out_row(tr: Node, spans: &mut Vec<(String, usize)>, decode: bool) -> Vec<String> {
+    let mut row = Vec::new();
+    let mut cells = cells(tr);
+    loop {
+        let column = row.len();
+        if let Some((text, _)) = spans.get(column).filter(|(_, rows)| *rows > 0) {
+            row.push(text.clone());
+            continue;
+        }
+        let Some(cell) = cells.next() else {
+            // Slots after the last cell are empty, up to the last one spanned into
+            if spans[column.min(spans.len())..].iter().any(|(_, rows)| *rows > 0) {
+                row.push(String::new());
+                continue;
+            }
+            break;
+        };
+
+        let text = text::text_content(&cell, decode);
+        let colspan = span_attribute(&cell, "colspan").unwrap_or(1).clamp(1, MAX_COLSPAN);
+        // A rowspan of 0 reaches to the end of the row group
+        let rowspan = match span_attribute(&cell, "rowspan") {
+            Some(0) => usize::MAX,
+            rowspan => rowspan.unwrap_or(1).clamp(1, MAX_ROWSPAN),
+        };
+        if spans.len() < column + colspan {
+            spans.resize(column + colspan, (String::new(), 0));
+        }
+        for slot in &mut spans[column..column + colspan] {
+            // The count includes this row, which is taken off below
+            *slot = (text.clone(), rowspan);
+        }
+        row.extend(std::iter::repeat_n(text, colspan));
+    }
+    for (_, rows) in spans.iter_mut() {
+        *rows = rows.saturating_sub(1);
+    }
+    row
+}
+
+fn span_attribute(cell: &Node, name: &str) -> Option<usize> {
+    let value = cell.attribute(name)?;
+    let digits = value.trim_start();
+    let end = digits.find(|c: char| !c.is_ascii_digit()).unwrap_or(digits.len());
+    digits[..end].parse().ok()
+}
+
+/// Joins the distinct labels of each column of the header rows
+fn merge_headers(rows: Vec<Vec<String>>, width: usize) -> Vec<String> {
+    if rows.len() < 2 {
+        return rows.into_iter().next().unwrap_or_default();
+    }
+    (0..width)
+        .map(|column| {
+            let mut labels: Vec<&str> = Vec::new();
+            for row in &rows {
+                let label = row[column].as_str();
+                if !label.is_empty() && labels.last() != Some(&label) {
+                    labels.push(label);
+                }
+            }
+            labels.join(" ")
+        })
+        .collect()
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn json_array(values: &[String]) -> String {
+    let strings: Vec<String> = values.iter().map(|value| json_string(value)).collect();
+    format!("[{}]", strings.join(","))
+}
+
+fn json_string(value: &str) -> String {
+    let mut json = String::with_capacity(value.len() + 2);
+    json.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if c < ' ' => {
+                let _ = write!(json, "\\u{:04x}", c as u32);
+            }
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
@@ -0,0 +1,100 @@
+use crate::dom::{Location, Node};
+use crate::parser::tokenizer::Span;
+
+/// An element found in a page, with its parts already taken apart
+///
+/// `Tag` owns its data, so it can outlive the [`Document`](crate::Document)
+/// it was built from. It is what the `find_*` functions return in place of the
+/// raw HTML strings returned by their `parse_*` counterparts.
+///
+/// # Examples
+///
+/// ```
+/// use tagparser::find_tags;
+///
+/// let html = "<a href='/docs' class='nav'>Read the <b>docs</b></a>".to_string();
+/// let tags = find_tags(html, "a".to_string());
+///
+/// let link = &tags[0];
+/// assert_eq!(link.name, "a");
+/// assert_eq!(link.attribute("href"), Some("/docs"));
+/// assert_eq!(link.attributes[1], ("class".to_string(), "nav".to_string()));
+/// assert_eq!(link.inner_html, "Read the <b>docs</b>");
+/// assert_eq!(link.text, "Read the docs");
+/// assert_eq!((link.span.start, link.span.end), (0, 52));
+/// assert_eq!(link.start_tag.start.to_string(), "1:1");
+/// assert_eq!(link.end_tag.unwrap().start.column, 49);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Tag {
+    /// The tag name as written in the source
+    pub name: String,
+    /// Attribute names and values in source order; boolean attributes have an empty value
+    ///
+    /// Character references in the values are decoded unless decoding was
+    /// turned off with [`Parser::decode_entities`](crate::Parser::decode_entities).
+    pub attributes: Vec<(String, String)>,
+    /// The HTML between the start and end tags
+    pub inner_html: String,
+    /// The HTML of the whole element, including its start and end tags
+    pub outer_html: String,
+    /// The text of the element and all of its descendants, decoded like `attributes`
+    pub text: String,
+    /// Byte range of the whole element in the source
+    pub span: Span,
+    /// Where the start tag is in the source
+    pub start_tag: Location,
+    /// Where the end tag is in the source; `None` for void elements, elements
+    /// written as `<tag/>` and elements whose end tag was left out
+    pub end_tag: Option<Location>,
+}
+
+impl Tag {
+    /// Returns the value of the attribute called `name`, compared ASCII case-insensitively
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(attr_name, _)| attr_name.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Returns `true` if the element has an attribute called `name`
+    pub fn has_attribute(&self, name: &str) -> bool {
+        self.attribute(name).is_some()
+    }
+}
+
+impl Tag {
+    /// Copies an element out of its document, decoding character references
+    /// in the text and attribute values if `decode` is set
+    pub(crate) fn new(node: Node<'_>, decode: bool) -> Tag {
+        let attributes = if decode {
+            node.attributes()
+                .map(|(name, value)| (name.to_string(), value.into_owned()))
+                .collect()
+        } else {
+            node.raw_attributes()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect()
+        };
+        Tag {
+            name: node.name().unwrap_or_default().to_string(),
+            attributes,
+            inner_html: node.inner_html().to_string(),
+            outer_html: node.outer_html().to_string(),
+            text: if decode { node.text() } else { node.raw_text() },
+            span: node.span(),
+            start_tag: node.start_tag_location().unwrap_or_else(|| node.location()),
+            end_tag: node.end_tag_location(),
+        }
+    }
+}
+
+impl From<Node<'_>> for Tag {
+    /// Copies an element out of its document
+    ///
+    /// Non-element nodes produce a `Tag` with an empty name and no attributes.
+    fn from(node: Node<'_>) -> Self {
+        Tag::new(node, true)
+    }
+}
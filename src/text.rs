@@ -0,0 +1,171 @@
+//! Rendering of an element's content as plain text
+//!
+//! This follows the spirit of the browser's `innerText`: markup is stripped,
+//! `<script>` and `<style>` bodies are dropped, runs of whitespace collapse to a
+//! single space, and block-level elements, `<br>` and table cells turn into line
+//! breaks and tabs. Whitespace inside `<pre>` and `<textarea>` is kept as written.
+
+use std::borrow::Cow;
+
+use crate::dom::Node;
+
+/// Elements whose content is never rendered as text
+const HIDDEN: &[&str] = &["script", "style", "template"];
+
+/// Elements that start and end a line of their own
+const BLOCKS: &[&str] = &[
+    "address", "article", "aside", "blockquote", "body", "caption", "center", "dd", "details", "dialog", "dir", "div",
+    "dl", "dt", "fieldset", "figcaption", "figure", "footer", "form", "h1", "h2", "h3", "h4", "h5", "h6", "head",
+    "header", "hgroup", "hr", "html", "legend", "li", "main", "menu", "nav", "ol", "option", "pre", "section",
+    "summary", "table", "tbody", "textarea", "tfoot", "thead", "title", "tr", "ul",
+];
+
+/// Elements whose whitespace is kept as written
+const PREFORMATTED: &[&str] = &["pre", "textarea", "listing", "plaintext"];
+
+fn is_one_of(name: &str, names: &[&str]) -> bool {
+    names.iter().any(|candidate| name.eq_ignore_ascii_case(candidate))
+}
+
+/// The plain text of `node`'s content; see the module documentation
+pub(crate) fn text_content(node: &Node, decode: bool) -> String {
+    let mut writer = TextWriter { output: String::new(), pending_breaks: 0, decode };
+    let preformatted = std::iter::once(*node)
+        .chain(node.ancestors())
+        .any(|node| node.name().is_some_and(|name| is_one_of(name, PREFORMATTED)));
+
+    if let Some(text) = writer.node_text(node) {
+        writer.text(&text, preformatted);
+    } else {
+        for child in node.children() {
+            writer.node(&child, preformatted);
+        }
+    }
+    writer.finish()
+}
+
+struct TextWriter {
+    output: String,
+    /// Line breaks owed before the next text, from block boundaries
+    pending_breaks: usize,
+    decode: bool,
+}
+
+impl TextWriter {
+    fn node(&mut self, node: &Node, preformatted: bool) {
+        if let Some(text) = self.node_text(node) {
+            self.text(&text, preformatted);
+            return;
+        }
+        let Some(name) = node.name() else {
+            return;
+        };
+
+        if is_one_of(name, HIDDEN) {
+            return;
+        }
+        if name.eq_ignore_ascii_case("br") {
+            self.line_break();
+        }
+
+        let is_cell = name.eq_ignore_ascii_case("td") || name.eq_ignore_ascii_case("th");
+        if is_cell && node.prev_siblings().any(|sibling| sibling.is_element()) {
+            self.tab();
+        }
+
+        // Paragraphs are set off by a blank line, other blocks by a line break
+        let breaks = if name.eq_ignore_ascii_case("p") {
+            2
+        } else if is_one_of(name, BLOCKS) {
+            1
+        } else {
+            0
+        };
+
+        self.require_breaks(breaks);
+        let preformatted = preformatted || is_one_of(name, PREFORMATTED);
+        for child in node.children() {
+            self.node(&child, preformatted);
+        }
+        self.require_breaks(breaks);
+    }
+
+    /// The text of a text node, decoded unless decoding is off
+    fn node_text<'a>(&self, node: &Node<'a>) -> Option<Cow<'a, str>> {
+        if self.decode {
+            node.as_text()
+        } else {
+            node.as_raw_text().map(Cow::Borrowed)
+        }
+    }
+
+    fn text(&mut self, decoded: &str, preformatted: bool) {
+        if preformatted {
+            if !decoded.is_empty() {
+                self.flush_breaks();
+                self.output.push_str(decoded);
+            }
+            return;
+        }
+
+        for (index, word) in decoded.split_ascii_whitespace().enumerate() {
+            let space_before = index > 0 || decoded.starts_with(|c: char| c.is_ascii_whitespace());
+            // A new line never starts with a space
+            let new_line = self.flush_breaks();
+            if !new_line && space_before && !self.at_line_start() && !self.output.ends_with(' ') {
+                self.output.push(' ');
+            }
+            self.output.push_str(word);
+        }
+        if decoded.ends_with(|c: char| c.is_ascii_whitespace()) && !self.at_line_start() {
+            self.output.push(' ');
+        }
+    }
+
+    fn require_breaks(&mut self, breaks: usize) {
+        self.pending_breaks = self.pending_breaks.max(breaks);
+    }
+
+    /// A `<br>`, which always produces a line break, even right after another one
+    fn line_break(&mut self) {
+        self.flush_breaks();
+        self.trim_trailing_spaces();
+        self.output.push('\n');
+    }
+
+    fn tab(&mut self) {
+        self.trim_trailing_spaces();
+        self.output.push('\t');
+    }
+
+    /// Writes the pending line breaks, unless nothing has been written yet;
+    /// returns `true` if any were written
+    fn flush_breaks(&mut self) -> bool {
+        let breaks = std::mem::take(&mut self.pending_breaks);
+        if breaks == 0 || self.output.is_empty() {
+            return false;
+        }
+        self.trim_trailing_spaces();
+        let existing = self.output.len() - self.output.trim_end_matches('\n').len();
+        for _ in existing..breaks {
+            self.output.push('\n');
+        }
+        true
+    }
+
+    fn at_line_start(&self) -> bool {
+        self.output.is_empty() || self.output.ends_with(['\n', '\t'])
+    }
+
+    fn trim_trailing_spaces(&mut self) {
+        let trimmed = self.output.trim_end_matches(' ').len();
+        self.output.truncate(trimmed);
+    }
+
+    fn finish(mut self) -> String {
+        self.trim_trailing_spaces();
+        let trimmed = self.output.trim_end_matches('\n').len();
+        self.output.truncate(trimmed);
+        self.output
+    }
+}
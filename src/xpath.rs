@@ -0,0 +1,1324 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::dom::{Node, NodeKind};
+use crate::entities::decode_attribute;
+
+/// An error found while parsing or evaluating an XPath expression
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum XPathError {
+    /// The expression is not valid XPath 1.0; `position` is a byte offset into it
+    Syntax { message: String, position: usize },
+    /// The expression is valid but can't be evaluated, e.g. it calls an unknown function
+    Evaluation(String),
+}
+
+impl fmt::Display for XPathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            XPathError::Syntax { message, position } => {
+                write!(f, "invalid XPath expression at position {}: {}", position, message)
+            }
+            XPathError::Evaluation(message) => write!(f, "XPath evaluation failed: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for XPathError {}
+
+fn syntax_error(message: impl Into<String>, position: usize) -> XPathError {
+    XPathError::Syntax { message: message.into(), position }
+}
+
+fn evaluation_error(message: impl Into<String>) -> XPathError {
+    XPathError::Evaluation(message.into())
+}
+
+/// A node in the XPath data model
+///
+/// XPath treats attributes as nodes of their own, which the element tree
+/// doesn't, so node-sets hold either a tree [`Node`] or one attribute of an element.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum XPathNode<'a> {
+    /// A document, element, text or comment node
+    Node(Node<'a>),
+    /// The attribute at `index` in `owner`'s attribute list; `value` is the
+    /// value as written, before character references are decoded
+    Attribute {
+        owner: Node<'a>,
+        index: usize,
+        name: &'a str,
+        value: &'a str,
+    },
+}
+
+impl<'a> XPathNode<'a> {
+    /// The tree node, or `None` for an attribute
+    pub fn as_node(&self) -> Option<Node<'a>> {
+        match self {
+            XPathNode::Node(node) => Some(*node),
+            XPathNode::Attribute { .. } => None,
+        }
+    }
+
+    /// The XPath string-value: the text of an element or document, the
+    /// content of a text or comment node, or the value of an attribute,
+    /// with character references decoded
+    pub fn string_value(&self) -> String {
+        match self {
+            XPathNode::Node(node) => match node.kind() {
+                NodeKind::Comment => node.as_comment().unwrap_or_default().to_string(),
+                _ => node.text(),
+            },
+            XPathNode::Attribute { value, .. } => decode_attribute(value).into_owned(),
+        }
+    }
+
+    /// The element an attribute belongs to, or the parent of a tree node
+    fn parent(&self) -> Option<XPathNode<'a>> {
+        match self {
+            XPathNode::Node(node) => node.parent().map(XPathNode::Node),
+            XPathNode::Attribute { owner, .. } => Some(XPathNode::Node(*owner)),
+        }
+    }
+
+    /// Sort key that puts nodes in document order, with an element's
+    /// attributes right after the element itself
+    fn order_key(&self) -> (usize, usize) {
+        match self {
+            XPathNode::Node(node) => (node.id().index(), 0),
+            XPathNode::Attribute { owner, index, .. } => (owner.id().index(), index + 1),
+        }
+    }
+}
+
+/// The result of evaluating an XPath expression
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value<'a> {
+    /// Nodes in document order, without duplicates
+    NodeSet(Vec<XPathNode<'a>>),
+    String(String),
+    Number(f64),
+    Boolean(bool),
+}
+
+impl<'a> Value<'a> {
+    /// Converts the value as the XPath `string()` function does
+    pub fn to_string_value(&self) -> String {
+        match self {
+            Value::NodeSet(nodes) => nodes.first().map(XPathNode::string_value).unwrap_or_default(),
+            Value::String(string) => string.clone(),
+            Value::Number(number) => format_number(*number),
+            Value::Boolean(boolean) => boolean.to_string(),
+        }
+    }
+
+    /// Converts the value as the XPath `number()` function does
+    pub fn to_number(&self) -> f64 {
+        match self {
+            Value::Number(number) => *number,
+            Value::Boolean(boolean) => f64::from(u8::from(*boolean)),
+            _ => parse_number(&self.to_string_value()),
+        }
+    }
+
+    /// Converts the value as the XPath `boolean()` function does
+    pub fn to_boolean(&self) -> bool {
+        match self {
+            Value::NodeSet(nodes) => !nodes.is_empty(),
+            Value::String(string) => !string.is_empty(),
+            Value::Number(number) => *number != 0.0 && !number.is_nan(),
+            Value::Boolean(boolean) => *boolean,
+        }
+    }
+
+    /// The nodes of a node-set, or `None` for any other value
+    pub fn as_node_set(&self) -> Option<&[XPathNode<'a>]> {
+        match self {
+            Value::NodeSet(nodes) => Some(nodes),
+            _ => None,
+        }
+    }
+}
+
+/// Formats a number the way XPath's `string()` does: integers without a
+/// fractional part, no exponent, and `NaN`/`Infinity` spelled out
+fn format_number(number: f64) -> String {
+    if number.is_nan() {
+        "NaN".to_string()
+    } else if number.is_infinite() {
+        if number > 0.0 { "Infinity" } else { "-Infinity" }.to_string()
+    } else if number == 0.0 {
+        // Negative zero too
+        "0".to_string()
+    } else {
+        // The shortest decimal that reads back as the same number, which has
+        // no fractional part for integers
+        format!("{}", number)
+    }
+}
+
+/// Parses a string the way XPath's `number()` does; anything that isn't an
+/// optionally negative decimal number becomes `NaN`
+fn parse_number(string: &str) -> f64 {
+    let trimmed = string.trim_matches(|c: char| c.is_ascii_whitespace());
+    let digits = trimmed.strip_prefix('-').unwrap_or(trimmed);
+    let valid = !digits.is_empty()
+        && digits != "."
+        && digits.chars().all(|c| c.is_ascii_digit() || c == '.')
+        && digits.matches('.').count() <= 1;
+    if valid {
+        trimmed.parse().unwrap_or(f64::NAN)
+    } else {
+        f64::NAN
+    }
+}
+
+/// A compiled XPath 1.0 expression
+///
+/// The full XPath 1.0 expression language is supported: location paths with
+/// all axes (`child`, `descendant`, `parent`, `ancestor`, `following-sibling`,
+/// `preceding-sibling`, `following`, `preceding`, `attribute`, `self`,
+/// `descendant-or-self`, `ancestor-or-self` and `namespace`) and their
+/// abbreviations, predicates, unions, arithmetic, comparisons and the core
+/// function library.
+///
+/// Element and attribute name tests match ASCII case-insensitively, since
+/// HTML names are case-insensitive. HTML has no namespaces, so the `namespace`
+/// axis is always empty and `namespace-uri()` always returns an empty string.
+///
+/// # Examples
+///
+/// ```
+/// use tagparser::{Document, XPath};
+///
+/// let document = Document::parse(r#"
+///     <table id="prices">
+///         <tr><th>Item</th><th>Price</th></tr>
+///         <tr><td>Apple</td><td>1.20</td></tr>
+///         <tr><td>Pear</td><td>0.80</td></tr>
+///     </table>
+/// "#);
+///
+/// let xpath = XPath::parse("//table[@id='prices']//tr[position()>1]/td[2]/text()").unwrap();
+/// let prices: Vec<String> = xpath
+///     .evaluate(document.root())
+///     .unwrap()
+///     .as_node_set()
+///     .unwrap()
+///     .iter()
+///     .map(|node| node.string_value())
+///     .collect();
+/// assert_eq!(prices, vec!["1.20", "0.80"]);
+///
+/// let total = XPath::parse("sum(//tr/td[2])").unwrap().evaluate(document.root()).unwrap();
+/// assert_eq!(total.to_number(), 2.0);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct XPath {
+    expr: Expr,
+}
+
+impl XPath {
+    /// Parses an XPath expression
+    pub fn parse(expression: &str) -> Result<XPath, XPathError> {
+        let tokens = lex(expression)?;
+        let mut parser = ExprParser { tokens, pos: 0, end: expression.len() };
+        let expr = parser.parse_expr()?;
+        if let Some(token) = parser.tokens.get(parser.pos) {
+            return Err(syntax_error("unexpected token", token.position));
+        }
+        Ok(XPath { expr })
+    }
+
+    /// Evaluates the expression with `context` as the context node
+    pub fn evaluate<'a>(&self, context: Node<'a>) -> Result<Value<'a>, XPathError> {
+        let context = Context { node: XPathNode::Node(context), position: 1, size: 1 };
+        evaluate(&self.expr, &context)
+    }
+}
+
+impl FromStr for XPath {
+    type Err = XPathError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        XPath::parse(s)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Lexer
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, PartialEq)]
+enum Tok {
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    Dot,
+    DotDot,
+    At,
+    Comma,
+    ColonColon,
+    Slash,
+    DoubleSlash,
+    Pipe,
+    Plus,
+    Minus,
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    Multiply,
+    And,
+    Or,
+    Mod,
+    Div,
+    /// `*`, or `prefix:*`, as a name test
+    Wildcard,
+    /// A name test
+    Name(String),
+    FunctionName(String),
+    NodeType(String),
+    AxisName(String),
+    Literal(String),
+    Number(f64),
+    Variable(String),
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    tok: Tok,
+    position: usize,
+}
+
+impl Tok {
+    /// Tokens after which `*` is a name test and a name is a name test rather than an operator
+    fn starts_operand(&self) -> bool {
+        matches!(
+            self,
+            Tok::At
+                | Tok::ColonColon
+                | Tok::LParen
+                | Tok::LBracket
+                | Tok::Comma
+                | Tok::Slash
+                | Tok::DoubleSlash
+                | Tok::Pipe
+                | Tok::Plus
+                | Tok::Minus
+                | Tok::Equal
+                | Tok::NotEqual
+                | Tok::Less
+                | Tok::LessEqual
+                | Tok::Greater
+                | Tok::GreaterEqual
+                | Tok::Multiply
+                | Tok::And
+                | Tok::Or
+                | Tok::Mod
+                | Tok::Div
+        )
+    }
+}
+
+fn is_name_start(c: char) -> bool {
+    c.is_alphabetic() || c == '_'
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '-' | '.') || (!c.is_ascii() && !c.is_whitespace())
+}
+
+fn lex(input: &str) -> Result<Vec<Token>, XPathError> {
+    let mut tokens: Vec<Token> = Vec::new();
+    let bytes = input.as_bytes();
+    let mut pos = 0;
+
+    let rest_after_whitespace = |from: usize| input[from..].trim_start_matches(|c: char| c.is_ascii_whitespace());
+
+    while pos < input.len() {
+        let c = input[pos..].chars().next().unwrap();
+        if c.is_ascii_whitespace() {
+            pos += 1;
+            continue;
+        }
+
+        let start = pos;
+        let operand_expected = tokens.last().is_none_or(|token| token.tok.starts_operand());
+        let next = bytes.get(pos + 1).copied();
+        // Where tokens longer than one character end, if not at the character after `pos`
+        let mut end = None;
+
+        let tok = match c {
+            '(' => Tok::LParen,
+            ')' => Tok::RParen,
+            '[' => Tok::LBracket,
+            ']' => Tok::RBracket,
+            '@' => Tok::At,
+            ',' => Tok::Comma,
+            '|' => Tok::Pipe,
+            '+' => Tok::Plus,
+            '-' => Tok::Minus,
+            '=' => Tok::Equal,
+            ':' if next == Some(b':') => {
+                pos += 1;
+                Tok::ColonColon
+            }
+            '!' if next == Some(b'=') => {
+                pos += 1;
+                Tok::NotEqual
+            }
+            '<' if next == Some(b'=') => {
+                pos += 1;
+                Tok::LessEqual
+            }
+            '<' => Tok::Less,
+            '>' if next == Some(b'=') => {
+                pos += 1;
+                Tok::GreaterEqual
+            }
+            '>' => Tok::Greater,
+            '/' if next == Some(b'/') => {
+                pos += 1;
+                Tok::DoubleSlash
+            }
+            '/' => Tok::Slash,
+            '*' if operand_expected => Tok::Wildcard,
+            '*' => Tok::Multiply,
+            '.' if next == Some(b'.') => {
+                pos += 1;
+                Tok::DotDot
+            }
+            '.' if !next.is_some_and(|b| b.is_ascii_digit()) => Tok::Dot,
+            '"' | '\'' => {
+                let end = input[pos + 1..]
+                    .find(c)
+                    .ok_or_else(|| syntax_error("unterminated string literal", start))?;
+                let literal = input[pos + 1..pos + 1 + end].to_string();
+                pos += end + 1;
+                Tok::Literal(literal)
+            }
+            '0'..='9' | '.' => {
+                let len = input[pos..]
+                    .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+                    .unwrap_or(input.len() - pos);
+                let number = &input[pos..pos + len];
+                if number.matches('.').count() > 1 {
+                    return Err(syntax_error(format!("invalid number '{}'", number), start));
+                }
+                end = Some(pos + len);
+                Tok::Number(number.parse().unwrap())
+            }
+            '$' => {
+                let name = lex_qname(input, pos + 1).ok_or_else(|| syntax_error("expected a variable name", start))?;
+                end = Some(pos + 1 + name.len());
+                Tok::Variable(name)
+            }
+            c if is_name_start(c) => {
+                let name = lex_qname(input, pos).unwrap();
+                let name_end = pos + name.len();
+                end = Some(name_end);
+
+                // `prefix:*` name test
+                if input[name_end..].starts_with(":*") && !name.contains(':') {
+                    end = Some(name_end + 2);
+                    Tok::Wildcard
+                } else if !operand_expected {
+                    match name.as_str() {
+                        "and" => Tok::And,
+                        "or" => Tok::Or,
+                        "mod" => Tok::Mod,
+                        "div" => Tok::Div,
+                        _ => return Err(syntax_error(format!("expected an operator but found '{}'", name), start)),
+                    }
+                } else {
+                    let after = rest_after_whitespace(name_end);
+                    if after.starts_with("::") {
+                        Tok::AxisName(name)
+                    } else if after.starts_with('(') {
+                        match name.as_str() {
+                            "node" | "text" | "comment" | "processing-instruction" => Tok::NodeType(name),
+                            _ => Tok::FunctionName(name),
+                        }
+                    } else {
+                        Tok::Name(name)
+                    }
+                }
+            }
+            c => return Err(syntax_error(format!("unexpected character '{}'", c), start)),
+        };
+
+        pos = end.unwrap_or_else(|| pos + input[pos..].chars().next().map_or(1, char::len_utf8));
+        tokens.push(Token { tok, position: start });
+    }
+
+    Ok(tokens)
+}
+
+/// Reads an NCName or `prefix:local` QName starting at `pos`
+fn lex_qname(input: &str, pos: usize) -> Option<String> {
+    let ncname = |from: usize| -> usize {
+        let rest = &input[from..];
+        if !rest.chars().next().is_some_and(is_name_start) {
+            return 0;
+        }
+        rest.find(|c: char| !is_name_char(c)).unwrap_or(rest.len())
+    };
+
+    let len = ncname(pos);
+    if len == 0 {
+        return None;
+    }
+    let mut end = pos + len;
+    if input[end..].starts_with(':') && !input[end..].starts_with("::") {
+        let local = ncname(end + 1);
+        if local > 0 {
+            end += 1 + local;
+        }
+    }
+    Some(input[pos..end].to_string())
+}
+
+// ---------------------------------------------------------------------------
+// Syntax tree and parser
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BinaryOp {
+    Or,
+    And,
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Modulo,
+    Union,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    Negate(Box<Expr>),
+    Literal(String),
+    Number(f64),
+    Variable(String),
+    Function(String, Vec<Expr>),
+    /// A primary expression followed by predicates
+    Filter(Box<Expr>, Vec<Expr>),
+    Path(Box<Path>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum PathStart {
+    /// Relative to the context node
+    Context,
+    /// `/...`, starting at the document root
+    Root,
+    /// A filter expression that must produce a node-set
+    Filter(Expr),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Path {
+    start: PathStart,
+    steps: Vec<Step>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Axis {
+    Ancestor,
+    AncestorOrSelf,
+    Attribute,
+    Child,
+    Descendant,
+    DescendantOrSelf,
+    Following,
+    FollowingSibling,
+    Namespace,
+    Parent,
+    Preceding,
+    PrecedingSibling,
+    Itself,
+}
+
+impl Axis {
+    fn from_name(name: &str) -> Option<Axis> {
+        Some(match name {
+            "ancestor" => Axis::Ancestor,
+            "ancestor-or-self" => Axis::AncestorOrSelf,
+            "attribute" => Axis::Attribute,
+            "child" => Axis::Child,
+            "descendant" => Axis::Descendant,
+            "descendant-or-self" => Axis::DescendantOrSelf,
+            "following" => Axis::Following,
+            "following-sibling" => Axis::FollowingSibling,
+            "namespace" => Axis::Namespace,
+            "parent" => Axis::Parent,
+            "preceding" => Axis::Preceding,
+            "preceding-sibling" => Axis::PrecedingSibling,
+            "self" => Axis::Itself,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum NodeTest {
+    /// `*`: any node of the axis' principal type
+    Wildcard,
+    Name(String),
+    Node,
+    Text,
+    Comment,
+    ProcessingInstruction,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Step {
+    axis: Axis,
+    test: NodeTest,
+    predicates: Vec<Expr>,
+}
+
+impl Step {
+    /// The `descendant-or-self::node()` step that `//` stands for
+    fn descendant_or_self() -> Step {
+        Step { axis: Axis::DescendantOrSelf, test: NodeTest::Node, predicates: Vec::new() }
+    }
+}
+
+struct ExprParser {
+    tokens: Vec<Token>,
+    pos: usize,
+    /// Length of the expression, reported as the position of a missing token
+    end: usize,
+}
+
+impl ExprParser {
+    fn peek(&self) -> Option<&Tok> {
+        self.tokens.get(self.pos).map(|token| &token.tok)
+    }
+
+    fn position(&self) -> usize {
+        self.tokens.get(self.pos).map_or(self.end, |token| token.position)
+    }
+
+    fn eat(&mut self, tok: &Tok) -> bool {
+        if self.peek() == Some(tok) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, tok: Tok, what: &str) -> Result<(), XPathError> {
+        if self.eat(&tok) {
+            Ok(())
+        } else {
+            Err(syntax_error(format!("expected {}", what), self.position()))
+        }
+    }
+
+    fn parse_expr(&mut self) -> Result<Expr, XPathError> {
+        self.parse_binary(0)
+    }
+
+    /// Parses the binary operators, loosest first: `or`, `and`, equality,
+    /// relational, additive, multiplicative
+    fn parse_binary(&mut self, level: usize) -> Result<Expr, XPathError> {
+        const LEVELS: [&[(Tok, BinaryOp)]; 6] = [
+            &[(Tok::Or, BinaryOp::Or)],
+            &[(Tok::And, BinaryOp::And)],
+            &[(Tok::Equal, BinaryOp::Equal), (Tok::NotEqual, BinaryOp::NotEqual)],
+            &[
+                (Tok::Less, BinaryOp::Less),
+                (Tok::LessEqual, BinaryOp::LessEqual),
+                (Tok::Greater, BinaryOp::Greater),
+                (Tok::GreaterEqual, BinaryOp::GreaterEqual),
+            ],
+            &[(Tok::Plus, BinaryOp::Add), (Tok::Minus, BinaryOp::Subtract)],
+            &[(Tok::Multiply, BinaryOp::Multiply), (Tok::Div, BinaryOp::Divide), (Tok::Mod, BinaryOp::Modulo)],
+        ];
+
+        if level == LEVELS.len() {
+            return self.parse_unary();
+        }
+
+        let mut left = self.parse_binary(level + 1)?;
+        'operators: loop {
+            for (tok, op) in LEVELS[level] {
+                if self.eat(tok) {
+                    let right = self.parse_binary(level + 1)?;
+                    left = Expr::Binary(*op, Box::new(left), Box::new(right));
+                    continue 'operators;
+                }
+            }
+            return Ok(left);
+        }
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, XPathError> {
+        if self.eat(&Tok::Minus) {
+            return Ok(Expr::Negate(Box::new(self.parse_unary()?)));
+        }
+        let mut left = self.parse_path()?;
+        while self.eat(&Tok::Pipe) {
+            let right = self.parse_path()?;
+            left = Expr::Binary(BinaryOp::Union, Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_path(&mut self) -> Result<Expr, XPathError> {
+        let start = match self.peek() {
+            Some(Tok::Slash) => {
+                self.pos += 1;
+                let mut steps = Vec::new();
+                if self.at_step() {
+                    steps = self.parse_relative_path()?;
+                }
+                return Ok(Expr::Path(Box::new(Path { start: PathStart::Root, steps })));
+            }
+            Some(Tok::DoubleSlash) => {
+                self.pos += 1;
+                let mut steps = vec![Step::descendant_or_self()];
+                steps.extend(self.parse_relative_path()?);
+                return Ok(Expr::Path(Box::new(Path { start: PathStart::Root, steps })));
+            }
+            Some(Tok::Variable(_) | Tok::LParen | Tok::Literal(_) | Tok::Number(_) | Tok::FunctionName(_)) => {
+                self.parse_filter()?
+            }
+            _ => {
+                let steps = self.parse_relative_path()?;
+                return Ok(Expr::Path(Box::new(Path { start: PathStart::Context, steps })));
+            }
+        };
+
+        let mut steps = Vec::new();
+        loop {
+            if self.eat(&Tok::Slash) {
+                steps.push(self.parse_step()?);
+            } else if self.eat(&Tok::DoubleSlash) {
+                steps.push(Step::descendant_or_self());
+                steps.push(self.parse_step()?);
+            } else {
+                break;
+            }
+        }
+
+        if steps.is_empty() {
+            Ok(start)
+        } else {
+            Ok(Expr::Path(Box::new(Path { start: PathStart::Filter(start), steps })))
+        }
+    }
+
+    fn parse_filter(&mut self) -> Result<Expr, XPathError> {
+        let primary = self.parse_primary()?;
+        let predicates = self.parse_predicates()?;
+        if predicates.is_empty() {
+            Ok(primary)
+        } else {
+            Ok(Expr::Filter(Box::new(primary), predicates))
+        }
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, XPathError> {
+        let position = self.position();
+        let tok = self.peek().cloned();
+        self.pos += 1;
+
+        match tok {
+            Some(Tok::Variable(name)) => Ok(Expr::Variable(name)),
+            Some(Tok::Literal(literal)) => Ok(Expr::Literal(literal)),
+            Some(Tok::Number(number)) => Ok(Expr::Number(number)),
+            Some(Tok::LParen) => {
+                let expr = self.parse_expr()?;
+                self.expect(Tok::RParen, "')'")?;
+                Ok(expr)
+            }
+            Some(Tok::FunctionName(name)) => {
+                self.expect(Tok::LParen, "'('")?;
+                let mut args = Vec::new();
+                if !self.eat(&Tok::RParen) {
+                    loop {
+                        args.push(self.parse_expr()?);
+                        if self.eat(&Tok::RParen) {
+                            break;
+                        }
+                        self.expect(Tok::Comma, "',' or ')'")?;
+                    }
+                }
+                check_function(&name, args.len(), position)?;
+                Ok(Expr::Function(name, args))
+            }
+            _ => Err(syntax_error("expected an expression", position)),
+        }
+    }
+
+    fn at_step(&self) -> bool {
+        matches!(
+            self.peek(),
+            Some(Tok::Dot | Tok::DotDot | Tok::At | Tok::AxisName(_) | Tok::Wildcard | Tok::Name(_) | Tok::NodeType(_))
+        )
+    }
+
+    fn parse_relative_path(&mut self) -> Result<Vec<Step>, XPathError> {
+        let mut steps = vec![self.parse_step()?];
+        loop {
+            if self.eat(&Tok::Slash) {
+                steps.push(self.parse_step()?);
+            } else if self.eat(&Tok::DoubleSlash) {
+                steps.push(Step::descendant_or_self());
+                steps.push(self.parse_step()?);
+            } else {
+                return Ok(steps);
+            }
+        }
+    }
+
+    fn parse_step(&mut self) -> Result<Step, XPathError> {
+        if self.eat(&Tok::Dot) {
+            return Ok(Step { axis: Axis::Itself, test: NodeTest::Node, predicates: Vec::new() });
+        }
+        if self.eat(&Tok::DotDot) {
+            return Ok(Step { axis: Axis::Parent, test: NodeTest::Node, predicates: Vec::new() });
+        }
+
+        let axis = match self.peek().cloned() {
+            Some(Tok::At) => {
+                self.pos += 1;
+                Axis::Attribute
+            }
+            Some(Tok::AxisName(name)) => {
+                let position = self.position();
+                self.pos += 1;
+                self.expect(Tok::ColonColon, "'::'")?;
+                Axis::from_name(&name).ok_or_else(|| syntax_error(format!("unknown axis '{}'", name), position))?
+            }
+            _ => Axis::Child,
+        };
+
+        let position = self.position();
+        let test = match self.peek().cloned() {
+            Some(Tok::Wildcard) => {
+                self.pos += 1;
+                NodeTest::Wildcard
+            }
+            Some(Tok::Name(name)) => {
+                self.pos += 1;
+                NodeTest::Name(name)
+            }
+            Some(Tok::NodeType(kind)) => {
+                self.pos += 1;
+                self.expect(Tok::LParen, "'('")?;
+                let test = match kind.as_str() {
+                    "node" => NodeTest::Node,
+                    "text" => NodeTest::Text,
+                    "comment" => NodeTest::Comment,
+                    _ => {
+                        // processing-instruction() takes an optional target literal
+                        if let Some(Tok::Literal(_)) = self.peek() {
+                            self.pos += 1;
+                        }
+                        NodeTest::ProcessingInstruction
+                    }
+                };
+                self.expect(Tok::RParen, "')'")?;
+                test
+            }
+            _ => return Err(syntax_error("expected a node test", position)),
+        };
+
+        let predicates = self.parse_predicates()?;
+        Ok(Step { axis, test, predicates })
+    }
+
+    fn parse_predicates(&mut self) -> Result<Vec<Expr>, XPathError> {
+        let mut predicates = Vec::new();
+        while self.eat(&Tok::LBracket) {
+            predicates.push(self.parse_expr()?);
+            self.expect(Tok::RBracket, "']'")?;
+        }
+        Ok(predicates)
+    }
+}
+
+/// Rejects calls to unknown functions or with the wrong number of arguments
+fn check_function(name: &str, args: usize, position: usize) -> Result<(), XPathError> {
+    let (min, max) = match name {
+        "last" | "position" | "true" | "false" => (0, 0),
+        "count" | "id" | "boolean" | "not" | "lang" | "sum" | "floor" | "ceiling" | "round" => (1, 1),
+        "local-name" | "namespace-uri" | "name" | "string" | "string-length" | "normalize-space" | "number" => (0, 1),
+        "starts-with" | "contains" | "substring-before" | "substring-after" => (2, 2),
+        "substring" => (2, 3),
+        "translate" => (3, 3),
+        "concat" => (2, usize::MAX),
+        _ => return Err(syntax_error(format!("unknown function '{}()'", name), position)),
+    };
+    if args < min || args > max {
+        return Err(syntax_error(format!("wrong number of arguments for '{}()'", name), position));
+    }
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Evaluation
+// ---------------------------------------------------------------------------
+
+struct Context<'a> {
+    node: XPathNode<'a>,
+    position: usize,
+    size: usize,
+}
+
+fn evaluate<'a>(expr: &Expr, context: &Context<'a>) -> Result<Value<'a>, XPathError> {
+    match expr {
+        Expr::Literal(literal) => Ok(Value::String(literal.clone())),
+        Expr::Number(number) => Ok(Value::Number(*number)),
+        Expr::Variable(name) => Err(evaluation_error(format!("unknown variable '${}'", name))),
+        Expr::Negate(inner) => Ok(Value::Number(-evaluate(inner, context)?.to_number())),
+        Expr::Function(name, args) => call_function(name, args, context),
+        Expr::Filter(primary, predicates) => {
+            let nodes = node_set(evaluate(primary, context)?)?;
+            Ok(Value::NodeSet(apply_predicates(nodes, predicates)?))
+        }
+        Expr::Path(path) => evaluate_path(path, context),
+        Expr::Binary(op, left, right) => evaluate_binary(*op, left, right, context),
+    }
+}
+
+fn node_set(value: Value) -> Result<Vec<XPathNode>, XPathError> {
+    match value {
+        Value::NodeSet(nodes) => Ok(nodes),
+        _ => Err(evaluation_error("expected a node-set")),
+    }
+}
+
+fn sort_and_dedup(nodes: &mut Vec<XPathNode>) {
+    nodes.sort_by_key(XPathNode::order_key);
+    nodes.dedup();
+}
+
+fn evaluate_binary<'a>(op: BinaryOp, left: &Expr, right: &Expr, context: &Context<'a>) -> Result<Value<'a>, XPathError> {
+    // `and` and `or` only evaluate their right side when needed
+    match op {
+        BinaryOp::Or => {
+            let result = evaluate(left, context)?.to_boolean() || evaluate(right, context)?.to_boolean();
+            return Ok(Value::Boolean(result));
+        }
+        BinaryOp::And => {
+            let result = evaluate(left, context)?.to_boolean() && evaluate(right, context)?.to_boolean();
+            return Ok(Value::Boolean(result));
+        }
+        _ => {}
+    }
+
+    let left = evaluate(left, context)?;
+    let right = evaluate(right, context)?;
+
+    let number = |f: fn(f64, f64) -> f64| Ok(Value::Number(f(left.to_number(), right.to_number())));
+    match op {
+        BinaryOp::Add => number(|a, b| a + b),
+        BinaryOp::Subtract => number(|a, b| a - b),
+        BinaryOp::Multiply => number(|a, b| a * b),
+        BinaryOp::Divide => number(|a, b| a / b),
+        BinaryOp::Modulo => number(|a, b| a % b),
+        BinaryOp::Union => {
+            let mut nodes = node_set(left)?;
+            nodes.extend(node_set(right)?);
+            sort_and_dedup(&mut nodes);
+            Ok(Value::NodeSet(nodes))
+        }
+        _ => Ok(Value::Boolean(compare(op, &left, &right))),
+    }
+}
+
+/// Compares two values with XPath's rules, where a comparison involving a
+/// node-set is true if it holds for any of its nodes
+fn compare(op: BinaryOp, left: &Value, right: &Value) -> bool {
+    match (left, right) {
+        (Value::NodeSet(left), Value::NodeSet(right)) => left.iter().any(|a| {
+            let a = Value::String(a.string_value());
+            right.iter().any(|b| compare(op, &a, &Value::String(b.string_value())))
+        }),
+        (Value::NodeSet(nodes), Value::Boolean(_)) => compare(op, &Value::Boolean(!nodes.is_empty()), right),
+        (Value::Boolean(_), Value::NodeSet(nodes)) => compare(op, left, &Value::Boolean(!nodes.is_empty())),
+        (Value::NodeSet(nodes), other) => nodes
+            .iter()
+            .any(|node| compare(op, &scalar_like(node, other), other)),
+        (other, Value::NodeSet(nodes)) => nodes
+            .iter()
+            .any(|node| compare(op, other, &scalar_like(node, other))),
+        _ => match op {
+            BinaryOp::Equal | BinaryOp::NotEqual => {
+                let equal = if matches!(left, Value::Boolean(_)) || matches!(right, Value::Boolean(_)) {
+                    left.to_boolean() == right.to_boolean()
+                } else if matches!(left, Value::Number(_)) || matches!(right, Value::Number(_)) {
+                    left.to_number() == right.to_number()
+                } else {
+                    left.to_string_value() == right.to_string_value()
+                };
+                equal == (op == BinaryOp::Equal)
+            }
+            _ => {
+                let (a, b) = (left.to_number(), right.to_number());
+                match op {
+                    BinaryOp::Less => a < b,
+                    BinaryOp::LessEqual => a <= b,
+                    BinaryOp::Greater => a > b,
+                    _ => a >= b,
+                }
+            }
+        },
+    }
+}
+
+/// Converts a node to the type of the value it is compared with
+fn scalar_like<'a>(node: &XPathNode<'a>, other: &Value) -> Value<'a> {
+    match other {
+        Value::Number(_) => Value::Number(parse_number(&node.string_value())),
+        _ => Value::String(node.string_value()),
+    }
+}
+
+fn evaluate_path<'a>(path: &Path, context: &Context<'a>) -> Result<Value<'a>, XPathError> {
+    let mut nodes = match &path.start {
+        PathStart::Context => vec![context.node],
+        PathStart::Root => {
+            let node = match context.node {
+                XPathNode::Node(node) => node,
+                XPathNode::Attribute { owner, .. } => owner,
+            };
+            vec![XPathNode::Node(node.document().root())]
+        }
+        PathStart::Filter(expr) => node_set(evaluate(expr, context)?)?,
+    };
+
+    for step in &path.steps {
+        let mut next = Vec::new();
+        for node in &nodes {
+            let candidates: Vec<XPathNode> = axis_nodes(node, step.axis)
+                .into_iter()
+                .filter(|candidate| node_test(candidate, &step.test, step.axis))
+                .collect();
+            next.extend(apply_predicates(candidates, &step.predicates)?);
+        }
+        sort_and_dedup(&mut next);
+        nodes = next;
+    }
+
+    Ok(Value::NodeSet(nodes))
+}
+
+/// Filters `nodes` (in axis order) through each predicate in turn
+fn apply_predicates<'a>(mut nodes: Vec<XPathNode<'a>>, predicates: &[Expr]) -> Result<Vec<XPathNode<'a>>, XPathError> {
+    for predicate in predicates {
+        let size = nodes.len();
+        let mut kept = Vec::new();
+        for (index, node) in nodes.into_iter().enumerate() {
+            let context = Context { node, position: index + 1, size };
+            let keep = match evaluate(predicate, &context)? {
+                Value::Number(number) => number == context.position as f64,
+                value => value.to_boolean(),
+            };
+            if keep {
+                kept.push(node);
+            }
+        }
+        nodes = kept;
+    }
+    Ok(nodes)
+}
+
+/// Doctype nodes are not part of the XPath data model
+fn visible(node: &Node) -> bool {
+    node.kind() != NodeKind::Doctype
+}
+
+/// The nodes on `axis` from `node`, in axis order
+///
+/// The reverse axes (`ancestor`, `ancestor-or-self`, `preceding` and
+/// `preceding-sibling`) list the nearest node first, so predicate positions
+/// count backwards from the context node.
+fn axis_nodes<'a>(node: &XPathNode<'a>, axis: Axis) -> Vec<XPathNode<'a>> {
+    let wrap = |nodes: &mut dyn Iterator<Item = Node<'a>>| -> Vec<XPathNode<'a>> {
+        nodes.filter(visible).map(XPathNode::Node).collect()
+    };
+
+    match axis {
+        Axis::Itself => vec![*node],
+        Axis::Parent => node.parent().into_iter().collect(),
+        Axis::Ancestor | Axis::AncestorOrSelf => {
+            let mut nodes = Vec::new();
+            if axis == Axis::AncestorOrSelf {
+                nodes.push(*node);
+            }
+            let mut current = node.parent();
+            while let Some(ancestor) = current {
+                nodes.push(ancestor);
+                current = ancestor.parent();
+            }
+            nodes
+        }
+        Axis::Attribute => match node {
+            XPathNode::Node(owner) => owner
+                .raw_attributes()
+                .enumerate()
+                .map(|(index, (name, value))| XPathNode::Attribute { owner: *owner, index, name, value })
+                .collect(),
+            XPathNode::Attribute { .. } => Vec::new(),
+        },
+        Axis::Namespace => Vec::new(),
+        _ => {
+            let tree_node = match node {
+                XPathNode::Node(tree_node) => *tree_node,
+                XPathNode::Attribute { owner, .. } => {
+                    return match axis {
+                        // An attribute's "following" nodes start with its element's content
+                        Axis::Following => {
+                            let mut nodes = wrap(&mut owner.descendants());
+                            nodes.extend(axis_nodes(&XPathNode::Node(*owner), Axis::Following));
+                            nodes
+                        }
+                        Axis::Preceding => {
+                            let mut nodes = axis_nodes(&XPathNode::Node(*owner), Axis::Preceding);
+                            nodes.retain(|candidate| candidate.as_node() != Some(*owner));
+                            nodes
+                        }
+                        _ => Vec::new(),
+                    };
+                }
+            };
+
+            match axis {
+                Axis::Child => wrap(&mut tree_node.children()),
+                Axis::Descendant => wrap(&mut tree_node.descendants()),
+                Axis::DescendantOrSelf => {
+                    let mut nodes = vec![*node];
+                    nodes.extend(wrap(&mut tree_node.descendants()));
+                    nodes
+                }
+                Axis::FollowingSibling => wrap(&mut tree_node.next_siblings()),
+                Axis::PrecedingSibling => wrap(&mut tree_node.prev_siblings()),
+                Axis::Following => {
+                    let mut nodes = Vec::new();
+                    let mut current = Some(tree_node);
+                    while let Some(ancestor) = current {
+                        for sibling in ancestor.next_siblings() {
+                            nodes.push(sibling);
+                            nodes.extend(sibling.descendants());
+                        }
+                        current = ancestor.parent();
+                    }
+                    wrap(&mut nodes.into_iter())
+                }
+                Axis::Preceding => {
+                    let ancestors: Vec<Node> = tree_node.ancestors().collect();
+                    let mut nodes: Vec<Node> = tree_node
+                        .document()
+                        .root()
+                        .descendants()
+                        .take_while(|candidate| *candidate != tree_node)
+                        .filter(|candidate| !ancestors.contains(candidate))
+                        .collect();
+                    nodes.reverse();
+                    wrap(&mut nodes.into_iter())
+                }
+                _ => unreachable!(),
+            }
+        }
+    }
+}
+
+fn node_test(node: &XPathNode, test: &NodeTest, axis: Axis) -> bool {
+    match (test, node) {
+        (NodeTest::Node, _) => true,
+        (NodeTest::Text, XPathNode::Node(node)) => node.is_text(),
+        (NodeTest::Comment, XPathNode::Node(node)) => node.is_comment(),
+        (NodeTest::ProcessingInstruction, _) => false,
+        // The principal node type is attribute on the attribute axis and element elsewhere
+        (NodeTest::Wildcard, XPathNode::Attribute { .. }) => axis == Axis::Attribute,
+        (NodeTest::Wildcard, XPathNode::Node(node)) => axis != Axis::Attribute && node.is_element(),
+        (NodeTest::Name(name), XPathNode::Attribute { name: attr_name, .. }) => {
+            axis == Axis::Attribute && attr_name.eq_ignore_ascii_case(name)
+        }
+        (NodeTest::Name(name), XPathNode::Node(node)) => {
+            axis != Axis::Attribute && node.name().is_some_and(|node_name| node_name.eq_ignore_ascii_case(name))
+        }
+        _ => false,
+    }
+}
+
+fn call_function<'a>(name: &str, args: &[Expr], context: &Context<'a>) -> Result<Value<'a>, XPathError> {
+    let arg = |index: usize| evaluate(&args[index], context);
+    let string_arg = |index: usize| -> Result<String, XPathError> {
+        match args.get(index) {
+            Some(expr) => Ok(evaluate(expr, context)?.to_string_value()),
+            None => Ok(context.node.string_value()),
+        }
+    };
+    let number_arg = |index: usize| -> Result<f64, XPathError> { Ok(arg(index)?.to_number()) };
+    // Functions taking an optional node-set use the first node, or the context node
+    let node_arg = |index: usize| -> Result<Option<XPathNode<'a>>, XPathError> {
+        match args.get(index) {
+            Some(expr) => Ok(node_set(evaluate(expr, context)?)?.first().copied()),
+            None => Ok(Some(context.node)),
+        }
+    };
+
+    let value = match name {
+        "last" => Value::Number(context.size as f64),
+        "position" => Value::Number(context.position as f64),
+        "count" => Value::Number(node_set(arg(0)?)?.len() as f64),
+        "id" => {
+            let ids: Vec<String> = match arg(0)? {
+                Value::NodeSet(nodes) => nodes.iter().map(XPathNode::string_value).collect(),
+                value => vec![value.to_string_value()],
+            };
+            let ids: Vec<&str> = ids.iter().flat_map(|ids| ids.split_ascii_whitespace()).collect();
+            let root = match context.node {
+                XPathNode::Node(node) => node.document().root(),
+                XPathNode::Attribute { owner, .. } => owner.document().root(),
+            };
+            let nodes = root
+                .descendants()
+                .filter(|node| node.attribute("id").is_some_and(|id| ids.contains(&id.as_ref())))
+                .map(XPathNode::Node)
+                .collect();
+            Value::NodeSet(nodes)
+        }
+        "local-name" | "name" => {
+            let name = match node_arg(0)? {
+                Some(XPathNode::Node(node)) => node.name().unwrap_or_default(),
+                Some(XPathNode::Attribute { name, .. }) => name,
+                None => "",
+            };
+            Value::String(name.to_string())
+        }
+        "namespace-uri" => {
+            node_arg(0)?;
+            Value::String(String::new())
+        }
+        "string" => Value::String(string_arg(0)?),
+        "concat" => {
+            let mut result = String::new();
+            for index in 0..args.len() {
+                result.push_str(&string_arg(index)?);
+            }
+            Value::String(result)
+        }
+        "starts-with" => Value::Boolean(string_arg(0)?.starts_with(&string_arg(1)?)),
+        "contains" => Value::Boolean(string_arg(0)?.contains(&string_arg(1)?)),
+        "substring-before" => {
+            let (string, pattern) = (string_arg(0)?, string_arg(1)?);
+            Value::String(string.find(&pattern).map_or("", |index| &string[..index]).to_string())
+        }
+        "substring-after" => {
+            let (string, pattern) = (string_arg(0)?, string_arg(1)?);
+            Value::String(
+                string.find(&pattern).map_or("", |index| &string[index + pattern.len()..]).to_string(),
+            )
+        }
+        "substring" => {
+            let string = string_arg(0)?;
+            let start = round(number_arg(1)?);
+            let end = match args.get(2) {
+                Some(_) => start + round(number_arg(2)?),
+                None => f64::INFINITY,
+            };
+            // Characters are numbered from 1; NaN bounds select nothing
+            let result = string
+                .chars()
+                .enumerate()
+                .filter(|(index, _)| {
+                    let position = (*index + 1) as f64;
+                    position >= start && position < end
+                })
+                .map(|(_, c)| c)
+                .collect();
+            Value::String(result)
+        }
+        "string-length" => Value::Number(string_arg(0)?.chars().count() as f64),
+        "normalize-space" => {
+            let string = string_arg(0)?;
+            Value::String(string.split_ascii_whitespace().collect::<Vec<_>>().join(" "))
+        }
+        "translate" => {
+            let (string, from, to) = (string_arg(0)?, string_arg(1)?, string_arg(2)?);
+            let from: Vec<char> = from.chars().collect();
+            let to: Vec<char> = to.chars().collect();
+            let result = string
+                .chars()
+                .filter_map(|c| match from.iter().position(|f| *f == c) {
+                    Some(index) => to.get(index).copied(),
+                    None => Some(c),
+                })
+                .collect();
+            Value::String(result)
+        }
+        "boolean" => Value::Boolean(arg(0)?.to_boolean()),
+        "not" => Value::Boolean(!arg(0)?.to_boolean()),
+        "true" => Value::Boolean(true),
+        "false" => Value::Boolean(false),
+        "lang" => {
+            let wanted = string_arg(0)?.to_ascii_lowercase();
+            let mut current = Some(context.node);
+            let mut lang = None;
+            while let Some(node) = current {
+                if let Some(element) = node.as_node() {
+                    lang = element
+                        .attributes()
+                        .find(|(name, _)| name.eq_ignore_ascii_case("xml:lang") || name.eq_ignore_ascii_case("lang"))
+                        .map(|(_, value)| value.to_ascii_lowercase());
+                    if lang.is_some() {
+                        break;
+                    }
+                }
+                current = node.parent();
+            }
+            Value::Boolean(lang.is_some_and(|lang| {
+                lang == wanted || (lang.starts_with(&wanted) && lang[wanted.len()..].starts_with('-'))
+            }))
+        }
+        "number" => Value::Number(match args.first() {
+            Some(_) => number_arg(0)?,
+            None => parse_number(&context.node.string_value()),
+        }),
+        "sum" => Value::Number(
+            node_set(arg(0)?)?
+                .iter()
+                .map(|node| parse_number(&node.string_value()))
+                .sum(),
+        ),
+        "floor" => Value::Number(number_arg(0)?.floor()),
+        "ceiling" => Value::Number(number_arg(0)?.ceil()),
+        "round" => Value::Number(round(number_arg(0)?)),
+        _ => return Err(evaluation_error(format!("unknown function '{}()'", name))),
+    };
+    Ok(value)
+}
+
+/// Rounds half up, as XPath's `round()` does (so `round(-2.5)` is `-2`)
+fn round(number: f64) -> f64 {
+    if number.is_nan() || number.is_infinite() {
+        return number;
+    }
+    let rounded = (number + 0.5).floor();
+    if rounded == 0.0 && number.is_sign_negative() {
+        -0.0
+    } else {
+        rounded
+    }
+}
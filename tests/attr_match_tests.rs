@@ -0,0 +1,76 @@
+use tagparser::{parse_tags_matching, try_parse_tags_matching, AttrMatch, Error, Parser, StreamParser};
+
+const PAGE: &str = r#"
+    <a href="https://rust-lang.org" class="nav primary" lang="en-GB">Rust</a>
+    <a href="http://example.com/docs" class="nav" lang="en">Docs</a>
+    <a href="/about" class="navigation" lang="fr">About</a>
+    <a href="HTTPS://SHOUT.EXAMPLE/" lang="english">Shout</a>
+    <img src="/logo.png"><img src="/photo.JPG"><img src="/icon.svg">
+"#;
+
+// Helper function to get the text of the links whose attribute passes `matcher`
+fn links(attr_name: &str, matcher: &AttrMatch) -> Vec<String> {
+    let parser = Parser::new(PAGE);
+    parser.find_tags_matching("a", attr_name, matcher).into_iter().map(|tag| tag.text).collect()
+}
+
+fn s(value: &str) -> String {
+    value.to_string()
+}
+
+#[test]
+fn test_string_operators() {
+    assert_eq!(vec!["Rust", "Docs", "About", "Shout"], links("href", &AttrMatch::Exists));
+    assert_eq!(vec!["About"], links("href", &AttrMatch::Equals(s("/about"))));
+    assert_eq!(vec!["Rust"], links("href", &AttrMatch::Prefix(s("https://"))));
+    assert_eq!(vec!["Docs"], links("href", &AttrMatch::Suffix(s("/docs"))));
+    assert_eq!(vec!["Rust", "Docs", "Shout"], links("href", &AttrMatch::Contains(s("."))));
+
+    // Test that a word must be a whole whitespace-separated word
+    assert_eq!(vec!["Rust", "Docs"], links("class", &AttrMatch::Word(s("nav"))));
+    assert_eq!(vec!["Rust"], links("class", &AttrMatch::Word(s("primary"))));
+
+    // Test that a dash prefix matches the whole value or the part before a `-`
+    assert_eq!(vec!["Rust", "Docs"], links("lang", &AttrMatch::DashPrefix(s("en"))));
+}
+
+#[test]
+fn test_regex_and_ignore_case() {
+    let raster = AttrMatch::regex(r"\.(png|jpe?g)$").unwrap();
+    let parser = Parser::new(PAGE);
+    assert_eq!(vec![r#"<img src="/logo.png">"#], parser.parse_tags_matching("img", "src", &raster));
+    assert_eq!(2, parser.parse_tags_matching("img", "src", &raster.ignore_case()).len());
+    assert!(AttrMatch::regex("(").is_err());
+
+    // Test that both the value and the operand are compared ignoring case
+    assert_eq!(vec!["Rust", "Shout"], links("href", &AttrMatch::Prefix(s("https://")).ignore_case()));
+    assert_eq!(vec!["Shout"], links("href", &AttrMatch::Contains(s("Shout")).ignore_case()));
+    assert_eq!(vec!["About"], links("href", &AttrMatch::IgnoreCase(Box::new(AttrMatch::Equals(s("/ABOUT"))))));
+    assert!(AttrMatch::Word(s("ÉTÉ")).ignore_case().matches("été hiver"));
+
+    // Test that a regular expression wrapped in IgnoreCase sees the value as it is
+    let capitals = AttrMatch::IgnoreCase(Box::new(AttrMatch::regex("[A-Z]").unwrap()));
+    assert!(capitals.matches("ABC"));
+    assert!(!capitals.matches("abc"));
+}
+
+#[test]
+fn test_matching_functions() {
+    let secure = AttrMatch::Prefix(s("https://"));
+
+    // Test that the free functions and the stream give the parser's results
+    let expected = vec![r#"<a href="https://rust-lang.org" class="nav primary" lang="en-GB">Rust</a>"#];
+    assert_eq!(expected, parse_tags_matching(PAGE.to_string(), "a".to_string(), "href", &secure));
+    let streamed: Vec<String> = StreamParser::new(PAGE.as_bytes())
+        .parse_tags_matching("a", "href", &secure)
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(expected, streamed);
+
+    // Test that invalid names are rejected by the try_* variants
+    assert_eq!(
+        Err(Error::InvalidAttributeName("h ref".to_string())),
+        try_parse_tags_matching(PAGE.to_string(), "a".to_string(), "h ref", &secure)
+    );
+    assert!(StreamParser::new(PAGE.as_bytes()).try_parse_tags_matching("", "href", &secure).is_err());
+}
@@ -0,0 +1,71 @@
+use std::borrow::Cow;
+
+use tagparser::{Document, Parser};
+
+// Helper function to check that `part` is a slice of `whole` rather than a copy
+fn is_slice_of(part: &str, whole: &str) -> bool {
+    let start = whole.as_ptr() as usize;
+    let offset = part.as_ptr() as usize;
+    offset >= start && offset + part.len() <= start + whole.len()
+}
+
+#[test]
+fn test_parser_borrows_html() {
+    let html = "<div><a href='/one'>One</a><p class='x'>Text</p></div>";
+    let parser = Parser::new(html);
+
+    // Test that the document source is the input itself
+    assert_eq!(html.as_ptr(), parser.document().source().as_ptr());
+    assert_eq!(html.as_ptr(), Document::parse(html).source().as_ptr());
+
+    // Test that HTML results are slices of the input
+    let links = parser.parse_tags("a");
+    assert_eq!(vec!["<a href='/one'>One</a>"], links);
+    assert!(is_slice_of(links[0], html));
+
+    let content = parser.extract_tag_content("p");
+    assert_eq!(vec!["Text"], content);
+    assert!(is_slice_of(content[0], html));
+
+    let selected = parser.select("div > p.x").unwrap();
+    assert!(is_slice_of(selected[0], html));
+
+    let filtered = parser.parse_tags_with_attr("a", "href", Some("/one"));
+    assert!(is_slice_of(filtered[0], html));
+}
+
+#[test]
+fn test_values_are_owned_only_when_decoded() {
+    let html = "<a href='/plain'>A</a><a href='/a?x=1&amp;y=2'>B</a>";
+    let parser = Parser::new(html);
+
+    let hrefs = parser.extract_attribute_values("a", "href");
+    assert_eq!(vec!["/plain", "/a?x=1&y=2"], hrefs);
+    assert!(matches!(&hrefs[0], Cow::Borrowed(href) if is_slice_of(href, html)));
+    assert!(matches!(hrefs[1], Cow::Owned(_)));
+
+    // Test that turning decoding off borrows every value
+    let raw = Parser::new(html).decode_entities(false);
+    let hrefs = raw.extract_attribute_values("a", "href");
+    assert_eq!(vec!["/plain", "/a?x=1&amp;y=2"], hrefs);
+    assert!(hrefs.iter().all(|href| matches!(href, Cow::Borrowed(_))));
+
+    // Test that XPath results follow the same rule
+    let values = parser.xpath("//a/@href | //a/text()").unwrap();
+    assert_eq!(vec!["/plain", "A", "/a?x=1&y=2", "B"], values);
+    assert!(matches!(values[0], Cow::Borrowed(_)));
+    assert!(matches!(values[1], Cow::Borrowed(_)));
+    assert!(matches!(values[2], Cow::Owned(_)));
+}
+
+#[test]
+fn test_parser_owns_string_html() {
+    // Test that a parser built from a `String` can outlive the code that made it
+    fn parse(tag: &str) -> Parser<'static> {
+        Parser::new(format!("<{tag}>Owned</{tag}>"))
+    }
+
+    let parser = parse("b");
+    assert_eq!(vec!["<b>Owned</b>"], parser.parse_tags("b"));
+    assert_eq!(vec!["Owned"], parser.text_content("b"));
+}
@@ -1,4 +1,7 @@
-use std::process::Command;
+#![allow(clippy::needless_borrows_for_generic_args)]
+
+use std::io::Write;
+use std::process::{Command, Stdio};
 use std::str;
 
 #[test]
@@ -145,4 +148,276 @@ fn test_cli_extract_attribute_values_multiple_attributes() {
     assert!(stdout.contains("beginner"));
     assert!(stdout.contains("intermediate"));
     assert!(stdout.contains("advanced"));
-} 
\ No newline at end of file
+} 
+#[test]
+fn test_cli_positions() {
+    let html_file = "tests/test_data/rust_page.html";
+    
+    let output = Command::new("cargo")
+        .args(["run", "--", "--file", html_file, "h1", "--content", "--positions"])
+        .output()
+        .expect("Failed to execute command");
+    
+    let stdout = str::from_utf8(&output.stdout).unwrap();
+    assert_eq!(
+        "tests/test_data/rust_page.html:65:13: \"Rust Programming Language\"",
+        stdout.trim()
+    );
+}
+
+#[test]
+fn test_cli_positions_inline_html() {
+    let html = "<a href='/one'>One</a>\n  <a href='/two'>Two</a>";
+    
+    let output = Command::new("cargo")
+        .args(["run", "--", html, "a", "href", "--attr-values", "--positions"])
+        .output()
+        .expect("Failed to execute command");
+    
+    let stdout = str::from_utf8(&output.stdout).unwrap();
+    assert_eq!("<html>:1:1: \"/one\"\n<html>:2:3: \"/two\"", stdout.trim());
+}
+
+#[test]
+fn test_cli_extract_text() {
+    let html = "<p>Fish &amp; <b>Chips</b><script>alert(1)</script></p>";
+    
+    let output = Command::new("cargo")
+        .args(["run", "--", html, "p", "--text"])
+        .output()
+        .expect("Failed to execute command");
+    
+    let stdout = str::from_utf8(&output.stdout).unwrap();
+    assert_eq!("[\"Fish & Chips\"]", stdout.trim());
+}
+
+#[test]
+fn test_cli_validate() {
+    let html = "<div>\n  <b><i>Text</b></i>\n</div>";
+    
+    let output = Command::new("cargo")
+        .args(["run", "--", html, "--validate"])
+        .output()
+        .expect("Failed to execute command");
+    
+    let stdout = str::from_utf8(&output.stdout).unwrap();
+    assert_eq!(
+        "<html>:2:6: <i> is closed by </b> before its own end tag\n<html>:2:17: </i> doesn't close any open element and is ignored",
+        stdout.trim()
+    );
+    assert_eq!(Some(1), output.status.code());
+    
+    // Test that a clean file passes
+    let output = Command::new("cargo")
+        .args(["run", "--", "--file", "tests/test_data/rust_page.html", "--validate"])
+        .output()
+        .expect("Failed to execute command");
+    
+    assert!(output.status.success());
+    assert_eq!("", str::from_utf8(&output.stdout).unwrap());
+}
+
+#[test]
+fn test_cli_invalid_tag_name() {
+    let output = Command::new("cargo")
+        .args(["run", "--", "<a href='/'>Home</a>", "a b"])
+        .output()
+        .expect("Failed to execute command");
+    
+    let stdout = str::from_utf8(&output.stdout).unwrap();
+    assert_eq!("Error: invalid tag name \"a b\"", stdout.trim());
+}
+
+#[test]
+fn test_cli_file_from_stdin() {
+    let mut child = Command::new("cargo")
+        .args(["run", "--", "--file", "-", "a", "href", "--attr-values"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("Failed to execute command");
+    
+    let html = "<ul><li><a href='/one'>One</a><li><a href='/two'>Two</a></ul>";
+    child.stdin.take().unwrap().write_all(html.as_bytes()).unwrap();
+    let output = child.wait_with_output().expect("Failed to wait for command");
+    
+    let stdout = str::from_utf8(&output.stdout).unwrap();
+    assert_eq!("[\"/one\", \"/two\"]", stdout.trim());
+}
+
+#[test]
+fn test_cli_streamed_file_matches_parser() {
+    let html_file = "tests/test_data/rust_page.html";
+    let html = std::fs::read_to_string(html_file).unwrap();
+    
+    // Test that a streamed query prints exactly what the in-memory parser finds
+    let output = Command::new("cargo")
+        .args(["run", "--", "--file", html_file, "li"])
+        .output()
+        .expect("Failed to execute command");
+    
+    let expected = format!("{:?}", tagparser::Parser::new(html).parse_tags("li"));
+    assert_eq!(expected, str::from_utf8(&output.stdout).unwrap().trim());
+    
+    // Test that invalid names are still rejected
+    let output = Command::new("cargo")
+        .args(["run", "--", "--file", html_file, "a", "class name"])
+        .output()
+        .expect("Failed to execute command");
+    
+    let stdout = str::from_utf8(&output.stdout).unwrap();
+    assert_eq!("Error: invalid attribute name \"class name\"", stdout.trim());
+}
+
+#[test]
+fn test_cli_file_in_legacy_encoding() {
+    let html_file = "tests/test_data/cp1251_page.html";
+    
+    // Test both the streamed and the parsed query on a windows-1251 page
+    let output = Command::new("cargo")
+        .args(["run", "--", "--file", html_file, "a", "title", "--attr-values"])
+        .output()
+        .expect("Failed to execute command");
+    let stdout = str::from_utf8(&output.stdout).unwrap();
+    assert_eq!("[\"Последние новости\", \"О нас\"]", stdout.trim());
+    
+    let output = Command::new("cargo")
+        .args(["run", "--", "--file", html_file, "h1", "--content"])
+        .output()
+        .expect("Failed to execute command");
+    let stdout = str::from_utf8(&output.stdout).unwrap();
+    assert_eq!("[\"Главная страница\"]", stdout.trim());
+}
+
+#[test]
+fn test_cli_encoding_hint() {
+    let mut child = Command::new("cargo")
+        .args(["run", "--", "--file", "-", "p", "--content", "--encoding", "latin1"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("Failed to execute command");
+    
+    child.stdin.take().unwrap().write_all(b"<p>Caf\xe9 cr\xe8me</p>").unwrap();
+    let output = child.wait_with_output().expect("Failed to wait for command");
+    
+    let stdout = str::from_utf8(&output.stdout).unwrap();
+    assert_eq!("[\"Café crème\"]", stdout.trim());
+}
+
+#[test]
+fn test_cli_case_sensitive() {
+    let html = "<Item>First</Item><item>Second</item>";
+    
+    // Test that names ignore case unless --case-sensitive is given
+    let output = Command::new("cargo")
+        .args(["run", "--", html, "item", "--content"])
+        .output()
+        .expect("Failed to execute command");
+    let stdout = str::from_utf8(&output.stdout).unwrap();
+    assert_eq!("[\"First\", \"Second\"]", stdout.trim());
+    
+    let output = Command::new("cargo")
+        .args(["run", "--", html, "Item", "--content", "--case-sensitive"])
+        .output()
+        .expect("Failed to execute command");
+    let stdout = str::from_utf8(&output.stdout).unwrap();
+    assert_eq!("[\"First\"]", stdout.trim());
+}
+
+#[test]
+fn test_cli_attribute_match() {
+    let html = "<a href='https://a.example'>A</a><a href='http://b.example'>B</a><img src='/x.PNG'>";
+    
+    // Test an operator, an operator that ignores case, and values filtered by a test
+    let output = Command::new("cargo")
+        .args(["run", "--", html, "a", "href", "--match", "prefix", "https://"])
+        .output()
+        .expect("Failed to execute command");
+    let stdout = str::from_utf8(&output.stdout).unwrap();
+    assert_eq!("[\"<a href='https://a.example'>A</a>\"]", stdout.trim());
+    
+    let output = Command::new("cargo")
+        .args(["run", "--", html, "img", "src", "--match", "suffix", ".png", "--ignore-case"])
+        .output()
+        .expect("Failed to execute command");
+    let stdout = str::from_utf8(&output.stdout).unwrap();
+    assert_eq!("[\"<img src='/x.PNG'>\"]", stdout.trim());
+    
+    let output = Command::new("cargo")
+        .args(["run", "--", html, "a", "href", "--attr-values", "--match", "regex", "^http:"])
+        .output()
+        .expect("Failed to execute command");
+    let stdout = str::from_utf8(&output.stdout).unwrap();
+    assert_eq!("[\"http://b.example\"]", stdout.trim());
+    
+    // Test that an unknown operator is reported
+    let output = Command::new("cargo")
+        .args(["run", "--", html, "a", "href", "--match", "starts", "x"])
+        .output()
+        .expect("Failed to execute command");
+    let stdout = str::from_utf8(&output.stdout).unwrap();
+    assert!(stdout.starts_with("Error: unknown match operator \"starts\""));
+}
+
+#[test]
+fn test_cli_filter() {
+    let html = "<a rel='external' href='https://a.example'>A</a><a rel='external' target='_blank' href='https://b.example'>B</a><a href='/c'>C</a>";
+    
+    // Test a filter expression in place of the tag and attribute arguments
+    let output = Command::new("cargo")
+        .args(["run", "--", html, "--filter", "tag=a and @rel=external and not @target=_blank"])
+        .output()
+        .expect("Failed to execute command");
+    let stdout = str::from_utf8(&output.stdout).unwrap();
+    assert_eq!("[\"<a rel='external' href='https://a.example'>A</a>\"]", stdout.trim());
+    
+    let output = Command::new("cargo")
+        .args(["run", "--", html, "--filter", "@target or text=C"])
+        .output()
+        .expect("Failed to execute command");
+    let stdout = str::from_utf8(&output.stdout).unwrap();
+    assert_eq!(
+        "[\"<a rel='external' target='_blank' href='https://b.example'>B</a>\", \"<a href='/c'>C</a>\"]",
+        stdout.trim()
+    );
+    
+    // Test that a malformed expression is reported with its position
+    let output = Command::new("cargo")
+        .args(["run", "--", html, "--filter", "tag=a and"])
+        .output()
+        .expect("Failed to execute command");
+    let stdout = str::from_utf8(&output.stdout).unwrap();
+    assert_eq!("Error: invalid filter at position 9: expected a predicate", stdout.trim());
+}
+
+#[test]
+fn test_cli_table() {
+    let html = "<table id='prices'><tr><th>Item</th><th>Price</th></tr><tr><td>Pear, ripe</td><td>0.80</td></tr></table><table><tr><td>Other</td></tr></table>";
+    
+    // Test printing one table picked by a selector as CSV, and every table as JSON
+    let output = Command::new("cargo")
+        .args(["run", "--", html, "#prices", "--table", "csv"])
+        .output()
+        .expect("Failed to execute command");
+    let stdout = str::from_utf8(&output.stdout).unwrap();
+    assert_eq!("Item,Price\n\"Pear, ripe\",0.80", stdout.trim());
+    
+    let output = Command::new("cargo")
+        .args(["run", "--", html, "--table", "json"])
+        .output()
+        .expect("Failed to execute command");
+    let stdout = str::from_utf8(&output.stdout).unwrap();
+    assert_eq!(
+        "[{\"headers\":[\"Item\",\"Price\"],\"rows\":[[\"Pear, ripe\",\"0.80\"]]},{\"headers\":[],\"rows\":[[\"Other\"]]}]",
+        stdout.trim()
+    );
+    
+    // Test that an unknown format is reported
+    let output = Command::new("cargo")
+        .args(["run", "--", html, "--table", "xml"])
+        .output()
+        .expect("Failed to execute command");
+    let stdout = str::from_utf8(&output.stdout).unwrap();
+    assert!(stdout.starts_with("Error: unknown table format \"xml\""));
+}
@@ -0,0 +1,135 @@
+use tagparser::{validate, DiagnosticKind, Document, ParseDiagnostic, Parser, StreamParser};
+
+// Helper function to list the kinds of diagnostics found in some HTML
+fn kinds(html: &str) -> Vec<DiagnosticKind> {
+    validate(html.to_string()).iter().map(|diagnostic| diagnostic.kind).collect()
+}
+
+#[test]
+fn test_well_formed_html_has_no_diagnostics() {
+    let html = r#"<!DOCTYPE html>
+        <html><head><title>Page</title><meta charset="utf-8"></head>
+        <body><ul><li>One<li>Two</ul><p>Text<br>more<p>Again<img src="a.png"></body></html>"#;
+    
+    assert_eq!(Vec::<ParseDiagnostic>::new(), validate(html.to_string()));
+}
+
+#[test]
+fn test_misnested_tags() {
+    let document = Document::parse("<b><i>Text</b></i>");
+    let diagnostics = document.diagnostics();
+    
+    assert_eq!(2, diagnostics.len());
+    assert_eq!(DiagnosticKind::MisnestedTag, diagnostics[0].kind);
+    assert_eq!("<i> is closed by </b> before its own end tag", diagnostics[0].message);
+    assert_eq!("<i>", &document.source()[diagnostics[0].span.start..diagnostics[0].span.end]);
+    assert_eq!(DiagnosticKind::StrayEndTag, diagnostics[1].kind);
+    assert_eq!(14, diagnostics[1].span.start);
+    
+    // Test that the tree is repaired the way browsers repair it
+    let b = document.root().first_child().unwrap();
+    let i = b.first_child().unwrap();
+    assert_eq!(Some("i"), i.name());
+    assert_eq!("Text", i.text());
+    assert_eq!("<b><i>Text</b>", b.outer_html());
+}
+
+#[test]
+fn test_misnested_formatting_is_reopened() {
+    let html = "<p><b><i>x</b>y</i> z</p>";
+    let document = Document::parse(html);
+    
+    // The </i> closes the <i> reopened after </b>, so only the misnesting is reported
+    assert_eq!(vec![DiagnosticKind::MisnestedTag], kinds(html));
+    let p = document.root().first_child().unwrap();
+    let names: Vec<Option<&str>> = p.children().map(|node| node.name()).collect();
+    assert_eq!(vec![Some("b"), Some("i"), None], names);
+    let reopened = p.children().nth(1).unwrap();
+    assert_eq!(("y", "y</i>"), (reopened.text().as_str(), reopened.outer_html()));
+    assert_eq!(Some(14), reopened.start_tag_span().map(|span| span.start));
+    
+    // Test that the <i> is found as written, and the reopened copy isn't,
+    // by the parser and the stream parser alike
+    let html = "<b><i>x</b>y</i>";
+    let parser = Parser::new(html);
+    assert_eq!(vec!["<i>x"], parser.parse_tags("i"));
+    assert_eq!(vec!["x"], parser.extract_tag_content("i"));
+    let tags = parser.find_tags("i");
+    assert_eq!((1, "<i>x", 3, 7), (tags.len(), tags[0].outer_html.as_str(), tags[0].span.start, tags[0].span.end));
+    assert_eq!(vec!["<b><i>x</b>"], parser.parse_tags("b"));
+    let streamed: Vec<String> = StreamParser::new(html.as_bytes()).parse_tags("i").map(Result::unwrap).collect();
+    assert_eq!(vec!["<i>x"], streamed);
+    
+    // Test that the <i> also counts when its end tag is ignored, but not when there is none
+    assert_eq!(vec!["<i>x"], Parser::new("<b><i>x</b></i>y").parse_tags("i"));
+    assert!(Parser::new("<b><i>x</b>y").parse_tags("i").is_empty());
+    let streamed: Vec<String> = StreamParser::new("<b><i>x</b>y".as_bytes()).parse_tags("i").map(Result::unwrap).collect();
+    assert!(streamed.is_empty());
+    
+    // Test that a reopened <a> keeps its attributes, and formatting doesn't enter later table cells
+    let parser = Parser::new("<b><a href='/x'>1</b>2</a>");
+    assert_eq!(vec!["/x"], parser.extract_attribute_values("a", "href"));
+    let document = Document::parse("<table><tr><td><b>1</td><td>2</td></tr></table>");
+    assert_eq!(1, document.elements().filter(|node| node.name() == Some("b")).count());
+    
+    // Test that repeated misnesting keeps at most three reopened copies, reported once each
+    let html = "<p><b>x</p>".repeat(10);
+    let document = Document::parse(&html);
+    let last = document.elements().filter(|node| node.name() == Some("p")).last().unwrap();
+    assert_eq!(4, last.descendants().filter(|node| node.name() == Some("b")).count());
+    assert_eq!(vec![DiagnosticKind::MisnestedTag; 10], kinds(&html));
+}
+
+#[test]
+fn test_stray_end_tags() {
+    assert_eq!(vec![DiagnosticKind::StrayEndTag; 3], kinds("<p>One</span></p></br></>"));
+    
+    // An inline end tag can't close an element across a block element
+    let document = Document::parse("<span><div>Text</span></div>");
+    assert_eq!(vec![DiagnosticKind::UnclosedElement, DiagnosticKind::StrayEndTag], kinds(document.source()));
+    let div = document.root().first_child().unwrap().first_child().unwrap();
+    assert_eq!("<div>Text</span></div>", div.outer_html());
+}
+
+#[test]
+fn test_end_tags_respect_table_boundaries() {
+    let document = Document::parse("<p>Before<table><tr><td>Cell</p></td></tr></table>");
+    
+    // The </p> inside the cell doesn't close the paragraph outside the table
+    assert_eq!(vec![DiagnosticKind::StrayEndTag], kinds(document.source()));
+    let td = document.elements().find(|node| node.name() == Some("td")).unwrap();
+    assert_eq!("<td>Cell</p></td>", td.outer_html());
+}
+
+#[test]
+fn test_unclosed_elements_and_unterminated_markup() {
+    let diagnostics = validate("<div>\n  <span>Open\n  <a href='/x>Never closed".to_string());
+    
+    assert_eq!(
+        vec![
+            "1:1: <div> is never closed",
+            "2:3: <span> is never closed",
+            "3:3: attribute value is missing its closing quote; the tag is dropped",
+        ],
+        diagnostics.iter().map(|diagnostic| diagnostic.to_string()).collect::<Vec<_>>()
+    );
+    
+    assert_eq!(vec![DiagnosticKind::UnterminatedComment], kinds("<p>Text</p><!-- open"));
+    assert_eq!(vec![DiagnosticKind::UnterminatedTag], kinds("<p>Text</p><p class"));
+}
+
+#[test]
+fn test_tag_syntax_diagnostics() {
+    assert_eq!(vec![DiagnosticKind::DuplicateAttribute], kinds("<a href='/a' HREF='/b'>x</a>"));
+    assert_eq!(vec![DiagnosticKind::MissingWhitespace], kinds("<a href='/a'class='x'>x</a>"));
+    assert_eq!(vec![DiagnosticKind::SelfClosingNonVoid], kinds("<div/><br/>"));
+    assert_eq!(
+        "<div/> is read as an empty element here, but browsers ignore the slash on non-void elements",
+        validate("<div/>after".to_string())[0].message
+    );
+    assert_eq!(vec!["<div/>"], Parser::new("<div/>after").parse_tags("div"));
+    assert_eq!(
+        vec![DiagnosticKind::BogusComment, DiagnosticKind::BogusComment],
+        kinds("<?xml version='1.0'?><!ELEMENT p>")
+    );
+}
@@ -0,0 +1,171 @@
+use std::borrow::Cow;
+
+use tagparser::{Document, Node, NodeKind, Parser};
+
+#[test]
+fn test_document_structure() {
+    let document = Document::parse("<!DOCTYPE html><div id='main'><p>Hello <b>world</b></p><!-- note --></div>");
+    let root = document.root();
+    assert_eq!(NodeKind::Document, root.kind());
+    
+    let children: Vec<NodeKind> = root.children().map(|node| node.kind()).collect();
+    assert_eq!(vec![NodeKind::Doctype, NodeKind::Element], children);
+    assert_eq!(Some("html"), root.first_child().unwrap().as_doctype());
+    
+    let div = root.last_child().unwrap();
+    assert_eq!(Some("div"), div.name());
+    assert_eq!(Some("main"), div.attribute("id").as_deref());
+    assert_eq!(None, div.attribute("class"));
+    
+    let p = div.first_child().unwrap();
+    assert_eq!("Hello world", p.text());
+    assert_eq!("Hello <b>world</b>", p.inner_html());
+    assert_eq!("<p>Hello <b>world</b></p>", p.outer_html());
+    
+    let comment = p.next_sibling().unwrap();
+    assert_eq!(Some(" note "), comment.as_comment());
+    assert_eq!(Some(p), comment.prev_sibling());
+    assert_eq!(None, comment.next_sibling());
+}
+
+#[test]
+fn test_navigation_iterators() {
+    let document = Document::parse("<ul><li>One</li><li>Two</li><li>Three <em>!</em></li></ul>");
+    let em = document.elements().find(|node| node.name() == Some("em")).unwrap();
+    
+    let ancestors: Vec<&str> = em.ancestors().filter_map(|node| node.name()).collect();
+    assert_eq!(vec!["li", "ul"], ancestors);
+    assert_eq!(Some(document.root()), em.ancestors().last());
+    
+    let ul = document.root().first_child().unwrap();
+    let names: Vec<&str> = ul.descendants().filter_map(|node| node.name()).collect();
+    assert_eq!(vec!["li", "li", "li", "em"], names);
+    
+    let second = ul.children().nth(1).unwrap();
+    let following: Vec<String> = second.next_siblings().map(|node| node.text()).collect();
+    assert_eq!(vec!["Three !"], following);
+    let preceding: Vec<String> = second.prev_siblings().map(|node| node.text()).collect();
+    assert_eq!(vec!["One"], preceding);
+}
+
+#[test]
+fn test_unclosed_elements() {
+    let document = Document::parse("<div><p>Unclosed<span>inner</span></div>after");
+    let p = document.elements().find(|node| node.name() == Some("p")).unwrap();
+    
+    // The unclosed paragraph ends where its parent is closed
+    assert_eq!(None, p.end_tag_span());
+    assert_eq!("Unclosed<span>inner</span>", p.inner_html());
+    
+    let div = p.parent().unwrap();
+    assert!(div.end_tag_span().is_some());
+    assert_eq!(Some("after"), div.next_sibling().unwrap().as_text().as_deref());
+}
+
+// Helper function to render an element tree as nested names, e.g. "ul(li li)"
+fn shape(node: Node) -> String {
+    let children: Vec<String> = node.children().filter(|child| child.is_element()).map(shape).collect();
+    let name = node.name().unwrap_or("#document");
+    if children.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}({})", name, children.join(" "))
+    }
+}
+
+#[test]
+fn test_void_elements() {
+    let document = Document::parse("<p>One<br>Two<img src='a.png'><input disabled>Three</p><hr>");
+    let p = document.root().first_child().unwrap();
+    
+    // Void elements are never opened, so what follows them is their sibling
+    assert_eq!("#document(p(br img input) hr)", shape(document.root()));
+    assert_eq!("OneTwoThree", p.text());
+    let br = p.children().find(|node| node.name() == Some("br")).unwrap();
+    assert!(br.is_void());
+    assert!(!br.is_self_closing());
+    assert_eq!("<br>", br.outer_html());
+    assert_eq!(None, br.end_tag_span());
+}
+
+#[test]
+fn test_implied_end_tags() {
+    // Test list items, definitions and options
+    let document = Document::parse("<ul><li>One<li>Two<ul><li>Nested</ul><li>Three</ul>");
+    assert_eq!("#document(ul(li li(ul(li)) li))", shape(document.root()));
+    
+    let document = Document::parse("<dl><dt>Term<dd>Definition<dt>Other</dl><select><option>A<option>B</select>");
+    assert_eq!("#document(dl(dt dd dt) select(option option))", shape(document.root()));
+    
+    // Test paragraphs closed by block elements, and headings that don't nest
+    let document = Document::parse("<p>Intro<div>Block</div><p>One<p>Two<h1>Title<h2>Sub</h2>");
+    assert_eq!("#document(p div p p h1 h2)", shape(document.root()));
+    
+    // Test that inline elements don't close a paragraph
+    let document = Document::parse("<p>Some <b>bold</b> and <a href='#'>a link</a>");
+    assert_eq!("#document(p(b a))", shape(document.root()));
+    
+    // Test a head that is never closed
+    let document = Document::parse("<html><head><title>T</title><body><p>Text</html>");
+    assert_eq!("#document(html(head(title) body(p)))", shape(document.root()));
+}
+
+#[test]
+fn test_implied_table_end_tags() {
+    let document = Document::parse(
+        "<table><thead><tr><th>A<th>B<tbody><tr><td>1<td>2<tr><td>3<td><table><tr><td>x</table></table>",
+    );
+    
+    assert_eq!(
+        "#document(table(thead(tr(th th)) tbody(tr(td td) tr(td td(table(tr(td)))))))",
+        shape(document.root())
+    );
+    let cells: Vec<String> = document
+        .elements()
+        .filter(|node| node.name() == Some("td"))
+        .map(|node| node.inner_html().to_string())
+        .collect();
+    assert_eq!(vec!["1", "2", "3", "<table><tr><td>x</table>", "x"], cells);
+}
+
+#[test]
+fn test_stray_end_tags_are_ignored() {
+    let document = Document::parse("<p>One</span></p>");
+    let p = document.root().first_child().unwrap();
+    
+    assert_eq!("One", p.text());
+    assert_eq!("<p>One</span></p>", p.outer_html());
+}
+
+#[test]
+fn test_parser_document() {
+    let parser = Parser::new("<a href='https://github.com'>GitHub</a>".to_string());
+    let document = parser.document();
+    
+    let link = document.elements().next().unwrap();
+    assert_eq!(Some("https://github.com"), link.attribute("href").as_deref());
+    assert_eq!(vec![("href", Cow::from("https://github.com"))], link.attributes().collect::<Vec<_>>());
+    assert_eq!(document.source(), link.outer_html());
+}
+
+#[test]
+fn test_line_and_column_positions() {
+    let document = Document::parse("<ul>\n  <li>Caf\u{e9}</li><li>Two\n</li>\r\n</ul>");
+    let items: Vec<_> = document.elements().filter(|node| node.name() == Some("li")).collect();
+    
+    let first = items[0].start_tag_location().unwrap();
+    assert_eq!((2, 3, 7), (first.start.line, first.start.column, first.start.offset));
+    assert_eq!((2, 7), (first.end.line, first.end.column));
+    
+    // Columns count characters, so the two-byte 'é' moves the end tag by one
+    let end_tag = items[0].end_tag_location().unwrap();
+    assert_eq!((2, 11), (end_tag.start.line, end_tag.start.column));
+    assert_eq!(items[0].end_tag_span(), Some(end_tag.span()));
+    
+    let second = items[1].location();
+    assert_eq!("2:16", second.start.to_string());
+    assert_eq!("3:6", second.end.to_string());
+    
+    let list = items[0].parent().unwrap();
+    assert_eq!("4:1", list.end_tag_location().unwrap().start.to_string());
+}
@@ -0,0 +1,130 @@
+use std::borrow::Cow;
+use std::fs;
+
+use tagparser::encoding::{decode, sniff};
+use tagparser::{EncodingSource, Parser, StreamParser};
+
+// Helper function to get the name and source of the encoding picked for `bytes`
+fn detect(bytes: &[u8], hint: Option<&str>) -> (&'static str, EncodingSource) {
+    let detected = sniff(bytes, hint);
+    (detected.name, detected.source)
+}
+
+#[test]
+fn test_sniff_order() {
+    // Test that a byte order mark wins over a hint and a declaration
+    assert_eq!(("UTF-8", EncodingSource::ByteOrderMark), detect(b"\xEF\xBB\xBF<meta charset=latin1>", Some("sjis")));
+    assert_eq!(("UTF-16LE", EncodingSource::ByteOrderMark), detect(b"\xFF\xFE<\0p\0>\0", None));
+    assert_eq!(("UTF-16BE", EncodingSource::ByteOrderMark), detect(b"\xFE\xFF\0<\0p\0>", None));
+
+    // Test that a hint wins over a declaration, unless it isn't a known label
+    assert_eq!(("Shift_JIS", EncodingSource::Hint), detect(b"<meta charset=latin1>", Some("sjis")));
+    assert_eq!(("windows-1252", EncodingSource::Meta), detect(b"<meta charset=latin1>", Some("no-such-encoding")));
+
+    // Test the fallback for pages that declare nothing
+    assert_eq!(("UTF-8", EncodingSource::Default), detect("<p>Café</p>".as_bytes(), None));
+    assert_eq!(("windows-1252", EncodingSource::Default), detect(b"<p>Caf\xE9</p>", None));
+}
+
+#[test]
+fn test_sniff_meta_declarations() {
+    // Test the forms a declaration can take
+    assert_eq!("windows-1251", detect(b"<META CHARSET=\"Windows-1251\">", None).0);
+    assert_eq!("EUC-JP", detect(b"<meta charset = 'euc-jp' />", None).0);
+    assert_eq!(
+        "KOI8-R",
+        detect(b"<meta http-equiv=\"Content-Type\" content=\"text/html; charset=koi8-r\">", None).0
+    );
+    assert_eq!("GBK", detect(b"<meta content='text/html;charset=\"gb2312\"' http-equiv=content-type>", None).0);
+
+    // Test that `content` only counts together with `http-equiv`
+    assert_eq!(EncodingSource::Default, detect(b"<meta content='text/html; charset=koi8-r'>", None).1);
+    // Test that the first of a repeated attribute is the one used
+    assert_eq!("ISO-8859-2", detect(b"<meta charset=latin2 charset=latin1>", None).0);
+    // Test that UTF-16 declared in the page itself means UTF-8
+    assert_eq!(("UTF-8", EncodingSource::Meta), detect(b"<meta charset=utf-16le>", None));
+}
+
+#[test]
+fn test_sniff_skips_other_markup() {
+    // Test that declarations inside comments and other tags' attributes are ignored
+    let html = b"<!-- <meta charset=koi8-r> --><div title='<meta charset=koi8-r>'></div><meta charset=sjis>";
+    assert_eq!("Shift_JIS", detect(html, None).0);
+    assert_eq!("Shift_JIS", detect(b"<!--><meta charset=sjis>", None).0);
+    assert_eq!("Shift_JIS", detect(b"<?xml version='1.0'?><!DOCTYPE html><meta charset=sjis>", None).0);
+
+    // Test that only the first 1024 bytes are searched
+    let mut late = vec![b' '; 1024];
+    late.extend_from_slice(b"<meta charset=sjis>");
+    assert_eq!(("UTF-8", EncodingSource::Default), detect(&late, None));
+
+    // Test that a declaration cut off by the end of the input is ignored
+    assert_eq!(EncodingSource::Default, detect(b"<meta charset='sjis", None).1);
+}
+
+#[test]
+fn test_decode() {
+    // Test that UTF-8 input is borrowed, and a byte order mark is left out
+    let html = "<p>Crab \u{1F980}</p>".as_bytes();
+    assert!(matches!(decode(html, None).0, Cow::Borrowed(_)));
+    assert_eq!("<p>x</p>", decode(b"\xEF\xBB\xBF<p>x</p>", None).0);
+    assert_eq!("<p>x</p>", decode(b"\xFF\xFE<\0p\0>\0x\0<\0/\0p\0>\0", None).0);
+
+    // Test that malformed bytes in a declared encoding are replaced
+    assert_eq!("<meta charset=utf-8><p>\u{FFFD}</p>", decode(b"<meta charset=utf-8><p>\xFF</p>", None).0);
+
+    // Test transcoding from legacy encodings
+    assert_eq!("<p>Café</p>", decode(b"<p>Caf\xE9</p>", None).0);
+    assert_eq!("<p>日本</p>", decode(b"<p>\x93\xFA\x96\x7B</p>", Some("Shift_JIS")).0);
+}
+
+#[test]
+fn test_parser_from_bytes() {
+    let bytes = fs::read("tests/test_data/cp1251_page.html").unwrap();
+    let parser = Parser::from_bytes(&bytes, None);
+
+    let encoding = parser.encoding().unwrap();
+    assert_eq!(("windows-1251", EncodingSource::Meta), (encoding.name, encoding.source));
+    assert_eq!(vec!["Главная страница"], parser.extract_tag_content("h1"));
+    assert_eq!(vec!["Последние новости", "О нас"], parser.extract_attribute_values("a", "title"));
+
+    // Test that parsers made from text don't report an encoding
+    assert_eq!(None, Parser::new("<p>Text</p>").encoding());
+}
+
+#[test]
+fn test_stream_transcodes() {
+    let bytes = fs::read("tests/test_data/cp1251_page.html").unwrap();
+    let parser = Parser::from_bytes(&bytes, None);
+
+    for size in [1, 7, 1 << 20] {
+        let mut matches = StreamParser::new(Trickle { data: &bytes, size }).parse_tags("a");
+        assert_eq!(None, matches.encoding());
+        let links: Vec<String> = matches.by_ref().collect::<Result<_, _>>().unwrap();
+        assert_eq!(parser.parse_tags("a"), links);
+        assert_eq!("windows-1251", matches.encoding().unwrap().name);
+    }
+
+    // Test that a hint is used for input that declares nothing
+    let titles = StreamParser::new(&b"<a title='\xC7\xE0\xEA\xE0\xE7'>x</a>"[..])
+        .encoding_hint("cp1251")
+        .extract_attribute_values("a", "title")
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(vec!["Заказ"], titles);
+}
+
+// Reader that hands out at most `size` bytes per read
+struct Trickle<'a> {
+    data: &'a [u8],
+    size: usize,
+}
+
+impl std::io::Read for Trickle<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let len = self.size.min(buf.len()).min(self.data.len());
+        buf[..len].copy_from_slice(&self.data[..len]);
+        self.data = &self.data[len..];
+        Ok(len)
+    }
+}
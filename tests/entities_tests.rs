@@ -0,0 +1,88 @@
+use std::borrow::Cow;
+
+use tagparser::entities::{decode_attribute, decode_text};
+use tagparser::{extract_attribute_values, find_tags, Document, Parser};
+
+#[test]
+fn test_decode_named_references() {
+    assert_eq!("a & b < c > d \" e ' f", decode_text("a &amp; b &lt; c &gt; d &quot; e &apos; f"));
+    assert_eq!("Café naïve ©", decode_text("Caf&eacute; na&iuml;ve &copy;"));
+    // Names are case-sensitive and some stand for two characters
+    assert_eq!("É é", decode_text("&Eacute; &eacute;"));
+    assert_eq!("\u{2242}\u{338}", decode_text("&NotEqualTilde;"));
+    assert_eq!("\u{2233}", decode_text("&CounterClockwiseContourIntegral;"));
+}
+
+#[test]
+fn test_decode_numeric_references() {
+    assert_eq!("' ' ' —", decode_text("&#39; &#x27; &#X27; &#8212;"));
+    assert_eq!("🦀", decode_text("&#x1F980;"));
+    // A missing semicolon is tolerated
+    assert_eq!("AB", decode_text("&#65&#x42"));
+    // C1 controls map to their Windows-1252 characters
+    assert_eq!("€ ™ \u{81}", decode_text("&#128; &#x99; &#129;"));
+    // Unusable code points become U+FFFD
+    assert_eq!("\u{FFFD} \u{FFFD} \u{FFFD}", decode_text("&#0; &#xD800; &#x110000;"));
+    assert_eq!("\u{FFFD}", decode_text("&#99999999999999999999;"));
+    // No digits means no reference
+    assert_eq!("&#; &#x; &#q", decode_text("&#; &#x; &#q"));
+}
+
+#[test]
+fn test_decode_legacy_references_without_semicolon() {
+    assert_eq!("© 2024 & more", decode_text("&copy 2024 &amp more"));
+    // The longest legacy name that matches wins
+    assert_eq!("¬it;", decode_text("&notit;"));
+    assert_eq!("∉", decode_text("&notin;"));
+    // Names that aren't in the table stay as they are
+    assert_eq!("&bogus; & &; &123", decode_text("&bogus; & &; &123"));
+}
+
+#[test]
+fn test_decode_attribute_values() {
+    assert_eq!("/a?x=1&y=2", decode_attribute("/a?x=1&amp;y=2"));
+    // Legacy names followed by a letter, digit or '=' are kept in attributes
+    assert_eq!("/a?x=1&copy=2", decode_attribute("/a?x=1&copy=2"));
+    assert_eq!("&notit", decode_attribute("&notit"));
+    assert_eq!("© 2024", decode_attribute("&copy 2024"));
+    assert_eq!("©=", decode_attribute("&copy;="));
+}
+
+#[test]
+fn test_decode_borrows_when_unchanged() {
+    assert!(matches!(decode_text("plain text"), Cow::Borrowed(_)));
+    assert!(matches!(decode_text("&bogus; & stays"), Cow::Borrowed(_)));
+    assert!(matches!(decode_text("&amp;"), Cow::Owned(_)));
+}
+
+#[test]
+fn test_node_text_and_attributes_are_decoded() {
+    let document = Document::parse("<a href='/x?a=1&amp;b=2' title='&quot;Hi&quot;'>Fish &amp; Chips</a>");
+    let link = document.elements().next().unwrap();
+
+    assert_eq!(Some("/x?a=1&b=2"), link.attribute("href").as_deref());
+    assert_eq!(Some("/x?a=1&amp;b=2"), link.raw_attribute("href"));
+    assert_eq!("Fish & Chips", link.text());
+    assert_eq!("Fish &amp; Chips", link.raw_text());
+    assert_eq!("Fish &amp; Chips", link.inner_html());
+    assert_eq!(1, document.select(r#"a[title='"Hi"']"#).unwrap().len());
+}
+
+#[test]
+fn test_parser_decoding_and_raw_mode() {
+    let html = "<img src='a.png' alt='Tom &amp; Jerry'/><p>&lt;b&gt;</p>".to_string();
+
+    assert_eq!(vec!["Tom & Jerry"], extract_attribute_values(html.clone(), "img".to_string(), "alt"));
+
+    let tags = find_tags(html.clone(), "p".to_string());
+    assert_eq!("<b>", tags[0].text);
+    assert_eq!("&lt;b&gt;", tags[0].inner_html);
+
+    let raw = Parser::new(html).decode_entities(false);
+    assert_eq!(vec!["Tom &amp; Jerry"], raw.extract_attribute_values("img", "alt"));
+    assert_eq!(1, raw.parse_tags_with_attr("img", "alt", Some("Tom &amp; Jerry")).len());
+    assert_eq!("&lt;b&gt;", raw.find_tags("p")[0].text);
+
+    // Test that XPath attribute and text results are raw too
+    assert_eq!(vec!["Tom &amp; Jerry", "&lt;b&gt;"], raw.xpath("//img/@alt | //p/text()").unwrap());
+}
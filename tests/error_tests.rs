@@ -0,0 +1,87 @@
+use std::error::Error as _;
+
+use tagparser::{
+    extract_attribute_values, parse_tags, parse_tags_with_attr, try_extract_attribute_values,
+    try_extract_tag_content, try_parse_tags, try_parse_tags_with_attr, Error, Parser, Selector, XPath,
+};
+
+#[test]
+fn test_names_with_regex_metacharacters_are_literal() {
+    let html = "<a class='a.b' href='/x?y=(1)'>Dot</a><a class='axb' href='/x?y=1'>X</a>".to_string();
+    
+    // Test that metacharacters neither panic nor match anything but themselves
+    assert_eq!(Vec::<String>::new(), parse_tags(html.clone(), "a(".to_string()));
+    assert_eq!(Vec::<String>::new(), parse_tags(html.clone(), ".*".to_string()));
+    assert_eq!(Vec::<String>::new(), extract_attribute_values(html.clone(), "a".to_string(), "cl[a]ss"));
+    
+    let dotted = parse_tags_with_attr(html.clone(), "a".to_string(), "class", Some("a.b"));
+    assert_eq!(vec!["<a class='a.b' href='/x?y=(1)'>Dot</a>"], dotted);
+    let grouped = parse_tags_with_attr(html, "a".to_string(), "href", Some("/x?y=(1)"));
+    assert_eq!(vec!["<a class='a.b' href='/x?y=(1)'>Dot</a>"], grouped);
+}
+
+#[test]
+fn test_try_variants_succeed() {
+    let html = "<a class='nav' href='/one'>One</a><a href='/two'>Two</a>".to_string();
+    
+    assert_eq!(2, try_parse_tags(html.clone(), "a".to_string()).unwrap().len());
+    assert_eq!(
+        vec!["<a class='nav' href='/one'>One</a>"],
+        try_parse_tags_with_attr(html.clone(), "a".to_string(), "class", Some("nav")).unwrap()
+    );
+    assert_eq!(vec!["One", "Two"], try_extract_tag_content(html.clone(), "a".to_string()).unwrap());
+    assert_eq!(vec!["/one", "/two"], try_extract_attribute_values(html.clone(), "a".to_string(), "href").unwrap());
+    
+    // Unusual but possible names are accepted and simply find nothing
+    assert_eq!(Ok(Vec::new()), try_parse_tags(html, "my-element.v2(".to_string()));
+}
+
+#[test]
+fn test_try_variants_reject_invalid_names() {
+    let html = "<a href='/'>Home</a>".to_string();
+    
+    for tag in ["", "1a", "-a", "a b", "a/b", "a>", "<a>"] {
+        assert_eq!(Err(Error::InvalidTagName(tag.to_string())), try_parse_tags(html.clone(), tag.to_string()));
+        assert_eq!(Err(Error::InvalidTagName(tag.to_string())), try_extract_tag_content(html.clone(), tag.to_string()));
+    }
+    for attr in ["", "hr ef", "href=", "a/b", "a>"] {
+        assert_eq!(
+            Err(Error::InvalidAttributeName(attr.to_string())),
+            try_extract_attribute_values(html.clone(), "a".to_string(), attr)
+        );
+        assert_eq!(
+            Err(Error::InvalidAttributeName(attr.to_string())),
+            try_parse_tags_with_attr(html.clone(), "a".to_string(), attr, Some("/"))
+        );
+    }
+    
+    // The tag name is checked before the attribute name
+    assert_eq!(
+        Err(Error::InvalidTagName("".to_string())),
+        try_parse_tags_with_attr(html, "".to_string(), "", None)
+    );
+}
+
+#[test]
+fn test_parser_try_methods() {
+    let parser = Parser::new("<p>One</p><p>Two</p>".to_string());
+    
+    assert_eq!(Ok(vec!["One", "Two"]), parser.try_extract_tag_content("p"));
+    assert!(parser.try_parse_tags(" p").is_err());
+}
+
+#[test]
+fn test_error_display_and_source() {
+    assert_eq!("invalid tag name \"a b\"", Error::InvalidTagName("a b".to_string()).to_string());
+    assert_eq!("invalid attribute name \"\"", Error::InvalidAttributeName(String::new()).to_string());
+    assert!(Error::InvalidTagName(String::new()).source().is_none());
+    
+    // Selector and XPath errors convert into the crate error
+    let selector_error = Error::from(Selector::parse("a[").unwrap_err());
+    assert!(matches!(selector_error, Error::Selector(_)));
+    assert!(selector_error.source().is_some());
+    assert!(selector_error.to_string().starts_with("invalid selector"));
+    
+    let xpath_error: Error = XPath::parse("//a[").unwrap_err().into();
+    assert!(matches!(xpath_error, Error::XPath(_)));
+}
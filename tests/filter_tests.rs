@@ -0,0 +1,100 @@
+use tagparser::{filter, AttrMatch, Document, Error, Filter, FilterError, Parser};
+
+const PAGE: &str = r#"
+<nav>
+    <a class="nav home" href="/">Home</a>
+    <a class="nav" rel="external" href="https://rust-lang.org">Rust</a>
+    <a class="nav" rel="external" target="_blank" href="https://crates.io">Crates</a>
+</nav>
+<div><p>Buy <a href="/shop" rel="EXTERNAL">the book</a></p></div>
+"#;
+
+// Helper function to get the text of the elements a filter expression matches
+fn texts(expression: &str) -> Vec<String> {
+    let parser = Parser::new(PAGE);
+    let filter = Filter::parse(expression).unwrap();
+    parser.filter_tags(&filter).into_iter().map(|tag| tag.text).collect()
+}
+
+#[test]
+fn test_filter_in_code() {
+    let parser = Parser::new(PAGE);
+
+    // Test the example from the request: class=nav and rel=external but not target=_blank
+    let filter = Filter::tag("a")
+        .and(Filter::attr("class", AttrMatch::Word("nav".to_string())))
+        .and(Filter::attr("rel", AttrMatch::Equals("external".to_string())))
+        .and(!Filter::attr("target", AttrMatch::Equals("_blank".to_string())));
+    assert_eq!(vec![r#"<a class="nav" rel="external" href="https://rust-lang.org">Rust</a>"#], parser.filter(&filter));
+
+    // Test or, text and depth filters
+    let either = Filter::has_attr("target").or(Filter::text(AttrMatch::Prefix("Home".to_string())));
+    assert_eq!(2, parser.filter(&either).len());
+    let deep = Filter::tag("a").and(Filter::depth(2..));
+    assert_eq!(vec![r#"<a href="/shop" rel="EXTERNAL">the book</a>"#], parser.filter(&deep));
+    assert_eq!(2, parser.filter(&Filter::depth(..1)).len());
+    assert!(parser.filter(&Filter::depth(..0)).is_empty());
+
+    // Test that double negation cancels out, and that an empty `and` matches everything
+    let twice = !!Filter::tag("p");
+    assert!(matches!(twice, Filter::Tag(_)));
+    assert_eq!(7, parser.filter(&Filter::And(Vec::new())).len());
+    assert!(parser.filter(&Filter::Or(Vec::new())).is_empty());
+
+    // Test matching a single node
+    let document = Document::parse("<p class='x'>Hi</p>");
+    let p = document.root().first_child().unwrap();
+    assert!(Filter::parse("@class=x and text=Hi").unwrap().matches(&p));
+}
+
+#[test]
+fn test_filter_expressions() {
+    assert_eq!(vec!["Rust"], texts("tag=a and @class~=nav and @rel=external and not @target=_blank"));
+    assert_eq!(vec!["Home", "Rust", "Crates"], texts("tag=a and depth=1"));
+    assert_eq!(vec!["Rust", "Crates", "the book"], texts("tag=a and @rel=external i"));
+    assert_eq!(vec!["Home", "the book"], texts("tag=A and not @href^='https://'"));
+    assert_eq!(vec!["Rust", "Crates"], texts("TAG = a AND (@href=~'\\.(org|io)$') AND depth != 0"));
+    assert_eq!(vec!["Buy the book", "the book"], texts("(tag=p or tag=a) and text*=book"));
+    assert_eq!(vec!["Crates"], texts("@target or text$=\"Crates\""));
+
+    // Test that `and` binds tighter than `or`
+    assert_eq!(vec!["Home", "the book"], texts("tag=p and @id or tag=a and not @rel=external"));
+}
+
+#[test]
+fn test_filter_expression_errors() {
+    // Helper closure to get the message and position of an error
+    let error = |expression: &str| {
+        let FilterError { message, position } = Filter::parse(expression).unwrap_err();
+        (message, position)
+    };
+
+    assert_eq!(("expected a predicate".to_string(), 9), error("tag=a and"));
+    assert_eq!(("unknown predicate 'name'".to_string(), 0), error("name=a"));
+    assert_eq!(("expected ')'".to_string(), 6), error("(tag=a"));
+    assert_eq!(("unterminated string".to_string(), 7), error("@title='open"));
+    assert_eq!(("expected a number".to_string(), 6), error("depth>x"));
+    assert_eq!(("depth is too large".to_string(), 6), error(&format!("depth>{}", usize::MAX)));
+    assert!(Filter::parse(&format!("depth>={}", usize::MAX)).is_ok());
+    assert_eq!(("unexpected character ')'".to_string(), 5), error("tag=a)"));
+    assert_eq!(5, error("@href=~'('").1);
+
+    // Test that the free function reports errors, and that they convert into `Error`
+    let failed = filter(PAGE.to_string(), "text").unwrap_err();
+    assert_eq!("invalid filter at position 4: expected an operator", failed.to_string());
+    assert!(matches!(Error::from(failed), Error::Filter(_)));
+}
+
+#[test]
+fn test_filter_follows_parser_settings() {
+    let html = "<Item Kind='a&amp;b'>One</Item><item kind='x'>Two</item>";
+
+    let parser = Parser::new(html);
+    assert_eq!(2, parser.filter(&Filter::parse("tag=item").unwrap()).len());
+    assert_eq!(1, parser.filter(&Filter::parse("@kind='a&b'").unwrap()).len());
+
+    let exact = Parser::new(html).case_sensitive(true).decode_entities(false);
+    assert_eq!(vec!["<item kind='x'>Two</item>"], exact.filter(&Filter::parse("tag=item").unwrap()));
+    assert_eq!(vec!["<item kind='x'>Two</item>"], exact.filter(&Filter::parse("@kind").unwrap()));
+    assert_eq!(1, exact.filter(&Filter::parse("@Kind='a&amp;b'").unwrap()).len());
+}
@@ -0,0 +1,186 @@
+use tagparser::{extract_forms, ControlKind, Document, Form, Parser};
+
+// Helper function to get the only form of a page
+fn only_form(html: &str) -> Form {
+    let mut forms = Parser::new(html).forms();
+    assert_eq!(1, forms.len());
+    forms.remove(0)
+}
+
+// Helper function to get the name and value of each control
+fn values(form: &Form) -> Vec<(&str, &str)> {
+    form.controls.iter().map(|control| (control.name.as_str(), control.value.as_str())).collect()
+}
+
+#[test]
+fn test_form_attributes() {
+    let form = only_form("<form id='f' name='signup' action='/join?a=1&amp;b=2' method=' PoSt ' enctype='Multipart/Form-Data'></form>");
+    assert_eq!(Some("f".to_string()), form.id);
+    assert_eq!(Some("signup".to_string()), form.name);
+    assert_eq!(Some("/join?a=1&b=2".to_string()), form.action);
+    assert_eq!(("post", "multipart/form-data"), (form.method.as_str(), form.enctype.as_str()));
+
+    // Test the defaults for missing and unknown values
+    let form = only_form("<form method='put' enctype='application/json'></form>");
+    assert_eq!(None, form.action);
+    assert_eq!(("get", "application/x-www-form-urlencoded"), (form.method.as_str(), form.enctype.as_str()));
+    assert!(form.controls.is_empty());
+}
+
+#[test]
+fn test_control_ownership() {
+    let html = "
+        <input name='before' form='b'>
+        <form id='a'><input name='one'><input name='moved' form='b'><button name='go'>Go</button></form>
+        <form id='b'><textarea name='two'></textarea></form>
+        <select name='after' form='a'></select>
+        <input name='orphan'>";
+    let forms = Parser::new(html).forms();
+
+    // Test that the form attribute moves controls in and out of forms, in document order
+    assert_eq!(vec!["one", "go", "after"], forms[0].controls.iter().map(|c| c.name.as_str()).collect::<Vec<_>>());
+    assert_eq!(vec!["before", "moved", "two"], forms[1].controls.iter().map(|c| c.name.as_str()).collect::<Vec<_>>());
+    assert_eq!(
+        vec![ControlKind::Input, ControlKind::Button, ControlKind::Select],
+        forms[0].controls.iter().map(|c| c.kind).collect::<Vec<_>>()
+    );
+
+    // Test picking forms with a selector, and building one from a node
+    assert_eq!(vec![forms[1].clone()], Parser::new(html).select_forms("form#b").unwrap());
+    assert!(Parser::new(html).select_forms("input").unwrap().is_empty());
+    let document = Document::parse(html);
+    let first = document.elements().find(|node| node.name() == Some("form")).unwrap();
+    assert_eq!(forms[0], Form::from(first));
+}
+
+#[test]
+fn test_inputs_and_buttons() {
+    let form = only_form(
+        "<form>
+            <input name='plain'><input type='EMAIL' name='mail' value='a@b.c'><input type='fancy' name='odd'>
+            <input type='checkbox' name='opt'><input type='checkbox' name='agree' value='yes' checked>
+            <input type='radio' name='size' value='s' checked><input type='radio' name='size' value='m' checked>
+            <button name='b1'>One</button><button type='RESET' name='b2' value='r'></button>
+        </form>",
+    );
+
+    let types: Vec<&str> = form.controls.iter().map(|control| control.control_type.as_str()).collect();
+    assert_eq!(vec!["text", "email", "text", "checkbox", "checkbox", "radio", "radio", "submit", "reset"], types);
+    assert_eq!(
+        vec![
+            ("plain", ""), ("mail", "a@b.c"), ("odd", ""), ("opt", "on"), ("agree", "yes"),
+            ("size", "s"), ("size", "m"), ("b1", ""), ("b2", "r")
+        ],
+        values(&form)
+    );
+
+    // Test that only the last checked radio button of a group stays checked
+    let checked: Vec<bool> = form.controls.iter().map(|control| control.checked).collect();
+    assert_eq!(vec![false, false, false, false, true, false, true, false, false], checked);
+}
+
+#[test]
+fn test_select_options() {
+    let form = only_form(
+        "<form>
+            <select name='first'><option disabled>Pick</option><option>  Red\n apple </option><option value='g'>Green</option></select>
+            <select name='last'><option selected>A</option><option selected>B</option></select>
+            <select name='list' size='3'><option>A</option><option>B</option></select>
+            <select name='many' multiple><option selected>A</option><option>B</option><option selected value='c'>C</option></select>
+            <select name='grouped'><optgroup label='Old' disabled><option selected>X</option></optgroup><optgroup><option>Y</option></optgroup></select>
+        </form>",
+    );
+
+    // Test that a single-choice select without a selected option picks the first enabled one
+    let first = &form.controls[0];
+    assert_eq!(("select-one", "Red apple"), (first.control_type.as_str(), first.value.as_str()));
+    assert_eq!(vec!["Pick", "Red apple", "g"], first.options.iter().map(|o| o.value.as_str()).collect::<Vec<_>>());
+    assert_eq!(vec![false, true, false], first.options.iter().map(|o| o.selected).collect::<Vec<_>>());
+    assert_eq!("Green", first.options[2].text);
+
+    // Test that the last selected option wins, and list boxes may have none
+    assert_eq!(vec![false, true], form.controls[1].options.iter().map(|o| o.selected).collect::<Vec<_>>());
+    assert_eq!("", form.controls[2].value);
+    assert!(form.controls[2].options.iter().all(|option| !option.selected));
+
+    // Test multiple selection and options disabled by their group
+    assert_eq!(("select-multiple", "A"), (form.controls[3].control_type.as_str(), form.controls[3].value.as_str()));
+    assert!(form.controls[4].options[0].disabled && form.controls[4].options[0].selected);
+    assert_eq!("first=Red+apple&last=B&many=A&many=c", form.to_urlencoded());
+}
+
+#[test]
+fn test_textarea_and_decoding() {
+    let html = "<form><textarea name='t'>\nFish &amp; chips\n</textarea><input name='i' value='&lt;b&gt;'></form>";
+    let form = only_form(html);
+
+    // Test that the first line break is dropped and references decoded, unless decoding is off
+    assert_eq!(vec![("t", "Fish & chips\n"), ("i", "<b>")], values(&form));
+    let raw = Parser::new(html).decode_entities(false).forms().remove(0);
+    assert_eq!(vec![("t", "Fish &amp; chips\n"), ("i", "&lt;b&gt;")], values(&raw));
+}
+
+#[test]
+fn test_disabled_controls() {
+    let form = only_form(
+        "<form>
+            <input name='a' disabled>
+            <fieldset disabled>
+                <legend><input name='b'></legend>
+                <input name='c'>
+                <legend><input name='d'></legend>
+            </fieldset>
+            <fieldset><input name='e'></fieldset>
+        </form>",
+    );
+
+    // Test that a disabled fieldset disables all but the controls in its first legend
+    let disabled: Vec<bool> = form.controls.iter().map(|control| control.disabled).collect();
+    assert_eq!(vec![true, false, true, true, false], disabled);
+    assert_eq!("b=&e=", form.to_urlencoded());
+}
+
+#[test]
+fn test_labels() {
+    let form = only_form(
+        "<label for='user'>User <b>name</b></label>
+        <form>
+            <input id='user' name='user'>
+            <label>Country <select name='country'><option>France</option></select> (required)</label>
+            <label for='other'><input name='stray'></label>
+            <label><input type='hidden' name='token'>Hidden</label>
+            <input id='none' name='none'>
+        </form>",
+    );
+
+    // Test labels found by `for`, by wrapping, and that hidden inputs and other labels' targets get none
+    let labels: Vec<Option<&str>> = form.controls.iter().map(|control| control.label.as_deref()).collect();
+    assert_eq!(vec![Some("User name"), Some("Country (required)"), None, None, None], labels);
+}
+
+#[test]
+fn test_submission() {
+    let form = only_form(
+        "<form>
+            <input name='q' value='caf\u{e9} & co=1'>
+            <input type='file' name='upload'>
+            <input type='submit' name='send' value='Send'>
+            <input type='image' name='map'>
+            <input value='unnamed'>
+            <input type='checkbox' name='off'>
+            <textarea name='note'>a\r\nb\rc</textarea>
+            <button name='go' value='1'>Go</button>
+        </form>",
+    );
+
+    // Test which controls are submitted, and how names and values are encoded
+    let submission = form.submission();
+    let names: Vec<&str> = submission.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(vec!["q", "upload", "note"], names);
+    assert_eq!("q=caf%C3%A9+%26+co%3D1&upload=&note=a%0D%0Ab%0D%0Ac", form.to_urlencoded());
+
+    // Test that the free function gives the parser's results
+    let html = "<form><input name='x' value='1'></form><form></form>";
+    assert_eq!(Parser::new(html).forms(), extract_forms(html.to_string()));
+    assert_eq!("x=1", extract_forms(html.to_string())[0].to_urlencoded());
+}
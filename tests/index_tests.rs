@@ -0,0 +1,77 @@
+use std::sync::Arc;
+use std::thread;
+
+use tagparser::Parser;
+
+// Helper function to check at compile time that a type can be shared across threads
+fn assert_send_sync<T: Send + Sync>() {}
+
+#[test]
+fn test_parser_is_send_and_sync() {
+    assert_send_sync::<Parser>();
+}
+
+#[test]
+fn test_queries_share_one_parse() {
+    let html = r#"
+        <div id="main" class="page">
+            <a class="nav" href="/one">One</a>
+            <a class="nav external" href="https://example.com">Two</a>
+            <p class="intro">Hello</p>
+        </div>
+    "#.to_string();
+    let parser = Parser::new(html);
+    
+    // Test that the same parser answers any number of queries
+    for _ in 0..3 {
+        assert_eq!(2, parser.parse_tags("a").len());
+        assert_eq!(vec!["/one", "https://example.com"], parser.extract_attribute_values("a", "href"));
+        assert_eq!(vec!["Hello"], parser.extract_tag_content("p"));
+    }
+    
+    // Test that the document is the one that was parsed up front
+    let first = parser.document() as *const _;
+    assert_eq!(first, parser.document() as *const _);
+}
+
+#[test]
+fn test_find_by_id_and_class() {
+    let html = r#"
+        <section id="a" class="box  wide box"><p id="dup">First</p></section>
+        <section id="b" class="box"><p id="dup">Second</p></section>
+        <span id="open" class="box">never closed
+    "#.to_string();
+    let parser = Parser::new(html);
+    
+    assert_eq!(Some("a"), parser.find_by_id("a").unwrap().attribute("id"));
+    assert_eq!("First", parser.find_by_id("dup").unwrap().text);
+    assert!(parser.find_by_id("missing").is_none());
+    assert!(parser.find_by_id("open").is_none());
+    
+    // Test that a class listed twice on an element finds it once, and unclosed elements are left out
+    let boxes: Vec<String> = parser.find_by_class("box").into_iter().map(|tag| tag.attribute("id").unwrap().to_string()).collect();
+    assert_eq!(vec!["a", "b"], boxes);
+    assert_eq!(1, parser.find_by_class("wide").len());
+    assert!(parser.find_by_class("bo").is_empty());
+}
+
+#[test]
+fn test_parser_shared_across_threads() {
+    let html = (0..50).map(|i| format!("<li class='item' data-n='{}'>Item {}</li>", i, i)).collect::<String>();
+    let parser = Arc::new(Parser::new(format!("<ul>{}</ul>", html)));
+    
+    let handles: Vec<_> = (0..4)
+        .map(|_| {
+            let parser = Arc::clone(&parser);
+            thread::spawn(move || {
+                let items = parser.extract_tag_content("li");
+                let numbers = parser.extract_attribute_values("li", "data-n");
+                (items.len(), numbers.len(), parser.find_by_class("item").len())
+            })
+        })
+        .collect();
+    
+    for handle in handles {
+        assert_eq!((50, 50, 50), handle.join().unwrap());
+    }
+}
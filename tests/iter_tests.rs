@@ -0,0 +1,52 @@
+use tagparser::Parser;
+
+const PAGE: &str = r#"
+    <head><title>First title</title><title>Second title</title></head>
+    <ul>
+        <li><a href="/1">One</a></li>
+        <li><a>No link</a></li>
+        <li><a href="/2">Two</a></li>
+        <li class="last"><a href="/3">Three</a></li>
+    </ul>
+"#;
+
+#[test]
+fn test_iterators_match_collected_results() {
+    let parser = Parser::new(PAGE);
+
+    // Test that each iterator yields what its Vec counterpart returns, in order
+    assert_eq!(parser.parse_tags("a"), parser.iter_tags("a").collect::<Vec<_>>());
+    assert_eq!(
+        parser.extract_attribute_values("a", "href"),
+        parser.iter_attribute_values("a", "href").collect::<Vec<_>>()
+    );
+    assert_eq!(parser.select("ul li > a").unwrap(), parser.iter_select("ul li > a").unwrap().collect::<Vec<_>>());
+}
+
+#[test]
+fn test_iterators_stop_early() {
+    let parser = Parser::new(PAGE);
+
+    // Test taking only what is needed
+    assert_eq!(Some("<title>First title</title>"), parser.iter_tags("title").next());
+    assert_eq!(vec!["/1", "/2"], parser.iter_attribute_values("a", "href").take(2).collect::<Vec<_>>());
+
+    let found = parser.iter_tags("a").find(|link| link.contains("Two"));
+    assert_eq!(Some(r#"<a href="/2">Two</a>"#), found);
+
+    let last = parser.iter_select("li.last a").unwrap().next();
+    assert_eq!(Some(r#"<a href="/3">Three</a>"#), last);
+}
+
+#[test]
+fn test_iterators_without_matches() {
+    let parser = Parser::new(PAGE);
+
+    // Test that missing tags and attributes give empty iterators
+    assert_eq!(None, parser.iter_tags("table").next());
+    assert_eq!(None, parser.iter_attribute_values("a", "target").next());
+    assert_eq!(None, parser.iter_select("li.first").unwrap().next());
+
+    // Test that an invalid selector is reported before iterating
+    assert!(parser.iter_select("li:nth-child(").is_err());
+}
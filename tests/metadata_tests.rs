@@ -0,0 +1,140 @@
+use std::fs;
+
+use tagparser::{extract_metadata, Document, Metadata, Parser};
+
+const PAGE: &str = r#"<!DOCTYPE html>
+<html lang="en-GB">
+<head>
+    <meta charset="UTF-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1">
+    <title>
+        Fish &amp; Chips   Recipes
+    </title>
+    <META NAME="Description" CONTENT="How to make fish &amp; chips.">
+    <meta name="keywords" content="fish, chips,, cooking ">
+    <meta name="robots" content="index, follow">
+    <link rel="canonical" href="https://example.com/recipes/fish">
+    <link rel="icon" href="/favicon.ico" sizes="32x32">
+    <link rel="Shortcut  Icon" href="/old.ico" type="image/x-icon">
+    <link rel="apple-touch-icon-precomposed" href="/touch.png" sizes="180x180">
+    <link rel="stylesheet" href="/site.css">
+    <meta property="og:title" content="Fish &amp; Chips">
+    <meta property="og:type" content="article">
+    <meta property="og:image" content="https://example.com/fish.jpg">
+    <meta property="og:image:width" content="1200">
+    <meta property="og:image:height" content="630">
+    <meta property="og:site_name" content="Recipes">
+    <meta property="og:image" content="https://example.com/chips.jpg">
+    <meta property="og:image:alt" content="Chips">
+    <meta name="twitter:card" content="summary_large_image">
+    <meta name="twitter:image" content="https://example.com/card.jpg">
+    <meta name="twitter:image:alt" content="A plate">
+    <meta property="article:published_time" content="2024-05-01T10:00:00Z">
+    <meta property="article:tag" content="fish">
+    <meta property="article:tag" content="chips">
+    <meta property="fb:app_id" content="123">
+</head>
+<body><title>Not the title</title><meta name="description" content="Later"></body>
+</html>"#;
+
+#[test]
+fn test_basic_metadata() {
+    let metadata = Parser::new(PAGE).metadata();
+
+    assert_eq!(Some("Fish & Chips Recipes".to_string()), metadata.title);
+    assert_eq!(Some("How to make fish & chips.".to_string()), metadata.description);
+    assert_eq!(vec!["fish", "chips", "cooking"], metadata.keywords);
+    assert_eq!(Some("https://example.com/recipes/fish".to_string()), metadata.canonical);
+    assert_eq!(Some("index, follow".to_string()), metadata.robots);
+    assert_eq!(Some("width=device-width, initial-scale=1".to_string()), metadata.viewport);
+    assert_eq!(Some("UTF-8".to_string()), metadata.charset);
+    assert_eq!(Some("en-GB".to_string()), metadata.lang);
+}
+
+#[test]
+fn test_icons() {
+    let metadata = extract_metadata(PAGE.to_string());
+
+    // Test that every kind of icon link is found, and other links are not
+    let icons: Vec<(&str, &str)> = metadata.icons.iter().map(|icon| (icon.rel.as_str(), icon.href.as_str())).collect();
+    assert_eq!(
+        vec![("icon", "/favicon.ico"), ("shortcut icon", "/old.ico"), ("apple-touch-icon-precomposed", "/touch.png")],
+        icons
+    );
+    assert_eq!(Some("32x32"), metadata.icons[0].sizes.as_deref());
+    assert_eq!(Some("image/x-icon"), metadata.icons[1].mime_type.as_deref());
+}
+
+#[test]
+fn test_properties() {
+    let metadata = Parser::new(PAGE).metadata();
+
+    // Test that og, twitter and article properties are collected, and others are not
+    assert_eq!(Some("Fish & Chips"), metadata.property("og:title"));
+    assert_eq!(Some("summary_large_image"), metadata.property("twitter:card"));
+    assert_eq!(Some("2024-05-01T10:00:00Z"), metadata.property("article:published_time"));
+    assert_eq!(None, metadata.property("fb:app_id"));
+    assert_eq!(vec!["fish", "chips"], metadata.properties_named("article:tag").map(|tag| tag.content.as_str()).collect::<Vec<_>>());
+
+    // Test that structured properties attach to the latest property they describe
+    let images: Vec<_> = metadata.properties_named("og:image").collect();
+    assert_eq!(2, images.len());
+    assert_eq!(vec![("width".to_string(), "1200".to_string()), ("height".to_string(), "630".to_string())], images[0].properties);
+    assert_eq!(Some("Chips"), images[1].property("alt"));
+    assert_eq!(None, images[1].property("width"));
+    assert_eq!(Some("A plate"), metadata.property("twitter:image:alt"));
+    assert_eq!(Some("1200"), metadata.property("og:image:width"));
+    assert_eq!(None, metadata.property("og:video:width"));
+}
+
+#[test]
+fn test_property_forms() {
+    let html = "
+        <meta property='OG:Image:URL' content='/a.png'>
+        <meta property='og:image:secure_url' content='https://x/a.png'>
+        <meta name='og:description' content='By name'>
+        <meta property='twitter:title' content='By property'>
+        <meta property='og:video:width' content='640'>
+        <meta property='og:title'>";
+    let metadata = Parser::new(html).metadata();
+
+    // Test that og:image:url starts an image, and names are lowercased
+    let names: Vec<&str> = metadata.properties.iter().map(|property| property.name.as_str()).collect();
+    assert_eq!(vec!["og:image", "og:description", "twitter:title", "og:video:width"], names);
+    assert_eq!(Some("https://x/a.png"), metadata.properties[0].property("secure_url"));
+    assert_eq!(Some("/a.png"), metadata.property("og:image"));
+
+    // Test that structured properties written before the property they describe attach to it
+    let html = "
+        <meta property='og:image:width' content='1200'>
+        <meta property='og:title' content='Title'>
+        <meta property='og:image' content='/a.png'>
+        <meta property='og:image:height' content='630'>
+        <meta property='og:image' content='/b.png'>";
+    let metadata = Parser::new(html).metadata();
+    let names: Vec<&str> = metadata.properties.iter().map(|property| property.name.as_str()).collect();
+    assert_eq!(vec!["og:title", "og:image", "og:image"], names);
+    assert_eq!(vec![("width".to_string(), "1200".to_string()), ("height".to_string(), "630".to_string())], metadata.properties[1].properties);
+    assert!(metadata.properties[2].properties.is_empty());
+}
+
+#[test]
+fn test_fallbacks_and_settings() {
+    // Test the charset and language taken from http-equiv
+    let html = "<meta http-equiv='Content-Type' content='text/html; charset=\"windows-1251\"'><meta http-equiv='content-language' content='ru'>";
+    let metadata = extract_metadata(html.to_string());
+    assert_eq!((Some("windows-1251"), Some("ru")), (metadata.charset.as_deref(), metadata.lang.as_deref()));
+
+    // Test that a page without metadata gives an empty result
+    assert_eq!(Metadata::default(), extract_metadata("<p>Nothing here</p>".to_string()));
+
+    // Test that decoding follows the parser, and building from a document
+    let raw = Parser::new(PAGE).decode_entities(false).metadata();
+    assert_eq!(Some("Fish &amp; Chips Recipes".to_string()), raw.title);
+    assert_eq!(Parser::new(PAGE).metadata(), Metadata::from(&Document::parse(PAGE)));
+
+    // Test a page read in a legacy encoding
+    let bytes = fs::read("tests/test_data/cp1251_page.html").unwrap();
+    let metadata = Parser::from_bytes(&bytes, None).metadata();
+    assert_eq!((Some("windows-1251"), Some("Новости")), (metadata.charset.as_deref(), metadata.title.as_deref()));
+}
@@ -1,4 +1,4 @@
-use tagparser::{parse_tags, parse_tags_with_attr, extract_tag_content, extract_attribute_values};
+use tagparser::{parse_tags, parse_tags_with_attr, extract_tag_content, extract_attribute_values, find_tags, text_content, validate, DiagnosticKind, Parser};
 
 #[test]
 fn test_parse_tags() {
@@ -94,8 +94,14 @@ fn test_malformed_html() {
     let tags_a = parse_tags(html.clone(), "a".to_string());
     assert_eq!(Vec::<String>::new(), tags_a);
     
-    let tags_with_attr = parse_tags_with_attr(html, "a".to_string(), "href", None);
+    let tags_with_attr = parse_tags_with_attr(html.clone(), "a".to_string(), "href", None);
     assert_eq!(Vec::<String>::new(), tags_with_attr);
+    
+    // Test that the unclosed tag is reported
+    let diagnostics = validate(html);
+    assert_eq!(1, diagnostics.len());
+    assert_eq!(DiagnosticKind::UnclosedElement, diagnostics[0].kind);
+    assert_eq!("1:1: <a> is never closed", diagnostics[0].to_string());
 }
 
 #[test]
@@ -216,14 +222,104 @@ fn test_extract_tag_content_nested_tags() {
         </div>
     "#.to_string();
     
-    // Test with nested tags of the same type
+    // Test with nested tags of the same type: each div ends at its own closing tag
     let div_texts = extract_tag_content(html, "div".to_string());
     assert_eq!(
-        vec!["Nested content"],
+        vec![
+            "\n            <div class=\"inner\">Nested content</div>\n            Outer content\n        ",
+            "Nested content"
+        ],
         div_texts
     );
 }
 
+#[test]
+fn test_nested_tags_same_name() {
+    let html = "<div id='outer'><div id='inner'></div></div>".to_string();
+    
+    // The outer div must not be cut off at the inner closing tag
+    let tags_div = parse_tags(html.clone(), "div".to_string());
+    assert_eq!(
+        vec![
+            "<div id='outer'><div id='inner'></div></div>".to_string(),
+            "<div id='inner'></div>".to_string()
+        ],
+        tags_div
+    );
+    
+    let ids = extract_attribute_values(html, "div".to_string(), "id");
+    assert_eq!(vec!["outer", "inner"], ids);
+}
+
+#[test]
+fn test_nested_content_ends_at_own_end_tag() {
+    let html = "<div><div>x</div>y</div>";
+    let parser = Parser::new(html);
+    
+    // Test that the outer div's inner HTML, outer HTML and text all run to its own end tag
+    assert_eq!(vec!["<div>x</div>y", "x"], parser.extract_tag_content("div"));
+    assert_eq!(vec![html, "<div>x</div>"], parser.parse_tags("div"));
+    assert_eq!(vec!["x\ny", "x"], parser.text_content("div"));
+    
+    let outer = &parser.find_tags("div")[0];
+    assert_eq!(("<div>x</div>y", html, "xy"), (outer.inner_html.as_str(), outer.outer_html.as_str(), outer.text.as_str()));
+}
+
+#[test]
+fn test_tag_names_match_whole_names() {
+    let html = "<abbr title='HyperText'>HTML</abbr><a href='/'>Home</a><address>Here</address>";
+    let parser = Parser::new(html);
+    
+    // Test that `a` doesn't match `abbr` or `address`
+    assert_eq!(vec!["<a href='/'>Home</a>"], parser.parse_tags("a"));
+    assert_eq!(vec!["Home"], parser.extract_tag_content("a"));
+    assert_eq!(vec!["Home"], parser.text_content("a"));
+    assert_eq!(Vec::<&str>::new(), parser.extract_attribute_values("a", "title"));
+    assert_eq!(vec!["HTML"], parser.extract_tag_content("abbr"));
+}
+
+#[test]
+fn test_greater_than_inside_attribute_value() {
+    let html = r#"<a title="a > b" href='https://example.com'>Compare</a>"#.to_string();
+    
+    // A quoted '>' does not end the start tag
+    let tags_a = parse_tags(html.clone(), "a".to_string());
+    assert_eq!(vec![html.clone()], tags_a);
+    
+    let texts = extract_tag_content(html.clone(), "a".to_string());
+    assert_eq!(vec!["Compare"], texts);
+    
+    let hrefs = extract_attribute_values(html, "a".to_string(), "href");
+    assert_eq!(vec!["https://example.com"], hrefs);
+}
+
+#[test]
+fn test_multi_line_tags() {
+    let html = "<a\n    class='link'\n    href='https://example.com'\n>Multi\nline</a\n>".to_string();
+    
+    // Tags and content may span several lines
+    let tags_a = parse_tags(html.clone(), "a".to_string());
+    assert_eq!(vec![html.clone()], tags_a);
+    
+    let texts = extract_tag_content(html.clone(), "a".to_string());
+    assert_eq!(vec!["Multi\nline"], texts);
+    
+    let links = parse_tags_with_attr(html, "a".to_string(), "class", Some("link"));
+    assert_eq!(1, links.len());
+}
+
+#[test]
+fn test_self_closing_syntax() {
+    let html = "<p>Line<br/>break<br />here</p>".to_string();
+    
+    // Tags written as <tag/> are complete on their own and have no content
+    let tags_br = parse_tags(html.clone(), "br".to_string());
+    assert_eq!(vec!["<br/>", "<br />"], tags_br);
+    
+    let br_texts = extract_tag_content(html, "br".to_string());
+    assert_eq!(Vec::<String>::new(), br_texts);
+}
+
 #[test]
 fn test_extract_attribute_values() {
     let html = r#"
@@ -289,7 +385,168 @@ fn test_extract_attribute_values_empty_html() {
 fn test_extract_attribute_values_malformed() {
     let html = "<a href=https://example.com>No quotes</a>".to_string();
     
-    // Test with malformed attribute (no quotes)
+    // Test with unquoted attribute value
     let values = extract_attribute_values(html, "a".to_string(), "href");
-    assert_eq!(Vec::<String>::new(), values);
-} 
\ No newline at end of file
+    assert_eq!(vec!["https://example.com"], values);
+}
+
+#[test]
+fn test_attribute_syntax_forms() {
+    let html = r#"
+        <input disabled name="a"/>
+        <table><tr><td colspan=2>Wide</td><td>Narrow</td></tr></table>
+        <a href = "https://example.com" title="it's">Spaced</a>
+        <a title='say "hi"'>Quoted</a>
+    "#.to_string();
+    
+    // Test boolean attribute without a value
+    let disabled = extract_attribute_values(html.clone(), "input".to_string(), "disabled");
+    assert_eq!(vec![""], disabled);
+    
+    // Test unquoted value
+    let cells = parse_tags_with_attr(html.clone(), "td".to_string(), "colspan", Some("2"));
+    assert_eq!(vec!["<td colspan=2>Wide</td>"], cells);
+    
+    // Test whitespace around equals sign
+    let hrefs = extract_attribute_values(html.clone(), "a".to_string(), "href");
+    assert_eq!(vec!["https://example.com"], hrefs);
+    
+    // Test values containing the other quote character
+    let titles = extract_attribute_values(html.clone(), "a".to_string(), "title");
+    assert_eq!(vec!["it's", "say \"hi\""], titles);
+    
+    let quoted = parse_tags_with_attr(html, "a".to_string(), "title", Some("it's"));
+    assert_eq!(1, quoted.len());
+}
+
+#[test]
+fn test_attribute_names_match_whole_names() {
+    let html = "<a data-href='/data'>Data</a><a href='/plain'>Plain</a><a xhref='/x'>X</a>".to_string();
+    
+    // Test that href doesn't match data-href or xhref
+    let hrefs = extract_attribute_values(html.clone(), "a".to_string(), "href");
+    assert_eq!(vec!["/plain"], hrefs);
+    
+    let links = parse_tags_with_attr(html, "a".to_string(), "href", None);
+    assert_eq!(vec!["<a href='/plain'>Plain</a>"], links);
+}
+
+#[test]
+fn test_attribute_names_ignore_case() {
+    let html = "<a HREF='/upper'>Upper</a><a Href='/mixed'>Mixed</a>".to_string();
+    
+    let hrefs = extract_attribute_values(html.clone(), "a".to_string(), "href");
+    assert_eq!(vec!["/upper", "/mixed"], hrefs);
+    
+    let links = parse_tags_with_attr(html, "a".to_string(), "href", Some("/mixed"));
+    assert_eq!(vec!["<a Href='/mixed'>Mixed</a>"], links);
+}
+
+#[test]
+fn test_tag_names_ignore_case() {
+    let html = "<DIV class='a'>Upper</DIV><Div class='b'>Mixed</Div><div>Lower</div>";
+    let parser = Parser::new(html);
+    
+    // Test that every spelling of the name matches, whatever the query's case
+    assert_eq!(vec!["Upper", "Mixed", "Lower"], parser.extract_tag_content("div"));
+    assert_eq!(vec!["Upper", "Mixed", "Lower"], parser.extract_tag_content("DIV"));
+    assert_eq!(vec!["<Div class='b'>Mixed</Div>"], parser.parse_tags_with_attr("dIv", "CLASS", Some("b")));
+    assert_eq!(vec!["a", "b"], parser.extract_attribute_values("Div", "class"));
+    assert_eq!(3, parser.find_tags("div").len());
+    assert_eq!(3, parse_tags(html.to_string(), "div".to_string()).len());
+}
+
+#[test]
+fn test_case_sensitive_matching() {
+    let xml = "<Item ID='1'>First</Item><item id='2' ID='3'>Second</item><ITEM>Third</ITEM>";
+    let parser = Parser::new(xml).case_sensitive(true);
+    
+    // Test that names only match as written
+    assert_eq!(vec!["First"], parser.extract_tag_content("Item"));
+    assert_eq!(vec!["Second"], parser.extract_tag_content("item"));
+    assert_eq!(Vec::<&str>::new(), parser.extract_tag_content("iTeM"));
+    assert_eq!(vec!["1"], parser.extract_attribute_values("Item", "ID"));
+    assert_eq!(Vec::<&str>::new(), parser.extract_attribute_values("Item", "id"));
+    assert_eq!(vec!["<item id='2' ID='3'>Second</item>"], parser.parse_tags_with_attr("item", "id", Some("2")));
+    assert_eq!(vec!["Third"], parser.text_content("ITEM"));
+    
+    // Test that the option can be turned back off
+    assert_eq!(3, parser.case_sensitive(false).find_tags("item").len());
+}
+
+#[test]
+fn test_duplicate_attributes_keep_first() {
+    let html = "<a href='/first' HREF='/second'>Link</a>".to_string();
+    
+    let hrefs = extract_attribute_values(html.clone(), "a".to_string(), "href");
+    assert_eq!(vec!["/first"], hrefs);
+    
+    let links = parse_tags_with_attr(html, "a".to_string(), "href", Some("/second"));
+    assert_eq!(Vec::<String>::new(), links);
+} 
+#[test]
+fn test_no_matches_inside_raw_text() {
+    let html = r#"
+        <script>document.write("<a href='/fake'>Fake</a>");</script>
+        <style>a::after { content: "<a href='/styled'>"; }</style>
+        <title><a href='/title'>Title</a></title>
+        <textarea><a href='/draft'>Draft</a></textarea>
+        <a href='/real'>Real</a>
+    "#.to_string();
+    
+    // Test that only the real link is found
+    assert_eq!(vec!["<a href='/real'>Real</a>"], parse_tags(html.clone(), "a".to_string()));
+    assert_eq!(vec!["/real"], extract_attribute_values(html.clone(), "a".to_string(), "href"));
+    
+    // Test that the raw-text elements keep their content as text
+    let scripts = extract_tag_content(html.clone(), "script".to_string());
+    assert_eq!(vec![r#"document.write("<a href='/fake'>Fake</a>");"#], scripts);
+    let drafts = text_content(html, "textarea".to_string());
+    assert_eq!(vec!["<a href='/draft'>Draft</a>"], drafts);
+}
+
+#[test]
+fn test_no_matches_inside_comments_and_cdata() {
+    let html = "<!-- <p>old</p> --><![CDATA[ <p>data</p> ]]><p>new</p>".to_string();
+    
+    assert_eq!(vec!["<p>new</p>"], parse_tags(html.clone(), "p".to_string()));
+    assert_eq!(vec!["new"], extract_tag_content(html, "p".to_string()));
+}
+
+#[test]
+fn test_raw_text_is_not_decoded() {
+    let html = "<script>if (a &amp;&amp; b) {}</script><title>Fish &amp; Chips</title>".to_string();
+    
+    // Test that script content is kept as written while title content is decoded
+    assert_eq!("if (a &amp;&amp; b) {}", find_tags(html.clone(), "script".to_string())[0].text);
+    assert_eq!("Fish & Chips", find_tags(html, "title".to_string())[0].text);
+}
+
+#[test]
+fn test_unclosed_list_items_and_paragraphs() {
+    let html = r#"
+        <ul>
+            <li>Fast
+            <li>Safe
+            <li><a href="/more">More</a>
+        </ul>
+        <p>First paragraph
+        <p>Second paragraph
+        <div>Block</div>
+    "#.to_string();
+    
+    // Test that every item is found, each ending where the next one starts
+    let items = extract_tag_content(html.clone(), "li".to_string());
+    assert_eq!(3, items.len());
+    assert_eq!("Fast", items[0].trim());
+    assert_eq!("Safe", items[1].trim());
+    assert_eq!(r#"<a href="/more">More</a>"#, items[2].trim());
+    
+    let paragraphs = text_content(html.clone(), "p".to_string());
+    assert_eq!(vec!["First paragraph", "Second paragraph"], paragraphs);
+    
+    // Test that void elements are found without a closing tag
+    let html = "<p>Line one<br>Line two<img src='a.png' alt='A'></p>".to_string();
+    assert_eq!(vec!["<br>"], parse_tags(html.clone(), "br".to_string()));
+    assert_eq!(vec!["a.png"], extract_attribute_values(html, "img".to_string(), "src"));
+}
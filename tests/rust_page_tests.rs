@@ -103,16 +103,14 @@ fn test_extract_attribute_values() {
 fn test_form_elements() {
     let html = load_test_html();
     
-    // Note: due to the parser's behavior, self-closing tags without a closing slash
-    // may not be detected if there is no corresponding closing tag in the HTML
-    
-    // Test extracting input tag attributes directly
+    // Test extracting input tag attributes directly; input is a void element,
+    // so it is found without a closing tag or slash
     let input_types = extract_attribute_values(html.clone(), "input".to_string(), "type");
     println!("input_types: {:?}", input_types);
-    
-    // Парсер не находит input-теги, так как они являются self-closing тегами без закрывающего слеша
-    // и не имеют соответствующего закрывающего тега в HTML
-    assert_eq!(input_types.len(), 0);
+    assert_eq!(
+        vec!["email", "text", "checkbox", "checkbox", "checkbox", "submit", "reset", "hidden"],
+        input_types
+    );
     
     // Test extracting values from select options
     let option_values = extract_attribute_values(html.clone(), "option".to_string(), "value");
@@ -127,22 +125,16 @@ fn test_form_elements() {
 fn test_self_closing_tags() {
     let html = load_test_html();
     
-    // Note: due to the parser's behavior, self-closing tags without a closing slash
-    // may not be detected if there is no corresponding closing tag in the HTML
-    
-    // Test extracting meta tag attributes directly
+    // Test extracting meta tag attributes directly; void elements need no closing slash
     let meta_names = extract_attribute_values(html.clone(), "meta".to_string(), "name");
     println!("meta_names: {:?}", meta_names);
-    
-    // Парсер не находит meta-теги, так как они являются self-closing тегами без закрывающего слеша
-    // и не имеют соответствующего закрывающего тега в HTML
-    assert_eq!(meta_names.len(), 0);
-    
-    // Test extracting input tag attributes directly
-    let input_types = extract_attribute_values(html.clone(), "input".to_string(), "type");
-    println!("input_types: {:?}", input_types);
-    
-    // Парсер не находит input-теги, так как они являются self-closing тегами без закрывающего слеша
-    // и не имеют соответствующего закрывающего тега в HTML
-    assert_eq!(input_types.len(), 0);
-} 
\ No newline at end of file
+    assert_eq!(vec!["viewport", "description", "keywords"], meta_names);
+    
+    // Test that void elements have no content to extract
+    assert_eq!(Vec::<String>::new(), extract_tag_content(html.clone(), "img".to_string()));
+    assert_eq!(2, parse_tags(html.clone(), "img".to_string()).len());
+    assert_eq!(
+        vec!["<br>"],
+        parse_tags(html, "br".to_string())
+    );
+}
@@ -0,0 +1,183 @@
+use tagparser::{select, Document, Parser, Selector};
+
+// Helper function to run a selector and collect the text of each match
+fn texts(html: &str, selector: &str) -> Vec<String> {
+    Document::parse(html)
+        .select(selector)
+        .unwrap()
+        .iter()
+        .map(|node| node.text())
+        .collect()
+}
+
+#[test]
+fn test_simple_selectors() {
+    let html = "<div id='main' class='box wide'><p class='note'>A</p><p>B</p><span class='note'>C</span></div>";
+    
+    assert_eq!(vec!["A", "B"], texts(html, "p"));
+    assert_eq!(vec!["A", "C"], texts(html, ".note"));
+    assert_eq!(vec!["A"], texts(html, "p.note"));
+    assert_eq!(vec!["ABC"], texts(html, "#main"));
+    assert_eq!(vec!["ABC"], texts(html, "div.box.wide"));
+    assert_eq!(Vec::<String>::new(), texts(html, "div.box.narrow"));
+    assert_eq!(vec!["ABC", "A", "B", "C"], texts(html, "*"));
+}
+
+#[test]
+fn test_type_selectors_ignore_case() {
+    assert_eq!(vec!["x"], texts("<DIV>x</DIV>", "div"));
+    assert_eq!(vec!["x"], texts("<div>x</div>", "DIV"));
+}
+
+#[test]
+fn test_attribute_selectors() {
+    let html = r#"
+        <a href="https://secure.example" lang="en-US" class="btn primary">1</a>
+        <a href="http://plain.example/logo.png" lang="en">2</a>
+        <a title="Rust Language">3</a>
+    "#;
+    
+    assert_eq!(vec!["1", "2"], texts(html, "a[href]"));
+    assert_eq!(vec!["2"], texts(html, "a[lang=en]"));
+    assert_eq!(vec!["1", "2"], texts(html, "a[lang|=en]"));
+    assert_eq!(vec!["1"], texts(html, "a[class~=primary]"));
+    assert_eq!(Vec::<String>::new(), texts(html, "a[class~=prim]"));
+    assert_eq!(vec!["1"], texts(html, r#"a[href^="https"]"#));
+    assert_eq!(vec!["2"], texts(html, "a[href$='.png']"));
+    assert_eq!(vec!["3"], texts(html, "a[title*=Rust]"));
+    assert_eq!(Vec::<String>::new(), texts(html, "a[title*=rust]"));
+    assert_eq!(vec!["3"], texts(html, "a[title*=rust i]"));
+    assert_eq!(vec!["3"], texts(html, "a[TITLE]"));
+}
+
+#[test]
+fn test_combinators() {
+    let html = "<div class='article'><h2><a>Deep</a></h2><a>Direct</a><p>P1</p><span>S</span><p>P2</p></div>";
+    
+    assert_eq!(vec!["Deep", "Direct"], texts(html, "div.article a"));
+    assert_eq!(vec!["Direct"], texts(html, "div.article > a"));
+    assert_eq!(vec!["Deep"], texts(html, "div.article > h2 a"));
+    assert_eq!(vec!["P1"], texts(html, "a + p"));
+    assert_eq!(vec!["P1", "P2"], texts(html, "h2 ~ p"));
+    assert_eq!(vec!["P2"], texts(html, "span+p"));
+}
+
+#[test]
+fn test_selector_lists_keep_document_order() {
+    let html = "<h1>One</h1><p>Two</p><h2>Three</h2>";
+    assert_eq!(vec!["One", "Two", "Three"], texts(html, "h2, p, h1"));
+    assert_eq!(vec!["One"], texts(html, "h1, h1"));
+}
+
+#[test]
+fn test_structural_pseudo_classes() {
+    let html = "<ul><li>1</li><li>2</li><li>3</li><li>4</li><li>5</li></ul><p></p><div><span>only</span></div>";
+    
+    assert_eq!(vec!["2"], texts(html, "ul li:nth-child(2)"));
+    assert_eq!(vec!["1", "3", "5"], texts(html, "li:nth-child(odd)"));
+    assert_eq!(vec!["2", "4"], texts(html, "li:nth-child(even)"));
+    assert_eq!(vec!["1", "2", "3"], texts(html, "li:nth-child(-n + 3)"));
+    assert_eq!(vec!["4"], texts(html, "li:nth-last-child(2)"));
+    assert_eq!(vec!["1"], texts(html, "li:first-child"));
+    assert_eq!(vec!["5"], texts(html, "li:last-child"));
+    assert_eq!(vec!["only"], texts(html, "span:only-child"));
+    assert_eq!(vec![""], texts(html, "p:empty"));
+    
+    // Test that arguments at the limits of the arithmetic match nothing instead of overflowing
+    assert!(texts(html, "li:nth-child(n-9223372036854775808)").is_empty());
+    assert!(texts(html, "li:nth-child(-n-9223372036854775808)").is_empty());
+    assert_eq!(vec!["1"], texts(html, "li:nth-child(-9223372036854775807n+1)"));
+    assert_eq!(3, Document::parse(html).select(":root").unwrap().len());
+}
+
+#[test]
+fn test_of_type_pseudo_classes() {
+    let html = "<div><h2>T</h2><p>A</p><p>B</p><span>S</span><p>C</p></div>";
+    
+    assert_eq!(vec!["A"], texts(html, "p:first-of-type"));
+    assert_eq!(vec!["C"], texts(html, "p:last-of-type"));
+    assert_eq!(vec!["B"], texts(html, "p:nth-of-type(2)"));
+    assert_eq!(vec!["B"], texts(html, "p:nth-last-of-type(2)"));
+    assert_eq!(vec!["T", "S"], texts(html, "div > :only-of-type"));
+    assert_eq!(vec!["A"], texts(html, "div > :nth-child(1 of p)"));
+}
+
+#[test]
+fn test_logical_pseudo_classes() {
+    let html = r#"
+        <input type="hidden" name="token"/>
+        <input type="text" name="query"/>
+        <section><h2>With link</h2><a href="x">x</a></section>
+        <section><h2>Without link</h2></section>
+    "#;
+    
+    let document = Document::parse(html);
+    let inputs = document.select("input:not([type=hidden])").unwrap();
+    assert_eq!(vec![Some("query")], inputs.iter().map(|node| node.raw_attribute("name")).collect::<Vec<_>>());
+    
+    assert_eq!(vec!["With link"], texts(html, "section:has(a[href]) h2"));
+    assert_eq!(vec!["With link"], texts(html, "section:has(> a) > h2"));
+    assert_eq!(vec!["With link"], texts(html, "h2:has(+ a)"));
+    assert_eq!(vec!["Without link"], texts(html, "section:not(:has(a)) h2"));
+    assert_eq!(vec!["With link", "Without link"], texts(html, ":is(section, article) > h2"));
+    assert_eq!(vec!["With link", "Without link"], texts(html, ":where(section) h2"));
+}
+
+#[test]
+fn test_node_select_scope() {
+    let document = Document::parse("<div id='a'><p>1</p></div><div id='b'><p>2</p></div>");
+    let second = document.select("#b").unwrap()[0];
+    
+    let paragraphs: Vec<String> = second.select("p").unwrap().iter().map(|node| node.text()).collect();
+    assert_eq!(vec!["2"], paragraphs);
+}
+
+#[test]
+fn test_escaped_identifiers() {
+    let html = r#"<p class="a:b">colon</p><p id="1st">digit</p>"#;
+    assert_eq!(vec!["colon"], texts(html, r"p.a\:b"));
+    assert_eq!(vec!["digit"], texts(html, r"#\31 st"));
+}
+
+#[test]
+fn test_invalid_selectors() {
+    for selector in ["", "div >", "a[href", "a[href^]", "p:unknown", "p::before", "li:nth-child(x)", "li:nth-child(éé)", "p..a", "a,"] {
+        let error = Selector::parse(selector);
+        assert!(error.is_err(), "selector {:?} should be rejected", selector);
+    }
+    
+    let error = Selector::parse("div > :bogus").unwrap_err();
+    assert_eq!(7, error.position);
+    assert!(error.to_string().contains("bogus"));
+}
+
+#[test]
+fn test_parser_select() {
+    let html = "<nav><a class='nav-link' href='/'>Home</a><a class='social' href='https://x'>X</a></nav>".to_string();
+    
+    let parser = Parser::new(html.clone());
+    assert_eq!(
+        vec!["<a class='nav-link' href='/'>Home</a>"],
+        parser.select("nav > a.nav-link").unwrap()
+    );
+    
+    assert_eq!(
+        vec!["<a class='social' href='https://x'>X</a>"],
+        select(html, "a[href^=https]").unwrap()
+    );
+}
+
+#[test]
+fn test_select_on_test_page() {
+    let html = std::fs::read_to_string("tests/test_data/rust_page.html").unwrap();
+    let document = Document::parse(&html);
+    
+    let features = document.select("ul.feature-list li:nth-child(2)").unwrap();
+    assert_eq!("Concurrency without data races", features[0].text());
+    
+    let resources = document.select("table tbody tr td:nth-child(3) a").unwrap();
+    assert_eq!(3, resources.len());
+    
+    let external = document.select("nav a.external[target=_blank]").unwrap();
+    assert_eq!("Official Website", external[0].text());
+}
@@ -0,0 +1,142 @@
+use std::fs;
+use std::io::{self, Read};
+
+use tagparser::{Error, Parser, StreamParser};
+
+// Reader that hands out at most `size` bytes per read, to cut tokens and
+// characters at every possible place
+struct Trickle<'a> {
+    data: &'a [u8],
+    size: usize,
+}
+
+impl Read for Trickle<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = self.size.min(buf.len()).min(self.data.len());
+        buf[..len].copy_from_slice(&self.data[..len]);
+        self.data = &self.data[len..];
+        Ok(len)
+    }
+}
+
+// Helper function to stream `html` in reads of `size` bytes
+fn stream(html: &str, size: usize) -> StreamParser<Trickle<'_>> {
+    StreamParser::new(Trickle { data: html.as_bytes(), size })
+}
+
+fn collect(matches: impl Iterator<Item = io::Result<String>>) -> Vec<String> {
+    matches.collect::<io::Result<_>>().unwrap()
+}
+
+#[test]
+fn test_stream_matches_parser_on_rust_page() {
+    let html = fs::read_to_string("tests/test_data/rust_page.html").unwrap();
+    let parser = Parser::new(html.as_str());
+
+    for size in [1, 5, 64, 1 << 20] {
+        for tag in ["a", "li", "p", "div", "h1", "script", "meta", "input", "option"] {
+            assert_eq!(parser.parse_tags(tag), collect(stream(&html, size).parse_tags(tag)), "<{}> in reads of {}", tag, size);
+        }
+        assert_eq!(
+            parser.parse_tags_with_attr("a", "class", Some("nav-link")),
+            collect(stream(&html, size).parse_tags_with_attr("a", "class", Some("nav-link")))
+        );
+        assert_eq!(
+            parser.extract_attribute_values("a", "href"),
+            collect(stream(&html, size).extract_attribute_values("a", "href"))
+        );
+    }
+}
+
+#[test]
+fn test_stream_implied_and_nested_elements() {
+    let html = "<ul><li>One<li>Two<ul><li>Nested</ul><li>Three</ul><div><div>x</div>y</div><p>Unclosed <b>bold";
+    let parser = Parser::new(html);
+
+    for size in [1, 2, 3, 1024] {
+        for tag in ["li", "ul", "div", "p", "b"] {
+            assert_eq!(parser.parse_tags(tag), collect(stream(html, size).parse_tags(tag)));
+        }
+    }
+
+    // Test that nested matches come out in document order
+    let divs = collect(stream(html, 4).parse_tags("div"));
+    assert_eq!(vec!["<div><div>x</div>y</div>", "<div>x</div>"], divs);
+}
+
+#[test]
+fn test_stream_raw_text_and_comments() {
+    let html = "<script>if (a < b) { x = '<a href=\"/no\">'; }</script><!-- <a href='/no'> --><a href='/yes'>Yes</a>";
+
+    for size in [1, 7, 1024] {
+        assert_eq!(vec!["/yes"], collect(stream(html, size).extract_attribute_values("a", "href")));
+    }
+}
+
+#[test]
+fn test_stream_decoding_and_utf8() {
+    let html = "<a title='Caf&eacute; \u{1F980}'>Крабы \u{1F980}</a>";
+
+    // Test that multi-byte characters survive being split across reads
+    for size in [1, 2, 3] {
+        assert_eq!(vec![html], collect(stream(html, size).parse_tags("a")));
+        assert_eq!(vec!["Café \u{1F980}"], collect(stream(html, size).extract_attribute_values("a", "title")));
+    }
+
+    let raw = stream(html, 4).decode_entities(false).extract_attribute_values("a", "title");
+    assert_eq!(vec!["Caf&eacute; \u{1F980}"], collect(raw));
+}
+
+#[test]
+fn test_stream_name_case() {
+    let html = "<DIV HREF='/1'>A</DIV><div href='/2'>B</div><Div>C</Div>";
+    
+    for case_sensitive in [false, true] {
+        let parser = Parser::new(html).case_sensitive(case_sensitive);
+        for tag in ["div", "DIV", "Div"] {
+            let streamed = stream(html, 3).case_sensitive(case_sensitive);
+            assert_eq!(parser.parse_tags(tag), collect(streamed.parse_tags(tag)));
+            let streamed = stream(html, 3).case_sensitive(case_sensitive);
+            assert_eq!(parser.extract_attribute_values(tag, "HREF"), collect(streamed.extract_attribute_values(tag, "HREF")));
+        }
+    }
+    assert_eq!(vec!["/1", "/2"], collect(stream(html, 3).extract_attribute_values("div", "href")));
+}
+
+// Reader that fails once its data runs out
+struct Failing<'a>(&'a [u8]);
+
+impl Read for Failing<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.0.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::BrokenPipe, "connection lost"));
+        }
+        self.0.read(buf)
+    }
+}
+
+#[test]
+fn test_stream_errors() {
+    // Test that a read error is reported, and that nothing follows it
+    let mut matches = StreamParser::new(Failing(b"<a>One</a><a>Two")).parse_tags("a");
+    assert_eq!(io::ErrorKind::BrokenPipe, matches.next().unwrap().unwrap_err().kind());
+    assert!(matches.next().is_none());
+
+    // Test that bytes that aren't UTF-8 are read in the fallback encoding
+    let bytes = b"<a>One</a><a>\xFF</a>";
+    assert_eq!(vec!["<a>One</a>", "<a>\u{FF}</a>"], collect(StreamParser::new(&bytes[..]).parse_tags("a")));
+
+    // Test that a character cut off by the end of UTF-8 input is replaced
+    let bytes = "<meta charset='utf-8'><p>\u{e9}".as_bytes();
+    let cut = StreamParser::new(&bytes[..bytes.len() - 1]).parse_tags("p");
+    assert_eq!(vec!["<p>\u{FFFD}"], collect(cut));
+
+    // Test that invalid names are rejected by the try_* variants
+    let html = "<a href='/'>Home</a>";
+    assert_eq!(Some(Error::InvalidTagName("a b".to_string())), stream(html, 8).try_parse_tags("a b").err());
+    assert_eq!(
+        Some(Error::InvalidAttributeName("".to_string())),
+        stream(html, 8).try_extract_attribute_values("a", "").err()
+    );
+    assert_eq!(vec!["/"], collect(stream(html, 8).try_extract_attribute_values("a", "href").unwrap()));
+}
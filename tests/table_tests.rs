@@ -0,0 +1,138 @@
+use tagparser::{extract_tables, Document, Parser, Table};
+
+// Helper function to turn rows of string slices into owned rows
+fn grid(rows: &[&[&str]]) -> Vec<Vec<String>> {
+    rows.iter().map(|row| row.iter().map(|cell| cell.to_string()).collect()).collect()
+}
+
+// Helper function to get the only table of a page
+fn only_table(html: &str) -> Table {
+    let mut tables = Parser::new(html).tables();
+    assert_eq!(1, tables.len());
+    tables.remove(0)
+}
+
+#[test]
+fn test_spans_expand_into_grid() {
+    let table = only_table(
+        "<table>
+            <tr><td rowspan='3'>A</td><td colspan='2'>B</td></tr>
+            <tr><td>C</td><td rowspan='2' colspan='2'>D</td></tr>
+            <tr><td>E</td></tr>
+            <tr><td>F</td></tr>
+        </table>",
+    );
+
+    // Test that spanned cells are repeated and short rows padded to the widest one
+    assert!(table.headers.is_empty());
+    assert_eq!(
+        grid(&[&["A", "B", "B", ""], &["A", "C", "D", "D"], &["A", "E", "D", "D"], &["F", "", "", ""]]),
+        table.rows
+    );
+    assert_eq!(4, table.width());
+}
+
+#[test]
+fn test_span_attribute_values() {
+    // Test that missing, zero and malformed spans count as 1, and numbers may be followed by junk
+    let table = only_table("<table><tr><td colspan=0>A</td><td colspan=x>B</td><td colspan=' 2px'>C</td></tr></table>");
+    assert_eq!(grid(&[&["A", "B", "C", "C"]]), table.rows);
+
+    // Test that a rowspan of 0 reaches the end of its row group but not the next one
+    let table = only_table(
+        "<table>
+            <tbody><tr><td rowspan=0>A</td><td>1</td></tr><tr><td>2</td></tr><tr><td>3</td></tr></tbody>
+            <tbody><tr><td>B</td><td>4</td></tr></tbody>
+        </table>",
+    );
+    assert_eq!(grid(&[&["A", "1"], &["A", "2"], &["A", "3"], &["B", "4"]]), table.rows);
+
+    // Test that a rowspan running past the last row is cut off
+    let table = only_table("<table><tr><td rowspan=5>A</td><td>1</td></tr></table>");
+    assert_eq!(grid(&[&["A", "1"]]), table.rows);
+}
+
+#[test]
+fn test_headers() {
+    // Test that <thead> rows are headers even when made of <td> cells
+    let table = only_table("<table><thead><tr><td>Name</td><td>Age</td></tr></thead><tr><th>Ann</th><td>31</td></tr></table>");
+    assert_eq!(vec!["Name", "Age"], table.headers);
+    assert_eq!(grid(&[&["Ann", "31"]]), table.rows);
+
+    // Test that without a <thead>, only leading rows of <th> cells are headers
+    let table = only_table("<table><tr><th>Name</th><th>Age</th></tr><tr><th>Ann</th><td>31</td></tr><tr><th>x</th></tr></table>");
+    assert_eq!(vec!["Name", "Age"], table.headers);
+    assert_eq!(grid(&[&["Ann", "31"], &["x", ""]]), table.rows);
+
+    // Test that several header rows are merged column by column
+    let table = only_table(
+        "<table>
+            <thead>
+                <tr><th rowspan=2>Name</th><th colspan=2>Score</th><th></th></tr>
+                <tr><th>Math</th><th>Art</th><th>Total</th></tr>
+            </thead>
+            <tbody><tr><td>Ann</td><td>7</td><td>9</td><td>16</td></tr></tbody>
+        </table>",
+    );
+    assert_eq!(vec!["Name", "Score Math", "Score Art", "Total"], table.headers);
+    assert_eq!(grid(&[&["Ann", "7", "9", "16"]]), table.rows);
+}
+
+#[test]
+fn test_nested_tables() {
+    let html = "
+        <table id='outer'>
+            <tr><th>Team</th><th>Members</th></tr>
+            <tr><td>Core</td><td><table id='inner'><tr><td>Ann</td></tr><tr><td>Bob</td></tr></table></td></tr>
+        </table>";
+    let parser = Parser::new(html);
+    let tables = parser.tables();
+
+    // Test that the inner table's rows stay out of the outer table, and that it comes back on its own
+    assert_eq!(2, tables.len());
+    assert_eq!(2, tables[0].rows[0].len());
+    assert_eq!(1, tables[0].rows.len());
+    assert_eq!(grid(&[&["Ann"], &["Bob"]]), tables[1].rows);
+
+    // Test picking tables with a selector, skipping anything that isn't a table
+    assert_eq!(vec![tables[1].clone()], parser.select_tables("#inner").unwrap());
+    assert!(parser.select_tables("td").unwrap().is_empty());
+    assert!(parser.select_tables("table[").is_err());
+
+    // Test building a table from a node, and that other nodes give an empty table
+    let document = Document::parse(html);
+    let outer = document.elements().find(|node| node.name() == Some("table")).unwrap();
+    assert_eq!(tables[0], Table::from(outer));
+    assert_eq!(Table::default(), Table::from(document.root()));
+}
+
+#[test]
+fn test_cell_text() {
+    let html = "<table><tr><td> Fish &amp; <b>chips</b> </td><td>line<br>break</td><script>x</script></tr></table>";
+
+    // Test that cell text is rendered like text_content, and decoding follows the parser
+    assert_eq!(grid(&[&["Fish & chips", "line\nbreak"]]), Parser::new(html).tables()[0].rows);
+    assert_eq!("Fish &amp; chips", Parser::new(html).decode_entities(false).tables()[0].rows[0][0]);
+    assert_eq!(extract_tables(html.to_string()), Parser::new(html).tables());
+}
+
+#[test]
+fn test_csv_and_json() {
+    let table = Table {
+        headers: vec!["Name".to_string(), "Note".to_string()],
+        rows: grid(&[&["Ann", "says \"hi\", then\nleaves"], &["Bob\\", "tab\there \u{1}"]]),
+    };
+
+    // Test CSV quoting of commas, quotes and line breaks
+    assert_eq!("Name,Note\nAnn,\"says \"\"hi\"\", then\nleaves\"\nBob\\,tab\there \u{1}\n", table.to_csv());
+
+    // Test JSON escaping
+    assert_eq!(
+        r#"{"headers":["Name","Note"],"rows":[["Ann","says \"hi\", then\nleaves"],["Bob\\","tab\there \u0001"]]}"#,
+        table.to_json()
+    );
+
+    // Test that a table without headers writes no header line
+    assert_eq!("", Table::default().to_csv());
+    assert_eq!(r#"{"headers":[],"rows":[]}"#, Table::default().to_json());
+}
@@ -0,0 +1,64 @@
+use tagparser::{find_tags, find_tags_with_attr, parse_tags, Parser};
+
+#[test]
+fn test_find_tags_fields() {
+    let html = "<p>Intro</p><div id=\"main\" class=\"box\">Hello <b>world</b></div>".to_string();
+    let tags = find_tags(html, "div".to_string());
+
+    assert_eq!(1, tags.len());
+    let div = &tags[0];
+    assert_eq!("div", div.name);
+    assert_eq!(
+        vec![("id".to_string(), "main".to_string()), ("class".to_string(), "box".to_string())],
+        div.attributes
+    );
+    assert_eq!("Hello <b>world</b>", div.inner_html);
+    assert_eq!("<div id=\"main\" class=\"box\">Hello <b>world</b></div>", div.outer_html);
+    assert_eq!("Hello world", div.text);
+    assert_eq!(12, div.span.start);
+    assert_eq!(63, div.span.end);
+}
+
+#[test]
+fn test_find_tags_matches_parse_tags() {
+    let html = "<a href='/1'>One</a><p><a href='/2'>Two</a></p><a>Three</a>".to_string();
+    let outer: Vec<String> = find_tags(html.clone(), "a".to_string())
+        .into_iter()
+        .map(|tag| tag.outer_html)
+        .collect();
+
+    assert_eq!(parse_tags(html, "a".to_string()), outer);
+}
+
+#[test]
+fn test_tag_attribute_lookup() {
+    let html = "<input type='checkbox' checked name='agree'/>".to_string();
+    let tags = find_tags(html, "input".to_string());
+
+    assert_eq!(Some("checkbox"), tags[0].attribute("type"));
+    assert_eq!(Some(""), tags[0].attribute("checked"));
+    assert!(tags[0].has_attribute("name"));
+    assert!(!tags[0].has_attribute("value"));
+    assert_eq!("", tags[0].inner_html);
+}
+
+#[test]
+fn test_find_tags_with_attr() {
+    let html = "<a href='/a' class='nav'>A</a><a href='/b'>B</a><a class='nav'>C</a>".to_string();
+    let tags = find_tags_with_attr(html, "a".to_string(), "class", Some("nav"));
+
+    let texts: Vec<&str> = tags.iter().map(|tag| tag.text.as_str()).collect();
+    assert_eq!(vec!["A", "C"], texts);
+    assert_eq!(Some("/a"), tags[0].attribute("href"));
+    assert_eq!(None, tags[1].attribute("href"));
+}
+
+#[test]
+fn test_select_tags() {
+    let parser = Parser::new("<ul><li>1</li><li class='x'>2</li></ul>".to_string());
+    let tags = parser.select_tags("li.x").unwrap();
+
+    assert_eq!(1, tags.len());
+    assert_eq!("2", tags[0].text);
+    assert!(parser.select_tags("li[").is_err());
+}
@@ -0,0 +1,65 @@
+use tagparser::{text_content, Document, Parser};
+
+// Helper function to render the first element of a snippet as plain text
+fn render(html: &str) -> String {
+    let document = Document::parse(html);
+    let element = document.elements().next().unwrap();
+    element.text_content()
+}
+
+#[test]
+fn test_strips_nested_markup() {
+    assert_eq!(
+        "This is a paragraph with some text.",
+        render("<p>This is a <strong>paragraph</strong> with some text.</p>")
+    );
+    assert_eq!("Nested deeply here", render("<div><span><em>Nested</em> <b>deeply</b></span> here</div>"));
+}
+
+#[test]
+fn test_collapses_whitespace() {
+    assert_eq!("one two three", render("<p>\n   one\t two  \n\n three   </p>"));
+    assert_eq!("a b", render("<p><b>a</b> <i>b</i></p>"));
+    assert_eq!("ab", render("<p><b>a</b><i>b</i></p>"));
+}
+
+#[test]
+fn test_drops_script_and_style() {
+    let html = "<div>Before<script>var x = '<b>no</b>';</script><style>p { color: red }</style> after</div>";
+    assert_eq!("Before after", render(html));
+}
+
+#[test]
+fn test_decodes_entities() {
+    assert_eq!("Fish & Chips © 2024", render("<p>Fish &amp; Chips &copy; 2024</p>"));
+}
+
+#[test]
+fn test_block_and_inline_elements() {
+    let html = "<article><h1>Title</h1>Intro <a href='#'>link</a>.<p>First.</p><p>Second.</p><ul><li>One</li><li>Two</li></ul></article>";
+    assert_eq!("Title\nIntro link.\n\nFirst.\n\nSecond.\n\nOne\nTwo", render(html));
+}
+
+#[test]
+fn test_line_breaks_and_preformatted_text() {
+    assert_eq!("line one\nline two", render("<p>line one<br/>line two</p>"));
+    assert_eq!("a\n\nb", render("<p>a<br/><br/>b</p>"));
+    assert_eq!("keep   this\n  indented", render("<pre>keep   this\n  indented</pre>"));
+}
+
+#[test]
+fn test_table_cells() {
+    let html = "<table><tr><th>Name</th><th>Price</th></tr><tr><td>Apple</td><td> 1.20 </td></tr></table>";
+    assert_eq!("Name\tPrice\nApple\t1.20", render(html));
+}
+
+#[test]
+fn test_text_content_function() {
+    let html = "<p>One &amp; <b>two</b></p><p>Three</p>".to_string();
+    
+    assert_eq!(vec!["One & two", "Three"], text_content(html.clone(), "p".to_string()));
+    
+    // Raw mode keeps character references as written
+    let raw = Parser::new(html).decode_entities(false);
+    assert_eq!(vec!["One &amp; two", "Three"], raw.text_content("p"));
+}
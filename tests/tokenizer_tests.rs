@@ -0,0 +1,148 @@
+use tagparser::parser::tokenizer::{Span, Token, Tokenizer};
+
+// Helper function to render tokens in a compact, comparable form
+fn describe(html: &str) -> Vec<String> {
+    Tokenizer::new(html)
+        .map(|token| match token {
+            Token::StartTag(tag) => {
+                let attrs = tag.attributes
+                    .iter()
+                    .map(|attr| format!(" {}={:?}", attr.name, attr.value))
+                    .collect::<String>();
+                let slash = if tag.self_closing { "/" } else { "" };
+                format!("<{}{}{}>", tag.name, attrs, slash)
+            }
+            Token::EndTag(tag) => format!("</{}>", tag.name),
+            Token::Text { text, .. } => format!("text:{:?}", text),
+            Token::Comment { text, .. } => format!("comment:{:?}", text),
+            Token::Doctype { text, .. } => format!("doctype:{:?}", text),
+        })
+        .collect()
+}
+
+#[test]
+fn test_tokenize_basic_document() {
+    let html = "<!DOCTYPE html><html><body><p class='intro'>Hello</p><!-- done --></body></html>";
+    
+    assert_eq!(
+        vec![
+            "doctype:\"html\"",
+            "<html>",
+            "<body>",
+            "<p class=\"intro\">",
+            "text:\"Hello\"",
+            "</p>",
+            "comment:\" done \"",
+            "</body>",
+            "</html>",
+        ],
+        describe(html)
+    );
+}
+
+#[test]
+fn test_tokenize_attribute_forms() {
+    let html = r#"<input type="text" name='user' size=20 disabled data-x = "1">"#;
+    
+    assert_eq!(
+        vec![r#"<input type="text" name="user" size="20" disabled="" data-x="1">"#],
+        describe(html)
+    );
+}
+
+#[test]
+fn test_tokenize_duplicate_attributes() {
+    // Only the first occurrence of a repeated attribute name is kept
+    let html = "<a href='/one' class=x HREF='/two' href=/three>";
+    
+    assert_eq!(vec![r#"<a href="/one" class="x">"#], describe(html));
+}
+
+#[test]
+fn test_tokenize_quoted_greater_than() {
+    let html = r#"<a title="1 > 0" data-q='"'>x</a>"#;
+    
+    assert_eq!(
+        vec![r#"<a title="1 > 0" data-q="\"">"#, "text:\"x\"", "</a>"],
+        describe(html)
+    );
+}
+
+#[test]
+fn test_tokenize_self_closing() {
+    assert_eq!(vec!["<br/>", "<img src=\"a.png\"/>"], describe("<br/><img src=\"a.png\" />"));
+}
+
+#[test]
+fn test_tokenize_stray_less_than() {
+    // A '<' that doesn't start a tag is text
+    assert_eq!(
+        vec!["text:\"1 < 2 and 3 <= 4\""],
+        describe("1 < 2 and 3 <= 4")
+    );
+}
+
+#[test]
+fn test_tokenize_bogus_comments() {
+    assert_eq!(
+        vec!["comment:\"?xml version=\\\"1.0\\\"?\"", "comment:\"[if IE]\"", "comment:\"\""],
+        describe("<?xml version=\"1.0\"?><![if IE]><!-->")
+    );
+}
+
+#[test]
+fn test_tokenize_unterminated_input() {
+    // A tag cut off by the end of the input is dropped
+    assert_eq!(vec!["text:\"before \""], describe("before <a href='x"));
+    
+    // An unterminated comment runs to the end of the input
+    assert_eq!(vec!["comment:\" open\""], describe("<!-- open"));
+}
+
+#[test]
+fn test_token_spans() {
+    let html = "<p id=x>Hi</p>";
+    let spans = Tokenizer::new(html).map(|token| token.span()).collect::<Vec<Span>>();
+    
+    assert_eq!(
+        vec![Span::new(0, 8), Span::new(8, 10), Span::new(10, 14)],
+        spans
+    );
+    assert_eq!("<p id=x>", &html[spans[0].start..spans[0].end]);
+}
+
+#[test]
+fn test_tokenize_raw_text_elements() {
+    // Script and style content is a single text token, whatever it contains
+    assert_eq!(
+        vec![
+            "<script>",
+            "text:\"var a = \\\"<a href='x'>\\\"; if (1 < 2) {} // </scripts>\"",
+            "</script>",
+            "<style>",
+            "text:\"p::before { content: '</p>' }\"",
+            "</style>",
+        ],
+        describe("<script>var a = \"<a href='x'>\"; if (1 < 2) {} // </scripts></script><style>p::before { content: '</p>' }</style>")
+    );
+    
+    // The end tag is matched ignoring case, and an unclosed element runs to the end
+    assert_eq!(vec!["<script>", "text:\"<b>\"", "</SCRIPT>"], describe("<script><b></SCRIPT >"));
+    assert_eq!(vec!["<style>", "text:\"<p>open\""], describe("<style><p>open"));
+}
+
+#[test]
+fn test_tokenize_escapable_raw_text_elements() {
+    assert_eq!(
+        vec!["<title>", "text:\"<b>Home</b> &amp; away\"", "</title>", "<textarea>", "text:\"<p>draft</p>\"", "</textarea>"],
+        describe("<title><b>Home</b> &amp; away</title><textarea><p>draft</p></textarea>")
+    );
+}
+
+#[test]
+fn test_tokenize_cdata_sections() {
+    assert_eq!(
+        vec!["comment:\" <p>not a tag</p> \"", "<p>", "comment:\"open <a>\""],
+        describe("<![CDATA[ <p>not a tag</p> ]]><p><![CDATA[open <a>")
+    );
+}
@@ -0,0 +1,131 @@
+use tagparser::{xpath, Document, Parser, Value, XPath, XPathError};
+
+// Helper function to evaluate an expression and collect the string-value of each node
+fn strings(html: &str, expression: &str) -> Vec<String> {
+    let document = Document::parse(html);
+    let result = document.xpath(expression).unwrap();
+    result
+        .as_node_set()
+        .unwrap()
+        .iter()
+        .map(|node| node.string_value())
+        .collect()
+}
+
+// Helper function to evaluate an expression that produces a single value
+fn scalar(html: &str, expression: &str) -> String {
+    Document::parse(html).xpath(expression).unwrap().to_string_value()
+}
+
+const TABLE: &str = r#"
+    <table id="prices">
+        <tr><th>Item</th><th>Price</th></tr>
+        <tr><td>Apple</td><td>1.20</td></tr>
+        <tr><td>Pear</td><td>0.80</td></tr>
+        <tr><td>Plum</td><td>2.00</td></tr>
+    </table>
+    <table id="other"><tr><td>X</td><td>9</td></tr></table>
+"#;
+
+#[test]
+fn test_location_paths() {
+    assert_eq!(vec!["1.20", "0.80", "2.00"], strings(TABLE, "//table[@id='prices']//tr[position()>1]/td[2]/text()"));
+    assert_eq!(vec!["Item", "Price"], strings(TABLE, "/table/tr/th"));
+    assert_eq!(vec!["prices", "other"], strings(TABLE, "//table/@id"));
+    assert_eq!(vec!["Apple", "Pear", "Plum", "X"], strings(TABLE, "//td[1]"));
+    assert_eq!(vec!["Apple"], strings(TABLE, "(//td)[1]"));
+    assert_eq!(vec!["9"], strings(TABLE, "(//td)[last()]"));
+    assert_eq!(vec!["Plum", "X"], strings(TABLE, "//tr[last()]/td[1]"));
+}
+
+#[test]
+fn test_axes() {
+    let html = "<div><p id='a'>A</p><p id='b'>B<b>!</b></p><p id='c'>C</p></div><span>D</span>";
+
+    assert_eq!(vec!["C"], strings(html, "//p[@id='b']/following-sibling::p"));
+    assert_eq!(vec!["A"], strings(html, "//p[@id='b']/preceding-sibling::*"));
+    assert_eq!(vec!["C", "D"], strings(html, "//p[@id='b']/following::*"));
+    assert_eq!(vec!["A"], strings(html, "//b/preceding::p"));
+    assert_eq!(vec!["AB!C"], strings(html, "//b/ancestor::div"));
+    assert_eq!(vec!["B!"], strings(html, "//b/.."));
+    assert_eq!(vec!["b"], strings(html, "//b/parent::*/@id"));
+    assert_eq!(vec!["B!", "!"], strings(html, "//p[@id='b']/descendant-or-self::*"));
+    assert_eq!(vec!["!"], strings(html, "//b/self::b"));
+    assert_eq!(vec!["A", "B", "!", "C", "D"], strings(html, "//text()"));
+}
+
+#[test]
+fn test_reverse_axis_positions() {
+    let html = "<ul><li>1</li><li>2</li><li>3</li></ul>";
+
+    // Positions on reverse axes count backwards from the context node
+    assert_eq!(vec!["2"], strings(html, "//li[3]/preceding-sibling::li[1]"));
+    assert_eq!(vec!["1"], strings(html, "//li[3]/preceding-sibling::li[last()]"));
+    assert_eq!(vec!["1", "2"], strings(html, "//li[3]/preceding-sibling::li"));
+}
+
+#[test]
+fn test_string_functions() {
+    let html = "<p class='note important'>  Hello,\n   world  </p><p class='plain'>Bye</p>";
+
+    assert_eq!(vec!["Bye"], strings(html, "//p[starts-with(@class, 'pl')]"));
+    assert_eq!(vec!["  Hello,\n   world  "], strings(html, "//p[contains(@class, 'important')]"));
+    assert_eq!("Hello, world", scalar(html, "normalize-space(//p)"));
+    assert_eq!("Hello", scalar(html, "substring-before(normalize-space(//p), ',')"));
+    assert_eq!(" world", scalar(html, "substring-after(normalize-space(//p), ',')"));
+    assert_eq!("ell", scalar(html, "substring('Hello', 2, 3)"));
+    assert_eq!("ello", scalar(html, "substring('Hello', 1.5)"));
+    assert_eq!("BYE", scalar(html, "translate(//p[2], 'bye', 'BYE')"));
+    assert_eq!("a-b-c", scalar(html, "concat('a', '-', 'b', '-', 'c')"));
+    assert_eq!("3", scalar(html, "string-length(//p[2])"));
+    assert_eq!("p", scalar(html, "name(//p)"));
+}
+
+#[test]
+fn test_numbers_and_booleans() {
+    assert_eq!(Value::Number(4.0), Document::parse(TABLE).xpath("sum(//table[@id='prices']//td[2])").unwrap());
+    assert_eq!(Value::Boolean(true), Document::parse(TABLE).xpath("count(//tr) = 5").unwrap());
+    assert_eq!(vec!["Plum", "X"], strings(TABLE, "//tr[td[2] > 1.5]/td[1]"));
+    assert_eq!(vec!["Pear"], strings(TABLE, "//tr[td[2] < 1 and td[1] != 'X']/td[1]"));
+    assert_eq!("2.5", scalar("", "5 div 2"));
+    assert_eq!("1", scalar("", "7 mod 3"));
+    assert_eq!("-2", scalar("", "round(-2.5)"));
+    assert_eq!("NaN", scalar("", "number('abc')"));
+    assert_eq!("Infinity", scalar("", "1 div 0"));
+    assert_eq!("1000000000000000000000000000000000000000000000", scalar("", "1000000000000000000000000000000000000000000000"));
+    assert_eq!("0", scalar("", "-0"));
+    assert_eq!("true", scalar("", "not(false()) or 1 = 2"));
+}
+
+#[test]
+fn test_names_ignore_case() {
+    assert_eq!(vec!["x"], strings("<DIV ID='a'>x</DIV>", "//div[@id='a']"));
+
+    // Test names, variables and numbers next to characters longer than one byte
+    assert_eq!(vec!["2"], strings("<p>1</p><café x='y'>2</café>", "//café[@x]"));
+    assert!(strings("<p>1</p>", "//é").is_empty());
+    assert!(XPath::parse("$prix_é").is_ok());
+    assert!(XPath::parse("1.5é").is_err());
+}
+
+#[test]
+fn test_invalid_expressions() {
+    assert!(matches!(XPath::parse("//div["), Err(XPathError::Syntax { .. })));
+    assert!(matches!(XPath::parse("//div/"), Err(XPathError::Syntax { .. })));
+    assert!(matches!(XPath::parse("unknown()"), Err(XPathError::Syntax { .. })));
+    assert!(matches!(XPath::parse("bogus::div"), Err(XPathError::Syntax { .. })));
+    assert!(matches!(XPath::parse("'unterminated"), Err(XPathError::Syntax { position: 0, .. })));
+
+    // Using a number as a node-set is only detected when evaluating
+    let document = Document::parse("<p>x</p>");
+    assert!(matches!(document.xpath("count(1)"), Err(XPathError::Evaluation(_))));
+}
+
+#[test]
+fn test_parser_xpath() {
+    let html = "<a href='/one'>One</a><a href='/two'>Two</a>".to_string();
+
+    assert_eq!(vec!["/one", "/two"], xpath(html.clone(), "//a/@href").unwrap());
+    assert_eq!(vec!["<a href='/two'>Two</a>"], xpath(html.clone(), "//a[. = 'Two']").unwrap());
+    assert_eq!(vec!["2"], Parser::new(html).xpath("count(//a)").unwrap());
+}